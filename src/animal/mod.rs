@@ -1,4 +1,3 @@
 pub mod controller;
-pub mod routes;
 pub mod service;
-pub mod models;
\ No newline at end of file
+pub use rocket_api_models::animal::models;
\ No newline at end of file
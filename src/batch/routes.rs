@@ -0,0 +1,38 @@
+use rocket::{Build, Rocket, State};
+use rocket::http::Status;
+use rocket::http::uri::Origin;
+use rocket::response::status::Custom;
+
+use db::{Db, Tx};
+use generic_crud_trait::{BatchOperation, BatchRegistry, BatchResult, IdempotencyKey, Idempotent, IdempotentRequest, Negotiated};
+
+use crate::{animal, car, caretaker, owner};
+
+#[post("/_batch", data = "<operations>")]
+async fn batch(operations: Negotiated<Vec<BatchOperation>>, registry: &State<BatchRegistry>, tx: Tx<'_, Db>, uri: &Origin<'_>, idempotency_key: Option<IdempotencyKey>) -> Idempotent<Custom<Negotiated<Vec<BatchResult>>>> {
+    let operations = operations.into_inner();
+    let request = match IdempotentRequest::begin(idempotency_key, uri, &operations, &*tx).await {
+        Ok(request) => request,
+        Err(replay) => return replay,
+    };
+    let (status, results) = registry.execute(operations, &tx).await;
+    if status == Status::Ok {
+        if let Err(err) = request.complete(status, None, &results, &*tx).await {
+            return Idempotent::Failed(err);
+        }
+    }
+    Idempotent::Response(Custom(status, Negotiated(results)))
+}
+
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    let registry = BatchRegistry::new()
+        .resource::<car::service::CarService, _, _, _>("cars")
+        .resource::<animal::service::AnimalService, _, _, _>("animals")
+        .resource::<owner::service::OwnerService, _, _, _>("owners")
+        .resource::<caretaker::service::CaretakerService, _, _, _>("caretakers");
+
+    rocket
+        .manage(registry)
+        .mount("/api", routes![batch])
+}
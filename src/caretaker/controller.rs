@@ -0,0 +1,5 @@
+use generic_crud_proc_macro::CRUDControllerImpl;
+
+#[derive(CRUDControllerImpl)]
+#[module = "caretaker"]
+pub struct CaretakerController;
\ No newline at end of file
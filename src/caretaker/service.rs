@@ -0,0 +1,5 @@
+use generic_crud_proc_macro::CRUDServiceImpl;
+
+#[derive(CRUDServiceImpl)]
+#[module = "caretaker"]
+pub struct CaretakerService;
\ No newline at end of file
@@ -0,0 +1,317 @@
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use generic_crud_dto::{ByIds, IdsRequest, Included};
+use generic_crud_proc_macro::CRUDClientImpl;
+
+// The models of the API, shared with the server and resolved by the CRUDClientImpl macro
+pub use rocket_api_models::{animal, car, caretaker, entity, owner};
+
+/// Method of the requests sent to the API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Patch,
+    Delete,
+}
+
+/// Request to the API, `uri` being relative to the server, e.g. `/api/cars?limit=10`, and `body` being JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiRequest {
+    pub method: Method,
+    pub uri: String,
+    pub body: Option<Vec<u8>>,
+}
+
+impl ApiRequest {
+    pub fn new(method: Method, uri: String) -> Self {
+        ApiRequest { method, uri, body: None }
+    }
+
+    pub fn json<Body: Serialize + ?Sized>(mut self, body: &Body) -> Result<Self, ClientError> {
+        self.body = Some(serde_json::to_vec(body)?);
+        Ok(self)
+    }
+}
+
+/// Status and body of a response of the API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl ApiResponse {
+    pub fn json<Body: DeserializeOwned>(&self) -> Result<Body, ClientError> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+/// Sends the requests of the clients, over HTTP with `HttpTransport`, or in process, e.g. to a Rocket local client in tests.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse, ClientError>;
+}
+
+/// Transport of an API served at `base_url`, e.g. `http://localhost:8000`.
+#[derive(Clone, Debug)]
+pub struct HttpTransport {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl HttpTransport {
+    /// A transport sending its requests with a configured `reqwest::Client` (timeouts, default headers...).
+    pub fn new(http: reqwest::Client, base_url: &str) -> Self {
+        HttpTransport { http, base_url: base_url.trim_end_matches('/').to_owned() }
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse, ClientError> {
+        let method = match request.method {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+            Method::Patch => reqwest::Method::PATCH,
+            Method::Delete => reqwest::Method::DELETE,
+        };
+        let mut builder = self.http.request(method, format!("{}{}", self.base_url, request.uri));
+        if let Some(body) = request.body {
+            builder = builder.header(reqwest::header::CONTENT_TYPE, "application/json").body(body);
+        }
+        let response = builder.send().await?;
+        let status = response.status().as_u16();
+        Ok(ApiResponse { status, body: response.bytes().await?.to_vec() })
+    }
+}
+
+/// Connection to the API, shared by the clients of every resource.
+#[derive(Clone)]
+pub struct ApiClient {
+    transport: Arc<dyn Transport>,
+}
+
+impl ApiClient {
+    /// A client of the API served at `base_url`, e.g. `http://localhost:8000`.
+    pub fn new(base_url: &str) -> Self {
+        ApiClient::with_client(reqwest::Client::new(), base_url)
+    }
+
+    /// A client sending its requests with a configured `reqwest::Client` (timeouts, default headers...).
+    pub fn with_client(http: reqwest::Client, base_url: &str) -> Self {
+        ApiClient::with_transport(HttpTransport::new(http, base_url))
+    }
+
+    pub fn with_transport<T: Transport + 'static>(transport: T) -> Self {
+        ApiClient { transport: Arc::new(transport) }
+    }
+
+    /// Send the request, a response without a 2xx status being a `ClientError::Status`.
+    pub async fn send(&self, request: ApiRequest) -> Result<ApiResponse, ClientError> {
+        let response = self.transport.send(request).await?;
+        if (200..300).contains(&response.status) {
+            Ok(response)
+        } else {
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+            Err(ClientError::Status { status: response.status, body })
+        }
+    }
+
+    /// Same as `send`, a 404 response being `None`.
+    async fn send_found(&self, request: ApiRequest) -> Result<Option<ApiResponse>, ClientError> {
+        match self.send(request).await {
+            Ok(response) => Ok(Some(response)),
+            Err(ClientError::Status { status: 404, .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiClient").finish_non_exhaustive()
+    }
+}
+
+/// Error of a request: not sent, answered with an unexpected status, or with an unexpected body.
+#[derive(Debug)]
+pub enum ClientError {
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    Status { status: u16, body: String },
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Transport(err) => write!(f, "Request failed: {}", err),
+            ClientError::Status { status, body } => write!(f, "Unexpected status {}: {}", status, body),
+            ClientError::Json(err) => write!(f, "Invalid JSON body: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Transport(Box::new(err))
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> Self {
+        ClientError::Json(err)
+    }
+}
+
+/// Parameters of the list requests: the filters, the relations to include and the page to fetch.
+#[derive(Clone, Debug, Default)]
+pub struct ListQuery {
+    filters: Vec<(String, String)>,
+    include: Vec<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl ListQuery {
+    /// Keep the objects whose `field` equals `value`, e.g. `filter("fuel_type", "electric")`,
+    /// `"null"` keeping the objects without a value.
+    pub fn filter<Value: fmt::Display>(mut self, field: &str, value: Value) -> Self {
+        self.filters.push((field.to_owned(), value.to_string()));
+        self
+    }
+
+    /// Embed a #[belongsTo] relation, e.g. `owner` for the cars.
+    pub fn include(mut self, relation: &str) -> Self {
+        self.include.push(relation.to_owned());
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// The `page`-th page of `per_page` objects, starting at 0.
+    pub fn page(self, page: u64, per_page: u64) -> Self {
+        self.limit(per_page).offset(page * per_page)
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        let mut params: Vec<(String, String)> = self.filters.iter()
+            .map(|(field, value)| (format!("filter[{}]", field), value.clone()))
+            .collect();
+        params.extend(include_param(&self.include));
+        if let Some(limit) = self.limit {
+            params.push(("limit".to_owned(), limit.to_string()));
+        }
+        if let Some(offset) = self.offset {
+            params.push(("offset".to_owned(), offset.to_string()));
+        }
+        params
+    }
+}
+
+fn include_param<S: AsRef<str>>(include: &[S]) -> Vec<(String, String)> {
+    if include.is_empty() {
+        return vec![];
+    }
+    let include: Vec<&str> = include.iter().map(AsRef::as_ref).collect();
+    vec![("include".to_owned(), include.join(","))]
+}
+
+/// `path` followed by the url-encoded query string of `params`.
+fn uri(path: String, params: &[(String, String)]) -> String {
+    if params.is_empty() {
+        return path;
+    }
+    let query = form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+    format!("{}?{}", path, query)
+}
+
+/// Typed client of the CRUD routes of a resource, implemented by the CRUDClientImpl macro.
+#[async_trait]
+pub trait CRUDClientTrait<Model, CreateModel, PartialModel>
+where
+    Model: DeserializeOwned + Send + 'static,
+    CreateModel: Serialize + Sync,
+    PartialModel: Serialize + Sync,
+{
+    /// Mount point of the routes of the resource, e.g. `/api/cars`.
+    const PATH: &'static str;
+
+    fn api(&self) -> &ApiClient;
+
+    async fn list(&self, query: &ListQuery) -> Result<Vec<Included<Model>>, ClientError> {
+        let request = ApiRequest::new(Method::Get, uri(Self::PATH.to_owned(), &query.params()));
+        self.api().send(request).await?.json()
+    }
+
+    /// The objects found, in the requested order, and the missing ids.
+    async fn get_many(&self, ids: &[i32], include: &[&str]) -> Result<ByIds<Included<Model>>, ClientError> {
+        let request = ApiRequest::new(Method::Post, uri(format!("{}/_get", Self::PATH), &include_param(include)))
+            .json(&IdsRequest { ids: ids.to_vec() })?;
+        self.api().send(request).await?.json()
+    }
+
+    async fn get(&self, obj_id: i32, include: &[&str]) -> Result<Option<Included<Model>>, ClientError> {
+        let request = ApiRequest::new(Method::Get, uri(format!("{}/{}", Self::PATH, obj_id), &include_param(include)));
+        self.api().send_found(request).await?.map(|response| response.json()).transpose()
+    }
+
+    async fn create(&self, obj: &CreateModel) -> Result<Model, ClientError> {
+        let request = ApiRequest::new(Method::Post, Self::PATH.to_owned()).json(obj)?;
+        self.api().send(request).await?.json()
+    }
+
+    async fn patch(&self, obj_id: i32, obj: &PartialModel) -> Result<Option<Model>, ClientError> {
+        let request = ApiRequest::new(Method::Patch, format!("{}/{}", Self::PATH, obj_id)).json(obj)?;
+        self.api().send_found(request).await?.map(|response| response.json()).transpose()
+    }
+
+    /// Whether the object existed, a 409 listing the children preventing the delete being an error.
+    async fn delete(&self, obj_id: i32) -> Result<bool, ClientError> {
+        let request = ApiRequest::new(Method::Delete, format!("{}/{}", Self::PATH, obj_id));
+        Ok(self.api().send_found(request).await?.is_some())
+    }
+}
+
+#[derive(Clone, Debug, CRUDClientImpl)]
+#[module = "car"]
+#[mount = "/api/cars"]
+pub struct CarClient {
+    api: ApiClient,
+}
+
+#[derive(Clone, Debug, CRUDClientImpl)]
+#[module = "animal"]
+#[mount = "/api/animals"]
+pub struct AnimalClient {
+    api: ApiClient,
+}
+
+#[derive(Clone, Debug, CRUDClientImpl)]
+#[module = "owner"]
+#[mount = "/api/owners"]
+pub struct OwnerClient {
+    api: ApiClient,
+}
+
+#[derive(Clone, Debug, CRUDClientImpl)]
+#[module = "caretaker"]
+#[mount = "/api/caretakers"]
+pub struct CaretakerClient {
+    api: ApiClient,
+}
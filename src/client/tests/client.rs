@@ -0,0 +1,237 @@
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use rocket::http::ContentType;
+use rocket::local::asynchronous::Client;
+
+use rocket_api_client::animal::models::PostAnimal;
+use rocket_api_client::car::models::{PartialCar, PostCar};
+use rocket_api_client::entity::sea_orm_active_enums::FuelType;
+use rocket_api_client::owner::models::PostOwner;
+use rocket_api_client::{AnimalClient, ApiClient, ApiRequest, ApiResponse, CRUDClientTrait, CarClient, ClientError, ListQuery, Method, OwnerClient, Transport};
+
+/// Sends the requests to the server through Rocket's local client, without listening on a port.
+struct LocalTransport(Client);
+
+#[async_trait]
+impl Transport for LocalTransport {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse, ClientError> {
+        let method = match request.method {
+            Method::Get => rocket::http::Method::Get,
+            Method::Post => rocket::http::Method::Post,
+            Method::Patch => rocket::http::Method::Patch,
+            Method::Delete => rocket::http::Method::Delete,
+        };
+        let mut local = self.0.req(method, request.uri);
+        if let Some(body) = request.body {
+            local = local.header(ContentType::JSON).body(body);
+        }
+        let response = local.dispatch().await;
+        let status = response.status().code;
+        Ok(ApiResponse { status, body: response.into_bytes().await.unwrap_or_default() })
+    }
+}
+
+/// Records the requests, answering them all with the same response.
+#[derive(Clone)]
+struct RecordingTransport {
+    requests: Arc<Mutex<Vec<ApiRequest>>>,
+    response: ApiResponse,
+}
+
+impl RecordingTransport {
+    fn new(status: u16, body: &str) -> Self {
+        RecordingTransport { requests: Arc::default(), response: ApiResponse { status, body: body.as_bytes().to_vec() } }
+    }
+
+    fn requests(&self) -> Vec<ApiRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse, ClientError> {
+        self.requests.lock().unwrap().push(request);
+        Ok(self.response.clone())
+    }
+}
+
+/// A client of the server, connected to the database of `DATABASE_URL`.
+///
+/// The tests using it are `#[ignore]`d, and run with `cargo test -- --include-ignored`.
+async fn api() -> ApiClient {
+    // The migrations run when the rocket ignites, one at a time
+    static IGNITE: rocket::tokio::sync::Mutex<()> = rocket::tokio::sync::Mutex::const_new(());
+
+    if std::env::var("DATABASE_URL").is_err() {
+        panic!("DATABASE_URL must be set to run the client tests");
+    }
+    let _ignite = IGNITE.lock().await;
+    let client = Client::tracked(rocket_api::rocket()).await.unwrap();
+    ApiClient::with_transport(LocalTransport(client))
+}
+
+/// Suffix keeping the natural keys of the objects created by concurrent runs apart.
+fn unique() -> String {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos().to_string()
+}
+
+fn post_car(model: &str, owner_id: Option<i32>) -> PostCar {
+    PostCar {
+        brand: "Client".to_owned(),
+        model: model.to_owned(),
+        year: 2020,
+        fuel_type: None,
+        purchased_at: None,
+        owner_id,
+        price: None,
+        tracker_id: None,
+        specs: None,
+    }
+}
+
+#[rocket::async_test]
+async fn list_query_is_encoded() {
+    let transport = RecordingTransport::new(200, "[]");
+    let cars = CarClient::new(ApiClient::with_transport(transport.clone()));
+
+    let query = ListQuery::default().filter("brand", "Aston Martin").filter("year", 2020).include("owner").page(2, 10);
+    assert!(cars.list(&query).await.unwrap().is_empty());
+    let uri = "/api/cars?filter%5Bbrand%5D=Aston+Martin&filter%5Byear%5D=2020&include=owner&limit=10&offset=20";
+    assert_eq!(transport.requests(), vec![ApiRequest::new(Method::Get, uri.to_owned())]);
+}
+
+#[rocket::async_test]
+async fn missing_objects_are_not_errors() {
+    let transport = RecordingTransport::new(404, "Not Found");
+    let cars = CarClient::new(ApiClient::with_transport(transport.clone()));
+
+    assert!(cars.get(7, &["owner"]).await.unwrap().is_none());
+    let patch = PartialCar {
+        brand: None,
+        model: None,
+        year: Some(2021),
+        fuel_type: None,
+        purchased_at: None,
+        owner_id: Some(None),
+        price: None,
+        tracker_id: None,
+        specs: None,
+    };
+    assert!(cars.patch(7, &patch).await.unwrap().is_none());
+    assert!(!cars.delete(7).await.unwrap());
+    // Unless a list is expected
+    match cars.list(&ListQuery::default()).await {
+        Err(ClientError::Status { status, body }) => assert_eq!((status, body.as_str()), (404, "Not Found")),
+        other => panic!("Expected a 404, got {:?}", other),
+    }
+
+    let requests = transport.requests();
+    assert_eq!(requests[0].uri, "/api/cars/7?include=owner");
+    assert_eq!((requests[1].method, requests[1].uri.as_str()), (Method::Patch, "/api/cars/7"));
+    // Only the fields to update are sent, `null` clearing the owner
+    assert_eq!(requests[1].body.as_deref(), Some(&br#"{"year":2021,"owner_id":null}"#[..]));
+    assert_eq!(requests[2].method, Method::Delete);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn create_read_patch_delete() {
+    let cars = CarClient::new(api().await);
+
+    let car = cars.create(&post_car(&unique(), None)).await.unwrap();
+    assert_eq!(car.fuel_type, FuelType::Petrol);
+
+    let found = cars.get(car.id, &[]).await.unwrap().unwrap();
+    assert_eq!(found.obj, car);
+
+    let patch = PartialCar {
+        brand: None,
+        model: None,
+        year: Some(2021),
+        fuel_type: Some(FuelType::Electric),
+        purchased_at: None,
+        owner_id: None,
+        price: None,
+        tracker_id: None,
+        specs: None,
+    };
+    let patched = cars.patch(car.id, &patch).await.unwrap().unwrap();
+    assert_eq!((patched.year, patched.fuel_type, patched.model), (2021, FuelType::Electric, car.model));
+
+    assert!(cars.delete(car.id).await.unwrap());
+    assert!(cars.get(car.id, &[]).await.unwrap().is_none());
+    assert!(cars.patch(car.id, &patch).await.unwrap().is_none());
+    assert!(!cars.delete(car.id).await.unwrap());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn include_and_get_many() {
+    let api = api().await;
+    let owners = OwnerClient::new(api.clone());
+    let cars = CarClient::new(api);
+
+    let owner = owners.create(&PostOwner { name: unique() }).await.unwrap();
+    let car = cars.create(&post_car(&unique(), Some(owner.id))).await.unwrap();
+
+    let found = cars.get(car.id, &["owner"]).await.unwrap().unwrap();
+    assert_eq!(found.relations["owner"]["name"], owner.name.as_str());
+
+    let by_ids = cars.get_many(&[car.id, -1], &[]).await.unwrap();
+    assert_eq!(by_ids.data.len(), 1);
+    assert_eq!(by_ids.data[0].obj, car);
+    assert_eq!(by_ids.missing, vec![-1]);
+
+    // The owner can't be deleted while it has cars
+    match owners.delete(owner.id).await {
+        Err(ClientError::Status { status, .. }) => assert_eq!(status, 409),
+        other => panic!("Expected a 409, got {:?}", other),
+    }
+    assert!(cars.delete(car.id).await.unwrap());
+    assert!(owners.delete(owner.id).await.unwrap());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn list_pages() {
+    let animals = AnimalClient::new(api().await);
+
+    for age in 0..3 {
+        let animal = PostAnimal { race: "Cat".to_owned(), name: unique(), age };
+        animals.create(&animal).await.unwrap();
+    }
+
+    let first = animals.list(&ListQuery::default().page(0, 2)).await.unwrap();
+    let second = animals.list(&ListQuery::default().page(1, 2)).await.unwrap();
+    assert_eq!(first.len(), 2);
+    assert!(!second.is_empty());
+    // Pages are ordered by id
+    assert!(first[0].obj.id < first[1].obj.id);
+    assert!(first[1].obj.id < second[0].obj.id);
+
+    let skipped = animals.list(&ListQuery::default().offset(1).limit(1)).await.unwrap();
+    assert_eq!(skipped[0].obj.id, first[1].obj.id);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn list_filters() {
+    let cars = CarClient::new(api().await);
+    let model = unique();
+
+    let petrol = cars.create(&post_car(&model, None)).await.unwrap();
+    let electric = cars.create(&PostCar { fuel_type: Some(FuelType::Electric), year: 2021, ..post_car(&model, None) }).await.unwrap();
+
+    let found = cars.list(&ListQuery::default().filter("model", &model)).await.unwrap();
+    assert_eq!(found.into_iter().map(|car| car.obj).collect::<Vec<_>>(), vec![petrol, electric.clone()]);
+    let found = cars.list(&ListQuery::default().filter("model", &model).filter("fuel_type", "electric")).await.unwrap();
+    assert_eq!(found.into_iter().map(|car| car.obj).collect::<Vec<_>>(), vec![electric]);
+
+    match cars.list(&ListQuery::default().filter("color", "red")).await {
+        Err(ClientError::Status { status, body }) => assert_eq!((status, body.as_str()), (400, "Unknown filter field `color`")),
+        other => panic!("Expected a 400, got {:?}", other),
+    }
+}
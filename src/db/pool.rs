@@ -1,8 +1,15 @@
+#[macro_use]
+extern crate rocket;
+
 use async_trait::async_trait;
 use sea_orm::ConnectOptions;
 use sea_orm_rocket::{rocket::figment::Figment, Config, Database};
 use std::time::Duration;
 
+mod tx;
+
+pub use tx::{Tx, TxFairing};
+
 #[derive(Database, Debug)]
 #[database("sea_orm")]
 pub struct Db(SeaOrmPool);
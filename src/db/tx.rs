@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Build, Response, Rocket};
+use sea_orm::{DatabaseConnection, DatabaseTransaction, TransactionTrait};
+use sea_orm_rocket::{Connection, Database, Pool};
+
+/// Request guard running the request in a database transaction.
+///
+/// The transaction is started by the first `Tx` guard of the request and shared by the others.
+/// It is committed by the [`TxFairing`] when the response status is 2xx, and rolled back otherwise.
+pub struct Tx<'r, D: Database> {
+    txn: Arc<DatabaseTransaction>,
+    _db: PhantomData<&'r D>,
+}
+
+impl<'r, D: Database> Tx<'r, D> {
+    /// Fairing committing or rolling back the transactions, to attach along with `D::init()`.
+    pub fn fairing() -> TxFairing<D> {
+        TxFairing(PhantomData)
+    }
+}
+
+impl<'r, D: Database> Deref for Tx<'r, D> {
+    type Target = DatabaseTransaction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.txn
+    }
+}
+
+/// Transaction of the current request, taken by the fairing once the response is ready.
+struct TxSlot<D> {
+    txn: Mutex<Option<Arc<DatabaseTransaction>>>,
+    error: Option<String>,
+    _db: PhantomData<fn() -> D>,
+}
+
+impl<D> TxSlot<D> {
+    fn new(txn: Result<DatabaseTransaction, String>) -> Self {
+        let (txn, error) = match txn {
+            Ok(txn) => (Some(Arc::new(txn)), None),
+            Err(err) => (None, Some(err)),
+        };
+        TxSlot { txn: Mutex::new(txn), error, _db: PhantomData }
+    }
+}
+
+/// Marker managed by the fairing so the guard can check it is attached.
+struct TxFairingAttached<D>(PhantomData<fn() -> D>);
+
+#[rocket::async_trait]
+impl<'r, D> FromRequest<'r> for Tx<'r, D>
+where
+    D: Database,
+    D::Pool: Pool<Connection = DatabaseConnection>,
+{
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if req.rocket().state::<TxFairingAttached<D>>().is_none() {
+            let type_name = std::any::type_name::<D>();
+            let err = format!("Tx<{}> requires the `Tx::<{}>::fairing()` fairing to be attached", type_name, type_name);
+            error!("{}", err);
+            return Outcome::Error((Status::InternalServerError, err));
+        }
+
+        let slot = req.local_cache_async(async {
+            let conn = match Connection::<D>::from_request(req).await {
+                Outcome::Success(conn) => Some(conn.into_inner()),
+                _ => None,
+            };
+            let txn = match conn {
+                Some(conn) => conn.begin().await.map_err(|err| err.to_string()),
+                None => Err("No database connection available".to_owned()),
+            };
+            TxSlot::<D>::new(txn)
+        }).await;
+
+        if let Some(err) = &slot.error {
+            error!("Cannot begin the transaction: {}", err);
+            return Outcome::Error((Status::ServiceUnavailable, err.clone()));
+        }
+
+        match slot.txn.lock().unwrap().as_ref() {
+            Some(txn) => Outcome::Success(Tx { txn: txn.clone(), _db: PhantomData }),
+            None => Outcome::Error((Status::InternalServerError, "The transaction has already ended".to_owned())),
+        }
+    }
+}
+
+/// Commits the request transaction on a 2xx response, rolls it back otherwise.
+pub struct TxFairing<D>(PhantomData<fn() -> D>);
+
+#[rocket::async_trait]
+impl<D: Database> Fairing for TxFairing<D> {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request transaction",
+            kind: Kind::Ignite | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket.manage(TxFairingAttached::<D>(PhantomData)))
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let slot = req.local_cache(|| TxSlot::<D>::new(Err("No transaction started".to_owned())));
+        let txn = match slot.txn.lock().unwrap().take() {
+            Some(txn) => txn,
+            None => return,
+        };
+
+        // The guards are dropped once the handler returns, the transaction is rolled back on drop otherwise
+        let txn = match Arc::try_unwrap(txn) {
+            Ok(txn) => txn,
+            Err(_) => {
+                error!("The request transaction is still borrowed, rolling back");
+                // The changes of the request are lost, whatever the handler answered
+                let body = "The request transaction is still in use and was rolled back";
+                res.set_status(Status::InternalServerError);
+                res.set_sized_body(body.len(), std::io::Cursor::new(body));
+                return;
+            }
+        };
+
+        let res_status = res.status();
+        let result = if res_status.class().is_success() {
+            txn.commit().await
+        } else {
+            txn.rollback().await
+        };
+
+        if let Err(err) = result {
+            error!("Cannot end the request transaction: {}", err);
+            if res_status.class().is_success() {
+                let body = format!("Cannot commit the transaction: {}", err);
+                res.set_status(Status::InternalServerError);
+                res.set_sized_body(body.len(), std::io::Cursor::new(body));
+            }
+        }
+    }
+}
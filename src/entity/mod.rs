@@ -1,6 +0,0 @@
-//! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.6
-
-pub mod prelude;
-
-pub mod animal;
-pub mod car;
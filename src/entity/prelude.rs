@@ -1,4 +0,0 @@
-//! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.6
-
-pub use super::animal::Entity as Animal;
-pub use super::car::Entity as Car;
@@ -0,0 +1,60 @@
+use rocket::futures::StreamExt;
+use rocket::tokio::fs::{self, File};
+use rocket::tokio::io::AsyncWriteExt;
+use sea_orm::{Condition, Database, DatabaseConnection};
+use serde::Serialize;
+
+use generic_crud_trait::{arrow_file, ArrowFields, CRUDServiceTrait};
+
+use crate::{animal, car, caretaker, owner};
+
+const USAGE: &str = "Usage: rocket_api export <cars|animals|owners|caretakers> <file.arrow>";
+
+/// `rocket_api export <resource> <file>` writes all the objects of a resource to an Arrow IPC file,
+/// connecting to the `DATABASE_URL` database.
+pub async fn run(args: &[String]) -> Result<(), String> {
+    let (resource, path) = match args {
+        [resource, path] => (resource.as_str(), path.as_str()),
+        _ => return Err(USAGE.to_owned()),
+    };
+
+    let database_url = std::env::var("DATABASE_URL").map_err(|err| format!("DATABASE_URL: {}", err))?;
+    let db = Database::connect(database_url).await.map_err(|err| err.to_string())?;
+
+    match resource {
+        "cars" => export::<car::service::CarService, _, _, _>(&db, path).await,
+        "animals" => export::<animal::service::AnimalService, _, _, _>(&db, path).await,
+        "owners" => export::<owner::service::OwnerService, _, _, _>(&db, path).await,
+        "caretakers" => export::<caretaker::service::CaretakerService, _, _, _>(&db, path).await,
+        _ => Err(USAGE.to_owned()),
+    }
+}
+
+async fn export<S, Model, CreateModel, PartialModel>(db: &DatabaseConnection, path: &str) -> Result<(), String>
+where
+    S: CRUDServiceTrait<Model, CreateModel, PartialModel>,
+    Model: ArrowFields + Serialize + Send,
+{
+    // Written next to the file and renamed once complete, an interrupted export leaving the previous file untouched
+    let tmp_path = format!("{}.{}.tmp", path, std::process::id());
+    if let Err(err) = write::<S, _, _, _>(db, &tmp_path).await {
+        let _ = fs::remove_file(&tmp_path).await;
+        return Err(err);
+    }
+    fs::rename(&tmp_path, path).await.map_err(|err| format!("{}: {}", path, err))
+}
+
+async fn write<S, Model, CreateModel, PartialModel>(db: &DatabaseConnection, path: &str) -> Result<(), String>
+where
+    S: CRUDServiceTrait<Model, CreateModel, PartialModel>,
+    Model: ArrowFields + Serialize + Send,
+{
+    let mut file = File::create(path).await.map_err(|err| format!("{}: {}", path, err))?;
+    let mut bytes = arrow_file(S::stream_all(None, Condition::all(), 0, None, db));
+    while let Some(chunk) = bytes.next().await {
+        let chunk = chunk.map_err(|err| format!("Cannot export the rows: {}", err))?;
+        file.write_all(&chunk).await.map_err(|err| format!("{}: {}", path, err))?;
+    }
+    file.flush().await.map_err(|err| format!("{}: {}", path, err))?;
+    file.sync_all().await.map_err(|err| format!("{}: {}", path, err))
+}
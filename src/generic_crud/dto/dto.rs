@@ -0,0 +1,42 @@
+//! The bodies shared by the CRUD routes and their clients, free of any server dependency.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+/// A model serialized along with its included relations.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Included<Model> {
+    #[serde(flatten)]
+    pub obj: Model,
+    #[serde(flatten)]
+    pub relations: Map<String, Value>,
+}
+
+impl<Model> Included<Model> {
+    pub fn new(obj: Model) -> Self {
+        Included { obj, relations: Map::new() }
+    }
+}
+
+/// Objects fetched by id, in the requested order, along with the requested ids that were not found.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ByIds<Model> {
+    pub data: Vec<Model>,
+    pub missing: Vec<i32>,
+}
+
+/// Body of the `POST /_get` routes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IdsRequest {
+    pub ids: Vec<i32>,
+}
+
+/// Deserialize a present value, even `null`, as `Some`, so that `Option<Option<T>>` fields
+/// can tell a missing key (`None`) from an explicit `null` (`Some(None)`).
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -3,20 +3,166 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 
 use quote::{format_ident, quote, TokenStreamExt};
-use syn::{DeriveInput, Ident, Lit, Meta, parse_macro_input, parse_quote, Path, PathArguments, PathSegment, Token, TypePath};
-use syn::__private::Span;
+use syn::{DeriveInput, Ident, Lit, Meta, NestedMeta, parse_macro_input, parse_quote, Path, PathArguments, PathSegment, Token, TypePath};
+use syn::__private::{Span, TokenStream2};
+use syn::parse::Parser;
+
+/// Field attributes understood by CRUDModel, stripped from the generated Post/Partial structs.
+const CRUD_FIELD_ATTRIBUTES: &[&str] = &[
+    "idField", "createdAt", "updatedAt", "readOnly", "writeOnly", "immutable",
+    "column", "from_entity", "to_entity", "computed", "default", "belongsTo", "deletedAt",
+];
+
+fn has_attribute(field: &syn::Field, name: &str) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident(name))
+}
+
+fn find_field_by_name<'a>(name: &'a str, data_struct: &'a syn::DataStruct) -> Option<&'a syn::Field> {
+    data_struct.fields.iter().find(|field| has_attribute(field, name))
+}
 
 fn get_field_by_name<'a>(name: &'a str, data_struct: &'a syn::DataStruct) -> &'a syn::Field {
-    data_struct.fields.iter().find(|field| {
-        field.attrs.iter().any(|attr| {
-            attr.path.is_ident(name)
+    find_field_by_name(name, data_struct).unwrap_or_else(|| panic!("#[{}] field not found", name))
+}
+
+fn has_serde_skip_serializing(field: &syn::Field) -> bool {
+    field.attrs.iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path.is_ident("skip") || path.is_ident("skip_serializing"),
+            _ => false,
+        })
+}
+
+fn is_option_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn strip_crud_attributes(field: &syn::Field) -> syn::Field {
+    let mut field = field.clone();
+    field.attrs.retain(|attr| !CRUD_FIELD_ATTRIBUTES.iter().any(|name| attr.path.is_ident(name)));
+    field
+}
+
+/// Name of the entity field backing a model field, `#[column = "foo"]` or the field name itself
+fn get_column_ident(field: &syn::Field) -> Ident {
+    match get_attrs_value(&field.attrs, "column") {
+        Some(column) => Ident::new(&column, Span::call_site()),
+        None => field.ident.clone().unwrap(),
+    }
+}
+
+/// Function path of a `#[name(with = path::to::fn)]` field attribute
+fn get_field_with_fn(field: &syn::Field, attr_name: &str) -> Option<Path> {
+    let attr = field.attrs.iter().find(|attr| attr.path.is_ident(attr_name))?;
+    let path = attr.parse_args_with(|input: syn::parse::ParseStream| {
+        let key: Ident = input.parse()?;
+        if key != "with" {
+            return Err(syn::Error::new(key.span(), "expected `with = path::to::fn`"));
+        }
+        input.parse::<Token![=]>()?;
+        input.parse::<Path>()
+    });
+    Some(path.unwrap_or_else(|err| panic!("#[{}(with = ...)]: {}", attr_name, err)))
+}
+
+/// Function path of a `#[computed(path::to::fn)]` field attribute
+fn get_computed_fn(field: &syn::Field) -> Option<Path> {
+    let attr = field.attrs.iter().find(|attr| attr.path.is_ident("computed"))?;
+    Some(attr.parse_args::<Path>().unwrap_or_else(|err| panic!("#[computed(...)]: {}", err)))
+}
+
+/// `Some(None)` for a bare `#[default]` field attribute, `Some(Some(expr))` for `#[default = expr]`
+fn get_default_value(field: &syn::Field) -> Option<Option<syn::Expr>> {
+    let attr = field.attrs.iter().find(|attr| attr.path.is_ident("default"))?;
+    let parser = |input: syn::parse::ParseStream| -> syn::Result<Option<syn::Expr>> {
+        if input.is_empty() {
+            return Ok(None);
+        }
+        input.parse::<Token![=]>()?;
+        input.parse().map(Some)
+    };
+    Some(parser.parse2(attr.tokens.clone()).unwrap_or_else(|err| panic!("#[default = ...]: {}", err)))
+}
+
+/// Parent module of a `#[belongsTo(parent)]` field attribute
+fn get_belongs_to(field: &syn::Field) -> Option<Ident> {
+    let attr = field.attrs.iter().find(|attr| attr.path.is_ident("belongsTo"))?;
+    Some(attr.parse_args::<Ident>().unwrap_or_else(|err| panic!("#[belongsTo(...)]: {}", err)))
+}
+
+/// Related and join table modules of the `#[manyToMany(related, via = join_table)]` struct attributes
+fn get_many_to_many(input: &DeriveInput) -> Vec<(Ident, Ident)> {
+    input.attrs.iter()
+        .filter(|attr| attr.path.is_ident("manyToMany"))
+        .map(|attr| {
+            let parser = |input: syn::parse::ParseStream| -> syn::Result<(Ident, Ident)> {
+                let related: Ident = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let key: Ident = input.parse()?;
+                if key != "via" {
+                    return Err(syn::Error::new(key.span(), "expected `via = join_table`"));
+                }
+                input.parse::<Token![=]>()?;
+                Ok((related, input.parse()?))
+            };
+            attr.parse_args_with(parser).unwrap_or_else(|err| panic!("#[manyToMany(...)]: {}", err))
         })
-    }).expect(format!("#[{}] field not found", name).as_str())
+        .collect()
+}
+
+/// Child module and delete policy of the `#[hasMany(child, onDelete = policy)]` struct attributes
+fn get_has_many(input: &DeriveInput) -> Vec<(Ident, Ident)> {
+    input.attrs.iter()
+        .filter(|attr| attr.path.is_ident("hasMany"))
+        .map(|attr| {
+            let parser = |input: syn::parse::ParseStream| -> syn::Result<(Ident, Ident)> {
+                let child: Ident = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let key: Ident = input.parse()?;
+                if key != "onDelete" {
+                    return Err(syn::Error::new(key.span(), "expected `onDelete = policy`"));
+                }
+                input.parse::<Token![=]>()?;
+                let policy: Ident = input.parse()?;
+                if !["restrict", "cascade", "setNull", "softCascade"].iter().any(|name| policy == name) {
+                    return Err(syn::Error::new(policy.span(), "expected one of `restrict`, `cascade`, `setNull` or `softCascade`"));
+                }
+                Ok((child, policy))
+            };
+            attr.parse_args_with(parser).unwrap_or_else(|err| panic!("#[hasMany(...)]: {}", err))
+        })
+        .collect()
+}
+
+/// Fields of the `#[naturalKey(field, ...)]` struct attribute
+fn get_natural_key(input: &DeriveInput) -> Option<Vec<Ident>> {
+    let attr = input.attrs.iter().find(|attr| attr.path.is_ident("naturalKey"))?;
+    let fields = attr.parse_args_with(syn::punctuated::Punctuated::<Ident, Token![,]>::parse_terminated)
+        .unwrap_or_else(|err| panic!("#[naturalKey(...)]: {}", err));
+    if fields.is_empty() {
+        panic!("#[naturalKey(...)] requires at least one field");
+    }
+    Some(fields.into_iter().collect())
 }
 
 fn get_attribute_value(input: &DeriveInput, attr_name: &str) -> Option<String> {
+    get_attrs_value(&input.attrs, attr_name)
+}
+
+fn get_attrs_value(attrs: &[syn::Attribute], attr_name: &str) -> Option<String> {
     // Iterate over the attributes of the input struct
-    for attr in &input.attrs {
+    for attr in attrs {
         // Check if the attribute is the one with the specified name
         if attr.path.is_ident(attr_name) {
             // Extract the attribute's value
@@ -34,6 +180,39 @@ fn get_attribute_value(input: &DeriveInput, attr_name: &str) -> Option<String> {
     None
 }
 
+/// JSON Schema of an object, a property being its name, its schema expression and whether it is required
+fn object_schema(properties: Vec<(String, TokenStream2, bool)>) -> TokenStream2 {
+    let required: Vec<&String> = properties.iter()
+        .filter(|(_name, _schema, required)| *required)
+        .map(|(name, _schema, _required)| name)
+        .collect();
+    // An empty list of required properties is not valid in OpenAPI 3.0
+    let required = if required.is_empty() {
+        quote! {}
+    } else {
+        quote! { schema.insert("required".to_owned(), json!([#(#required),*])); }
+    };
+    let properties = properties.iter().map(|(name, schema, _required)| quote! {
+        properties.insert(#name.to_owned(), #schema);
+    });
+    quote! {{
+        use rocket::serde::json::serde_json::{json, Map, Value};
+
+        #[allow(unused_mut)]
+        let mut properties = Map::new();
+        #(#properties)*
+        let mut schema = Map::new();
+        schema.insert("type".to_owned(), json!("object"));
+        #required
+        schema.insert("properties".to_owned(), Value::Object(properties));
+        Value::Object(schema)
+    }}
+}
+
+fn type_schema(ty: &syn::Type) -> TokenStream2 {
+    quote! { <#ty as generic_crud_trait::ApiSchema>::schema() }
+}
+
 fn build_type_path(path: &str) -> TypePath {
     let mut segments: syn::punctuated::Punctuated<_, Token![::]> = syn::punctuated::Punctuated::new();
 
@@ -54,7 +233,89 @@ fn build_type_path(path: &str) -> TypePath {
 }
 
 
-#[proc_macro_derive(CRUDModel, attributes(module, idField))]
+/// The id, the timestamps, the read only and the computed fields are set server-side, clients can't provide them.
+fn is_read_only(field: &syn::Field) -> bool {
+    ["idField", "createdAt", "updatedAt", "deletedAt", "readOnly", "computed"].iter()
+        .any(|name| has_attribute(field, name))
+}
+
+/// Immutable fields can be provided on creation only.
+fn is_immutable(field: &syn::Field) -> bool {
+    is_read_only(field) || has_attribute(field, "immutable")
+}
+
+fn check_write_only(data_struct: &syn::DataStruct) {
+    for field in data_struct.fields.iter() {
+        let ident = field.ident.as_ref().unwrap();
+        if has_attribute(field, "writeOnly") {
+            if has_attribute(field, "readOnly") {
+                panic!("#[readOnly] and #[writeOnly] can't be both set on field `{}`", ident);
+            }
+            if !has_serde_skip_serializing(field) {
+                panic!("#[writeOnly] field `{}` must also be marked #[serde(skip_serializing)]", ident);
+            }
+        }
+    }
+}
+
+/// The Post and Partial request bodies of a model, `deserialize_some` being the path of the function
+/// telling a missing nullable field from an explicit `null`.
+fn request_structs(name: &Ident, data_struct: &syn::DataStruct, deserialize_some: &str) -> TokenStream2 {
+    let post_name_ident = format_ident!("Post{}", name);
+    let fields = data_struct.fields.iter()
+        .filter(|field| !is_read_only(field))
+        .map(|field| {
+            let has_default = get_default_value(field).is_some();
+            let mut field = strip_crud_attributes(field);
+            // Fields with a default value can be omitted from the request body
+            if has_default {
+                let ty = field.ty.clone();
+                field.ty = parse_quote! { Option<#ty> };
+            }
+            field
+        });
+
+    let partial_name_ident = format_ident!("Partial{}", name);
+    let partial_fields = data_struct.fields.iter()
+        .filter(|field| !is_immutable(field))
+        .map(|field| {
+            let mut field = strip_crud_attributes(field);
+            let ty = field.ty.clone();
+            // Nullable columns: a missing key leaves the value untouched while `null` clears it
+            if is_option_type(&ty) {
+                field.attrs.push(parse_quote! {
+                    #[serde(
+                        default,
+                        deserialize_with = #deserialize_some,
+                        skip_serializing_if = "Option::is_none"
+                    )]
+                });
+            } else {
+                // Only the fields to update are sent by the clients
+                field.attrs.push(parse_quote! { #[serde(skip_serializing_if = "Option::is_none")] });
+            }
+            field.ty = parse_quote! { Option<#ty> };
+            field
+        });
+
+    quote! {
+        #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+        pub struct #post_name_ident {
+            #(#fields),*
+        }
+
+        #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+        pub struct #partial_name_ident {
+            #(#partial_fields),*
+        }
+    }
+}
+
+
+#[proc_macro_derive(CRUDModel, attributes(
+    module, idField, createdAt, updatedAt, readOnly, writeOnly, immutable,
+    column, from_entity, to_entity, computed, default, belongsTo, manyToMany, deletedAt, hasMany, naturalKey
+))]
 pub fn crud_model(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
     let input = parse_macro_input!(input as DeriveInput);
@@ -73,88 +334,641 @@ pub fn crud_model(input: TokenStream) -> TokenStream {
         _ => unimplemented!()
     };
     let id_field = get_field_by_name("idField", data_struct);
+    let created_at_field = find_field_by_name("createdAt", data_struct);
+    let updated_at_field = find_field_by_name("updatedAt", data_struct);
+    let deleted_at_field = find_field_by_name("deletedAt", data_struct);
 
-    let post_name_ident = format_ident!("Post{}", name);
-    let fields = data_struct.fields.iter().filter(|field| field != &id_field);
+    check_write_only(data_struct);
 
+    let post_name_ident = format_ident!("Post{}", name);
     let partial_name_ident = format_ident!("Partial{}", name);
-    let partial_fields = data_struct.fields.iter()
-        .filter(|field| field != &id_field)
-        .map(|field| {
-            let mut field = field.clone();
-            let ty = field.ty.clone();
-            field.ty = parse_quote! { Option<#ty> };
-            field
-        });
+    let request_structs = request_structs(name, data_struct, "generic_crud_trait::deserialize_some");
 
+    let mut computed_fields = quote! {};
     let mut from_entity_fields = quote! {};
     // Get the fields of the struct
     if let syn::Fields::Named(ref fields) = data_struct.fields {
         // Create a match expression that converts each field in the input model
         for field in fields.named.iter() {
             let ident = &field.ident;
-            from_entity_fields.append_all(quote! {
-                #ident: obj.#ident,
-            });
+            let column = get_column_ident(field);
+            // Computed fields borrow the whole entity, so they are evaluated before moving its fields
+            if let Some(compute) = get_computed_fn(field) {
+                computed_fields.append_all(quote! {
+                    let #ident = #compute(&obj);
+                });
+                from_entity_fields.append_all(quote! {
+                    #ident,
+                });
+            } else if let Some(convert) = get_field_with_fn(field, "from_entity") {
+                from_entity_fields.append_all(quote! {
+                    #ident: #convert(obj.#column),
+                });
+            } else {
+                from_entity_fields.append_all(quote! {
+                    #ident: obj.#column,
+                });
+            }
         }
     }
 
+    let mut post_active_model_fields = quote! {};
     let mut to_active_model_fields = quote! {};
     if let syn::Fields::Named(ref fields) = data_struct.fields {
-        // Create a match expression that converts each field in the input model
         for field in fields.named.iter() {
             let ident = &field.ident;
-            // if ident is idField, then use id instead
-            if ident == &id_field.ident {
-                to_active_model_fields.append_all(quote! {
-                    id: ActiveValue::set(placeholder.id),
+            let column = get_column_ident(field);
+            let value = match get_field_with_fn(field, "to_entity") {
+                Some(convert) => quote! { #convert(value) },
+                None => quote! { value },
+            };
+            if !is_read_only(field) {
+                post_active_model_fields.append_all(match get_default_value(field) {
+                    // Omitted, left to the column default
+                    Some(None) => quote! {
+                        #column: match self.#ident {
+                            Some(value) => ActiveValue::set(#value),
+                            None => ActiveValue::not_set(),
+                        },
+                    },
+                    Some(Some(default)) => quote! {
+                        #column: {
+                            let value = self.#ident.unwrap_or_else(|| ::std::convert::Into::into(#default));
+                            ActiveValue::set(#value)
+                        },
+                    },
+                    None => quote! {
+                        #column: {
+                            let value = self.#ident;
+                            ActiveValue::set(#value)
+                        },
+                    },
                 });
-            } else {
+            }
+            // Fields that can't be updated are left unchanged
+            if !is_immutable(field) {
                 to_active_model_fields.append_all(quote! {
-                    #ident: ActiveValue::set(self.#ident.unwrap_or(placeholder.#ident)),
+                    if let Some(value) = self.#ident {
+                        obj.#column = ActiveValue::set(#value);
+                    }
                 });
             }
         }
     }
 
+    let entity = build_type_path(&format!("{}::Entity", entity_module));
+    let id_ident = &id_field.ident;
+    let id_column = get_column_ident(id_field);
+    let id_column_variant = build_type_path(&format!(
+        "{}::Column::{}", entity_module, heck::AsUpperCamelCase(id_column.to_string())
+    ));
+
+    let column_type = build_type_path(&format!("{}::Column", entity_module));
+    // Bounds of the controller handling the routes of the relations and of the natural key, and those routes
+    let mut relation_controller_bounds = vec![];
+    let mut relation_routes = vec![];
+    let mut belongs_to_impls = quote! {};
+    let mut parent_checks = quote! {};
+    let mut include_arms = quote! {};
+    for field in data_struct.fields.iter() {
+        if let Some(parent) = get_belongs_to(field) {
+            let relation = parent.to_string();
+            let parent_entity = build_type_path(&format!("crate::entity::{}::Entity", parent));
+            let parent_model = build_type_path(&format!("crate::{}::models::{}", parent, heck::AsUpperCamelCase(&relation)));
+            // A single query joining the parents of all the objects
+            include_arms.append_all(quote! {
+                #relation => {
+                    let mut query = #entity::find()
+                        .filter(#id_column_variant.is_in(ids.clone()))
+                        .find_also_related(#parent_entity);
+                    // A soft deleted parent is left out like a missing one
+                    if let Some(deleted_at) = <#parent_model as generic_crud_trait::SoftDelete>::deleted_at_column() {
+                        query = query.filter(deleted_at.is_null());
+                    }
+                    let pairs = query.all(db).await?;
+                    let (child_ids, parents): (Vec<_>, Vec<_>) = pairs.into_iter()
+                        .filter_map(|(child, parent)| parent.map(|parent| (child.#id_column, #parent_model::from_entity(parent))))
+                        .unzip();
+                    let parents = <#parent_model as LoadRelations>::load_relations(parents, &nested, db).await?;
+                    let mut parents: std::collections::HashMap<_, _> = child_ids.into_iter()
+                        .zip(parents)
+                        .collect();
+                    for obj in objs.iter_mut() {
+                        let parent = parents.remove(&obj.obj.#id_ident)
+                            .map(|parent| rocket::serde::json::serde_json::to_value(parent).unwrap())
+                            .unwrap_or_default();
+                        obj.relations.insert(relation.clone(), parent);
+                    }
+                }
+            });
+
+            let field_name = field.ident.as_ref().unwrap().to_string();
+            let active_column = get_column_ident(field);
+            // A null parent is not checked
+            let parent_id = if is_option_type(&field.ty) {
+                quote! { ActiveValue::Set(Some(parent_id)) }
+            } else {
+                quote! { ActiveValue::Set(parent_id) }
+            };
+            parent_checks.append_all(quote! {
+                if let #parent_id = &obj.#active_column {
+                    if #parent_entity::find_by_id(parent_id.clone()).one(db).await?.is_none() {
+                        return Ok(Some(#field_name));
+                    }
+                }
+            });
+
+            let column = build_type_path(&format!(
+                "{}::Column::{}", entity_module, heck::AsUpperCamelCase(get_column_ident(field).to_string())
+            ));
+            relation_controller_bounds.push(quote! {
+                generic_crud_trait::CRUDNestedControllerTrait<#parent_entity, #name, #post_name_ident>
+            });
+            relation_routes.push(quote! {
+                generic_crud_trait::nested_routes::<Controller, Self, #parent_entity, #post_name_ident>(paths, #relation)
+            });
+            belongs_to_impls.append_all(quote! {
+                impl generic_crud_trait::BelongsTo<#parent_entity> for #name {
+                    type Column = #column_type;
+
+                    fn parent_column() -> Self::Column {
+                        #column
+                    }
+                }
+            });
+        }
+    }
+
+    let mut many_to_many_impls = quote! {};
+    for (related, via) in get_many_to_many(&input) {
+        let related_entity = build_type_path(&format!("crate::entity::{}::Entity", related));
+        let related_model = build_type_path(&format!("crate::{}::models::{}", related, heck::AsUpperCamelCase(related.to_string())));
+        let via_entity = build_type_path(&format!("crate::entity::{}::Entity", via));
+        let via_active_model = build_type_path(&format!("crate::entity::{}::ActiveModel", via));
+        let via_column = build_type_path(&format!("crate::entity::{}::Column", via));
+        let own_column = build_type_path(&format!("crate::entity::{}::Column::{}Id", via, heck::AsUpperCamelCase(&module)));
+        let related_column = build_type_path(&format!("crate::entity::{}::Column::{}Id", via, heck::AsUpperCamelCase(related.to_string())));
+        many_to_many_impls.append_all(quote! {
+            impl generic_crud_trait::ManyToMany<#related_entity> for #name {
+                type Via = #via_entity;
+                type ViaActiveModel = #via_active_model;
+                type RelatedModel = #related_model;
+
+                fn via_columns() -> (#via_column, #via_column) {
+                    (#own_column, #related_column)
+                }
+            }
+        });
+        let related_module = related.to_string();
+        relation_controller_bounds.push(quote! {
+            generic_crud_trait::CRUDManyToManyControllerTrait<#related_entity, #related_model>
+        });
+        relation_routes.push(quote! {
+            generic_crud_trait::link_routes::<Controller, Self, #related_entity, #related_model>(paths, #related_module)
+        });
+    }
+
+    let (deleted_at_column, soft_deletable) = match deleted_at_field {
+        Some(field) => {
+            let column = build_type_path(&format!(
+                "{}::Column::{}", entity_module, heck::AsUpperCamelCase(get_column_ident(field).to_string())
+            ));
+            (quote! { Some(#column) }, quote! {
+                impl generic_crud_trait::SoftDeletable for #name {
+                    fn deleted_at_column() -> Self::Column {
+                        #column
+                    }
+                }
+            })
+        }
+        None => (quote! { None }, quote! {}),
+    };
+
+    // Write only fields are never serialized
+    let field_names = data_struct.fields.iter()
+        .filter(|field| !has_serde_skip_serializing(field))
+        .map(|field| field.ident.as_ref().unwrap().to_string());
+
+    // Columns keep their type, the computed and converted fields have the type of their schema
+    let arrow_fields = data_struct.fields.iter()
+        .filter(|field| !has_serde_skip_serializing(field))
+        .map(|field| {
+            let name = field.ident.as_ref().unwrap().to_string();
+            let arrow_type = if has_attribute(field, "computed") || get_field_with_fn(field, "from_entity").is_some() {
+                let schema = type_schema(&field.ty);
+                quote! { generic_crud_trait::ArrowType::of_schema(&#schema) }
+            } else {
+                let column = build_type_path(&format!(
+                    "{}::Column::{}", entity_module, heck::AsUpperCamelCase(get_column_ident(field).to_string())
+                ));
+                quote! { generic_crud_trait::ArrowType::of(sea_orm::ColumnTrait::def(&#column).get_column_type()) }
+            };
+            quote! { (#name, #arrow_type) }
+        });
+
+    // Schemas of the model and of its request bodies, for the OpenAPI document and the `_schema` routes
+    let property_schema = |field: &syn::Field, schema: TokenStream2| {
+        let column = if has_attribute(field, "computed") {
+            quote! { None }
+        } else {
+            let column = build_type_path(&format!(
+                "{}::Column::{}", entity_module, heck::AsUpperCamelCase(get_column_ident(field).to_string())
+            ));
+            quote! { Some(sea_orm::ColumnTrait::def(&#column)) }
+        };
+        let read_only = is_read_only(field);
+        let write_only = has_attribute(field, "writeOnly");
+        quote! { generic_crud_trait::field_schema(#schema, #column, #read_only, #write_only) }
+    };
+    let model_schema = object_schema(data_struct.fields.iter()
+        .filter(|field| !has_serde_skip_serializing(field))
+        .map(|field| (field.ident.as_ref().unwrap().to_string(), property_schema(field, type_schema(&field.ty)), !is_option_type(&field.ty)))
+        .collect());
+    let post_schema = object_schema(data_struct.fields.iter()
+        .filter(|field| !is_read_only(field))
+        .map(|field| {
+            let ty = &field.ty;
+            let schema = match get_default_value(field) {
+                Some(Some(default)) => quote! {{
+                    let mut schema = <#ty as generic_crud_trait::ApiSchema>::schema();
+                    let default: #ty = ::std::convert::Into::into(#default);
+                    schema["default"] = rocket::serde::json::serde_json::to_value(default).unwrap();
+                    schema
+                }},
+                _ => type_schema(ty),
+            };
+            let required = get_default_value(field).is_none() && !is_option_type(ty);
+            (field.ident.as_ref().unwrap().to_string(), property_schema(field, schema), required)
+        })
+        .collect());
+    let partial_schema = object_schema(data_struct.fields.iter()
+        .filter(|field| !is_immutable(field))
+        .map(|field| (field.ident.as_ref().unwrap().to_string(), property_schema(field, type_schema(&field.ty)), false))
+        .collect());
+    let name_str = name.to_string();
+    let post_name_str = post_name_ident.to_string();
+    let partial_name_str = partial_name_ident.to_string();
+    let parents = data_struct.fields.iter()
+        .filter_map(get_belongs_to)
+        .map(|parent| parent.to_string());
+    let many_to_many_related = get_many_to_many(&input).into_iter()
+        .map(|(related, _via)| related.to_string());
+
+    let mut natural_key_schema = quote! { None };
+    let mut natural_key_impl = quote! {};
+    if let Some(key_fields) = get_natural_key(&input) {
+        let key_name_ident = format_ident!("{}NaturalKey", name);
+        let key_fields: Vec<&syn::Field> = key_fields.iter()
+            .map(|key_field| {
+                let field = data_struct.fields.iter()
+                    .find(|field| field.ident.as_ref() == Some(key_field))
+                    .unwrap_or_else(|| panic!("#[naturalKey(...)]: unknown field `{}`", key_field));
+                if is_read_only(field) {
+                    panic!("#[naturalKey(...)]: field `{}` is set server-side", key_field);
+                }
+                field
+            })
+            .collect();
+        let key_struct_fields = key_fields.iter().map(|field| {
+            let ident = &field.ident;
+            let ty = &field.ty;
+            quote! { pub #ident: #ty }
+        });
+        let column_variant = |field: &syn::Field| build_type_path(&format!(
+            "{}::Column::{}", entity_module, heck::AsUpperCamelCase(get_column_ident(field).to_string())
+        ));
+        let key_columns = key_fields.iter().map(|field| column_variant(field));
+        let insert_only_columns = std::iter::once(id_field).chain(created_at_field).map(column_variant);
+        let key_schema = object_schema(key_fields.iter()
+            .map(|field| (field.ident.as_ref().unwrap().to_string(), property_schema(field, type_schema(&field.ty)), !is_option_type(&field.ty)))
+            .collect());
+        natural_key_schema = quote! { Some(#key_schema) };
+        relation_controller_bounds.push(quote! {
+            generic_crud_trait::CRUDUpsertControllerTrait<#name, #key_name_ident, #post_name_ident>
+        });
+        relation_routes.push(quote! {
+            generic_crud_trait::upsert_routes::<Controller, Self, #key_name_ident, #post_name_ident>(paths)
+        });
+        natural_key_impl = quote! {
+            #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, rocket::FromForm)]
+            pub struct #key_name_ident {
+                #(#key_struct_fields),*
+            }
+
+            impl generic_crud_trait::NaturalKey for #name {
+                type Key = #key_name_ident;
+                type Column = #column_type;
+
+                fn key_columns() -> Vec<Self::Column> {
+                    vec![#(#key_columns),*]
+                }
+
+                fn insert_only_columns() -> Vec<Self::Column> {
+                    vec![#(#insert_only_columns),*]
+                }
+            }
+        };
+    }
+
+    let mut delete_policies = quote! {};
+    for (child, policy) in get_has_many(&input) {
+        let relation = child.to_string();
+        let child_entity = build_type_path(&format!("crate::entity::{}::Entity", child));
+        let child_model = build_type_path(&format!("crate::{}::models::{}", child, heck::AsUpperCamelCase(&relation)));
+        let apply_policy = match policy.to_string().as_str() {
+            "restrict" => quote! {
+                if !children.is_empty() {
+                    blocking.0.insert(#relation.to_owned(), children);
+                }
+            },
+            "cascade" => quote! {
+                for child_id in children {
+                    <#child_model as DeletePolicies>::before_delete(child_id, txn).await?;
+                }
+                #child_entity::delete_many()
+                    .filter(parent_column.eq(obj_id))
+                    .exec(txn)
+                    .await?;
+            },
+            "setNull" => quote! {
+                let null = generic_crud_trait::null_value(&parent_column.def().get_column_type());
+                #child_entity::update_many()
+                    .col_expr(parent_column, sea_orm::sea_query::Expr::value(null))
+                    .filter(parent_column.eq(obj_id))
+                    .exec(txn)
+                    .await?;
+            },
+            _ => {
+                // Spanned so that a child without a #[deletedAt] field is reported on the policy
+                let soft_deletable: TokenStream2 = quote! { #child_model }.into_iter()
+                    .map(|mut token| {
+                        token.set_span(policy.span());
+                        token
+                    })
+                    .collect();
+                quote! {
+                    let deleted_at = <#soft_deletable as generic_crud_trait::SoftDeletable>::deleted_at_column();
+                    for child_id in children {
+                        <#child_model as DeletePolicies>::before_delete(child_id, txn).await?;
+                    }
+                    #child_entity::update_many()
+                        .col_expr(deleted_at, sea_orm::sea_query::Expr::current_timestamp())
+                        .filter(parent_column.eq(obj_id))
+                        .filter(deleted_at.is_null())
+                        .exec(txn)
+                        .await?;
+                }
+            }
+        };
+        delete_policies.append_all(quote! {
+            {
+                let parent_column = <#child_model as BelongsTo<#entity>>::parent_column();
+                let mut query = #child_entity::find().filter(parent_column.eq(obj_id));
+                if let Some(deleted_at) = <#child_model as SoftDelete>::deleted_at_column() {
+                    query = query.filter(deleted_at.is_null());
+                }
+                let children: Vec<i32> = query.all(txn)
+                    .await?
+                    .into_iter()
+                    .map(|child| <#child_model as ObjectId>::obj_id(&#child_model::from_entity(child)))
+                    .collect();
+                #apply_policy
+            }
+        });
+    }
+
+    let mut before_insert_fields = quote! {};
+    let mut before_update_fields = quote! {};
+    if let Some(field) = created_at_field {
+        let column = get_column_ident(field);
+        before_insert_fields.append_all(quote! {
+            obj.#column = ActiveValue::set(chrono::Utc::now().into());
+        });
+    }
+    if let Some(field) = updated_at_field {
+        let column = get_column_ident(field);
+        before_insert_fields.append_all(quote! {
+            obj.#column = ActiveValue::set(chrono::Utc::now().into());
+        });
+        before_update_fields.append_all(quote! {
+            obj.#column = ActiveValue::set(chrono::Utc::now().into());
+        });
+    }
+
     // Hand the output tokens back to the compiler
     let expanded = quote! {
-        use sea_orm::DeriveIntoActiveModel;
-        use generic_crud_trait::{FromEntity, ToActiveModel};
+        use generic_crud_trait::{BeforeSave, FromEntity, ToActiveModel};
         use sea_orm::ActiveValue;
         use #active_model;
 
+        impl generic_crud_trait::ObjectId for #name {
+            fn obj_id(&self) -> i32 {
+                self.#id_ident
+            }
+        }
+
         impl FromEntity<#model> for #name {
             fn from_entity(obj: #model) -> Self {
+                #computed_fields
                 Self {
                     #from_entity_fields
                 }
             }
         }
 
-        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, DeriveIntoActiveModel)]
-        pub struct #post_name_ident {
-            #(#fields),*
+        #request_structs
+
+        impl sea_orm::IntoActiveModel<#active_model> for #post_name_ident {
+            fn into_active_model(self) -> #active_model {
+                #active_model {
+                    #post_active_model_fields
+                    ..::std::default::Default::default()
+                }
+            }
         }
 
-        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        pub struct #partial_name_ident {
-            #(#partial_fields),*
+        impl ToActiveModel<#active_model> for #partial_name_ident {
+            fn into_active_model(self) -> #active_model {
+                let mut obj: #active_model = ::std::default::Default::default();
+                #to_active_model_fields
+                obj
+            }
         }
 
-        impl ToActiveModel<#active_model, #model> for #partial_name_ident {
-            fn into_active_model(self, placeholder: #model) -> #active_model {
-                #active_model {
-                    #to_active_model_fields
+        #belongs_to_impls
+
+        #[async_trait]
+        impl generic_crud_trait::CheckParents<#active_model> for #name {
+            #[allow(unused_variables)]
+            async fn missing_parent<C: sea_orm::ConnectionTrait>(obj: &#active_model, db: &C) -> Result<Option<&'static str>, sea_orm::DbErr> {
+                use sea_orm::EntityTrait;
+
+                #parent_checks
+
+                Ok(None)
+            }
+        }
+
+        #many_to_many_impls
+
+        #natural_key_impl
+
+        impl<Controller> generic_crud_trait::RelationRoutes<Controller> for #name
+        where
+            Controller: 'static #(+ #relation_controller_bounds)*,
+        {
+            #[allow(unused_variables)]
+            fn relation_routes(paths: &generic_crud_trait::ApiPaths) -> Vec<(String, Vec<rocket::Route>)> {
+                let routes: Vec<Option<(String, Vec<rocket::Route>)>> = vec![#(#relation_routes),*];
+                routes.into_iter().flatten().collect()
+            }
+        }
+
+        impl generic_crud_trait::FieldNames for #name {
+            fn field_names() -> Vec<&'static str> {
+                vec![#(#field_names),*]
+            }
+        }
+
+        impl generic_crud_trait::ArrowFields for #name {
+            fn arrow_fields() -> Vec<(&'static str, generic_crud_trait::ArrowType)> {
+                vec![#(#arrow_fields),*]
+            }
+        }
+
+        impl generic_crud_trait::ApiSchema for #name {
+            fn schema() -> rocket::serde::json::serde_json::Value {
+                #model_schema
+            }
+        }
+
+        impl generic_crud_trait::ApiSchema for #post_name_ident {
+            fn schema() -> rocket::serde::json::serde_json::Value {
+                #post_schema
+            }
+        }
+
+        impl generic_crud_trait::ApiSchema for #partial_name_ident {
+            fn schema() -> rocket::serde::json::serde_json::Value {
+                #partial_schema
+            }
+        }
+
+        impl generic_crud_trait::ApiResource for #name {
+            const MODULE: &'static str = #module;
+            const NAME: &'static str = #name_str;
+
+            fn schemas() -> Vec<(String, rocket::serde::json::serde_json::Value)> {
+                use generic_crud_trait::ApiSchema;
+
+                vec![
+                    (#name_str.to_owned(), #name::schema()),
+                    (#post_name_str.to_owned(), #post_name_ident::schema()),
+                    (#partial_name_str.to_owned(), #partial_name_ident::schema()),
+                ]
+            }
+
+            fn parents() -> Vec<&'static str> {
+                vec![#(#parents),*]
+            }
+
+            fn many_to_many() -> Vec<&'static str> {
+                vec![#(#many_to_many_related),*]
+            }
+
+            fn natural_key() -> Option<rocket::serde::json::serde_json::Value> {
+                #natural_key_schema
+            }
+        }
+
+        impl generic_crud_trait::SoftDelete for #name {
+            type Column = #column_type;
+
+            fn deleted_at_column() -> Option<Self::Column> {
+                #deleted_at_column
+            }
+        }
+
+        #soft_deletable
+
+        #[async_trait]
+        impl generic_crud_trait::DeletePolicies for #name {
+            #[allow(unused_variables, unused_mut)]
+            async fn before_delete(obj_id: i32, txn: &sea_orm::DatabaseTransaction) -> Result<(), generic_crud_trait::DeleteError> {
+                use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+                use generic_crud_trait::{BelongsTo, DeletePolicies, ObjectId, SoftDelete};
+
+                let mut blocking = generic_crud_trait::BlockingChildren::default();
+
+                #delete_policies
+
+                if blocking.0.is_empty() {
+                    Ok(())
+                } else {
+                    Err(generic_crud_trait::DeleteError::Blocked(blocking))
                 }
             }
         }
 
+        #[async_trait]
+        impl generic_crud_trait::LoadRelations for #name {
+            #[allow(unused_variables, unused_mut)]
+            async fn load_relations<C: sea_orm::ConnectionTrait>(
+                objs: Vec<Self>,
+                include: &[String],
+                db: &C,
+            ) -> Result<Vec<generic_crud_trait::Included<Self>>, generic_crud_trait::QueryError> {
+                use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+                use generic_crud_trait::LoadRelations;
+
+                let ids: Vec<_> = objs.iter().map(|obj| obj.#id_ident.clone()).collect();
+                let mut objs: Vec<_> = objs.into_iter().map(generic_crud_trait::Included::new).collect();
+
+                for (relation, nested) in generic_crud_trait::group_includes(include) {
+                    match relation.as_str() {
+                        #include_arms
+                        _ => return Err(generic_crud_trait::QueryError::unknown_relation(&relation)),
+                    }
+                }
+
+                Ok(objs)
+            }
+        }
+
+        impl BeforeSave<#active_model> for #name {
+            #[allow(unused_variables)]
+            fn before_insert(obj: &mut #active_model) {
+                #before_insert_fields
+            }
+
+            #[allow(unused_variables)]
+            fn before_update(obj: &mut #active_model) {
+                #before_update_fields
+            }
+        }
+
     };
 
     TokenStream::from(expanded)
 }
 
+/// The Post and Partial request bodies of a model, without the database side of CRUDModel,
+/// for the crates sharing the models with the server, e.g. its clients.
+#[proc_macro_derive(CRUDDto, attributes(
+    module, idField, createdAt, updatedAt, readOnly, writeOnly, immutable,
+    column, from_entity, to_entity, computed, default, belongsTo, manyToMany, deletedAt, hasMany, naturalKey
+))]
+pub fn crud_dto(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let data_struct = match &input.data {
+        syn::Data::Struct(data_struct) => data_struct,
+        _ => unimplemented!()
+    };
+    check_write_only(data_struct);
+
+    TokenStream::from(request_structs(&input.ident, data_struct, "generic_crud_dto::deserialize_some"))
+}
+
 #[proc_macro_derive(CRUDControllerImpl, attributes(module, service, model))]
 pub fn crud_controller(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
@@ -169,53 +983,406 @@ pub fn crud_controller(input: TokenStream) -> TokenStream {
     let model = build_type_path(&format!("{}::{}", models_module, module_capitalized));
     let post_model = build_type_path(&format!("{}::Post{}", models_module, module_capitalized));
     let partial_model = build_type_path(&format!("{}::Partial{}", models_module, module_capitalized));
+    let active_model = build_type_path(&format!("crate::entity::{}::ActiveModel", module));
     let service = build_type_path(&format!("{}::{}Service", service_module, module_capitalized));
 
 
     let impl_block = quote! {
 
+        use rocket::http::Status;
         use rocket::http::uri::Origin;
-        use rocket::response::status::{Created, NoContent};
-        use rocket::serde::json::Json;
+        use rocket::response::status::{BadRequest, Created, Custom, NoContent};
+        use rocket::serde::json::serde_json::{self, Map, Value};
+        use rocket::futures::{future, stream, StreamExt};
+        use rocket::futures::stream::BoxStream;
+        use rocket::http::ContentType;
+        use rocket::response::stream::ByteStream;
+        use sea_orm::TransactionTrait;
         use sea_orm_rocket::Connection;
 
+        use generic_crud_trait::{parse_includes, DeleteError, Included, LoadRelations, QueryError};
+        use generic_crud_trait::{check_unpaginated, parse_filter, parse_ids, parse_pagination, ByIds, IdsRequest, ListResponse, ObjectId, StreamFormat, StreamResponse};
+        use generic_crud_trait::{arrow_file, csv_header, csv_interrupted, csv_record, ndjson_interrupted, stream_body};
+        use generic_crud_trait::{parse_rows, ImportBody, ImportReport};
+        use generic_crud_trait::{CreatedResponse, IdempotencyKey, Idempotent, IdempotentRequest, WriteError};
+        use generic_crud_trait::{Negotiated, ResourceSchemas};
+        use generic_crud_trait::{CRUDUpsertControllerTrait, CRUDUpsertServiceTrait, NaturalKey, Upserted};
         use generic_crud_trait::CRUDControllerTrait;
         use generic_crud_trait::CRUDServiceTrait;
-        use db::Db;
+        use generic_crud_trait::{CRUDNestedControllerTrait, CRUDNestedServiceTrait};
+        use generic_crud_trait::{CRUDManyToManyControllerTrait, CRUDManyToManyServiceTrait};
+        use db::{Db, Tx};
 
         #[async_trait]
         impl CRUDControllerTrait<#model, #post_model, #partial_model> for #name {
 
-            async fn reads(conn: Connection<'_, Db>) -> Json<Vec<#model>> {
-                let db = conn.into_inner();
-                let obj = #service::get_all(&db).await;
-                Json(obj)
+            async fn reads(include: Option<&str>, ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<u64>, offset: Option<u64>, tx: Tx<'_, Db>) -> Result<Negotiated<ListResponse<Included<#model>>>, QueryError> {
+                if let Some(ids) = ids {
+                    check_unpaginated(limit, offset).map_err(QueryError::Invalid)?;
+                    if !filter.is_empty() {
+                        return Err(QueryError::Invalid("`filter` can't be combined with `ids`".to_owned()));
+                    }
+                    let ids = parse_ids(ids).map_err(QueryError::Invalid)?;
+                    let objs = Self::reads_by_ids(Negotiated(IdsRequest { ids }), include, tx).await?;
+                    return Ok(Negotiated(ListResponse::ByIds(objs.into_inner())));
+                }
+
+                let obj = if limit.is_some() || offset.is_some() || !filter.is_empty() {
+                    let condition = parse_filter::<#model, #partial_model, #active_model>(&filter).map_err(QueryError::Invalid)?;
+                    #service::get_page(condition, offset.unwrap_or(0), limit, &*tx).await
+                } else {
+                    #service::get_all(&*tx).await
+                };
+                let obj = #model::load_relations(obj, &parse_includes(include), &*tx).await?;
+                Ok(Negotiated(ListResponse::All(obj)))
             }
 
-            async fn read(obj_id: i32, conn: Connection<'_, Db>) -> Option<Json<#model>> {
-                let db = conn.into_inner();
-                let obj = #service::get_by_id(obj_id, &db).await;
-                obj.map(|obj| Json(obj))
+            async fn reads_by_ids(ids: Negotiated<IdsRequest>, include: Option<&str>, tx: Tx<'_, Db>) -> Result<Negotiated<ByIds<Included<#model>>>, QueryError> {
+                let mut ids = ids.into_inner().ids;
+                let mut seen = std::collections::HashSet::new();
+                ids.retain(|id| seen.insert(*id));
+
+                let mut objs: std::collections::HashMap<i32, #model> = #service::get_by_ids(&ids, &*tx).await
+                    .into_iter()
+                    .map(|obj| (obj.obj_id(), obj))
+                    .collect();
+                let (found, missing): (Vec<i32>, Vec<i32>) = ids.into_iter().partition(|id| objs.contains_key(id));
+                let data = found.into_iter().filter_map(|id| objs.remove(&id)).collect();
+
+                let data = #model::load_relations(data, &parse_includes(include), &*tx).await?;
+                Ok(Negotiated(ByIds { data, missing }))
             }
 
-            async fn post(obj: Json<#post_model>, conn: Connection<'_, Db>, uri: &Origin<'_>) -> Created<Json<#model>> {
+            fn stream<'r>(format: StreamFormat, ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<u64>, offset: Option<u64>, conn: Connection<'r, Db>) -> Result<StreamResponse<'r>, BadRequest<String>> {
+                let ids = ids.map(parse_ids).transpose().map_err(BadRequest)?;
+                if ids.is_some() {
+                    check_unpaginated(limit, offset).map_err(BadRequest)?;
+                    if !filter.is_empty() {
+                        return Err(BadRequest("`filter` can't be combined with `ids`".to_owned()));
+                    }
+                }
+                let condition = parse_filter::<#model, #partial_model, #active_model>(&filter).map_err(BadRequest)?;
+                let offset = offset.unwrap_or(0);
                 let db = conn.into_inner();
-                let obj = #service::create(obj.into_inner(), &db).await;
-                Created::new(format!("{}/{}", uri.to_string(), obj.id)).body(Json(obj))
+                // An interrupted JSON array is left without its closing bracket, and an Arrow file without its footer
+                let bytes: BoxStream<'r, Vec<u8>> = match format {
+                    StreamFormat::Json => {
+                        let items = #service::stream_all(ids, condition, offset, limit, db).enumerate().map(|(index, obj)| obj.map(|obj| {
+                            let mut bytes = if index == 0 { Vec::new() } else { b",".to_vec() };
+                            serde_json::to_writer(&mut bytes, &obj).unwrap();
+                            bytes
+                        }));
+                        let chunks = stream::once(future::ready(Ok(b"[".to_vec()))).chain(items);
+                        stream_body(Box::pin(chunks), b"]".to_vec(), Vec::new())
+                    }
+                    StreamFormat::Ndjson => {
+                        let chunks = #service::stream_all(ids, condition, offset, limit, db).map(|obj| obj.map(|obj| {
+                            let mut bytes = serde_json::to_vec(&obj).unwrap();
+                            bytes.push(b'\n');
+                            bytes
+                        }));
+                        stream_body(Box::pin(chunks), Vec::new(), ndjson_interrupted())
+                    }
+                    StreamFormat::Csv => {
+                        let chunks = stream::once(future::ready(Ok(csv_header::<#model>())))
+                            .chain(#service::stream_all(ids, condition, offset, limit, db).map(|obj| obj.map(|obj| csv_record(&obj))));
+                        stream_body(Box::pin(chunks), Vec::new(), csv_interrupted())
+                    }
+                    StreamFormat::Arrow => {
+                        let chunks = arrow_file(#service::stream_all(ids, condition, offset, limit, db));
+                        stream_body(chunks, Vec::new(), Vec::new())
+                    }
+                };
+                Ok((format.content_type(), ByteStream(bytes)))
             }
 
-            async fn patch(obj_id: i32, obj: Json<#partial_model>, conn: Connection<'_, Db>) -> Option<Json<#model>> {
-                let db = conn.into_inner();
-                let obj = #service::update(obj_id, obj.into_inner(), &db).await;
-                obj.map(|obj| Json(obj))
+            async fn read(obj_id: i32, include: Option<&str>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Included<#model>>>, QueryError> {
+                let obj = match #service::get_by_id(obj_id, &*tx).await {
+                    Some(obj) => obj,
+                    None => return Ok(None),
+                };
+                let mut obj = #model::load_relations(vec![obj], &parse_includes(include), &*tx).await?;
+                Ok(obj.pop().map(|obj| Negotiated(obj)))
             }
 
-            async fn delete(obj_id: i32, conn: Connection<'_, Db>) -> Option<NoContent> {
-                let db = conn.into_inner();
-                let res = #service::delete(obj_id, &db).await;
-                res.map(|_res| NoContent)
+            async fn post(obj: Negotiated<#post_model>, tx: Tx<'_, Db>, uri: &Origin<'_>, idempotency_key: Option<IdempotencyKey>) -> CreatedResponse<#model> {
+                let obj = obj.into_inner();
+                let request = match IdempotentRequest::begin(idempotency_key, uri, &obj, &*tx).await {
+                    Ok(request) => request,
+                    Err(replay) => return replay,
+                };
+                // The failed insert aborts the transaction, rolled back with the claimed key by the error status
+                let obj = match #service::create(obj, &*tx).await {
+                    Ok(obj) => obj,
+                    Err(err) => return Idempotent::Response(Err(err)),
+                };
+                let location = format!("{}/{}", uri.to_string(), obj.obj_id());
+                if let Err(err) = request.complete(Status::Created, Some(&location), &obj, &*tx).await {
+                    return Idempotent::Failed(err);
+                }
+                Idempotent::Response(Ok(Created::new(location).body(Negotiated(obj))))
+            }
+
+            async fn patch(obj_id: i32, obj: Negotiated<#partial_model>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<#model>>, WriteError> {
+                let obj = #service::update(obj_id, obj.into_inner(), &*tx).await?;
+                Ok(obj.map(|obj| Negotiated(obj)))
+            }
+
+            async fn delete(obj_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, DeleteError> {
+                let res = #service::delete(obj_id, &*tx).await?;
+                Ok(res.map(|_res| NoContent))
+            }
+
+            async fn import(body: ImportBody, dry_run: Option<bool>, tx: Tx<'_, Db>) -> Result<Custom<Negotiated<ImportReport>>, WriteError> {
+                let dry_run = dry_run.unwrap_or(false);
+                let (objs, mut errors) = parse_rows::<#post_model>(&body);
+                let rows = objs.len() + errors.len();
+                let valid = objs.len();
+
+                // The rows are inserted in a savepoint, released only when the import is committed
+                let savepoint = tx.begin().await?;
+                errors.extend(#service::create_many(objs, &savepoint).await?);
+                errors.sort_by_key(|error| error.line);
+
+                let inserted = if dry_run || !errors.is_empty() {
+                    savepoint.rollback().await?;
+                    0
+                } else {
+                    savepoint.commit().await?;
+                    valid
+                };
+                let status = if errors.is_empty() { Status::Ok } else { Status::UnprocessableEntity };
+                Ok(Custom(status, Negotiated(ImportReport { dry_run, rows, inserted, errors })))
+            }
+
+            fn schema() -> Negotiated<ResourceSchemas> {
+                Negotiated(ResourceSchemas::of::<#model, #post_model, #partial_model>())
+            }
+
+        }
+
+        impl generic_crud_trait::CrudRoutes for #name {
+            type Resource = #model;
+
+            fn routes(paths: &generic_crud_trait::ApiPaths) -> Vec<(String, Vec<rocket::Route>)> {
+                let mut routes = vec![];
+                if let Some(resource) = paths.get(<#model as generic_crud_trait::ApiResource>::MODULE) {
+                    routes.push((resource.base.clone(), rocket::routes![
+                        crud_routes::reads, crud_routes::reads_by_ids, crud_routes::schema, crud_routes::stream,
+                        crud_routes::export_csv, crud_routes::export_ndjson, crud_routes::export_arrow, crud_routes::import,
+                        crud_routes::read, crud_routes::post, crud_routes::patch, crud_routes::delete,
+                    ]));
+                }
+                routes.extend(<#model as generic_crud_trait::RelationRoutes<Self>>::relation_routes(paths));
+                routes
+            }
+        }
+
+        mod crud_routes {
+            use super::*;
+
+            #[rocket::get("/?<include>&<ids>&<filter>&<limit>&<offset>")]
+            pub async fn reads(include: Option<&str>, ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<&str>, offset: Option<&str>, tx: Tx<'_, Db>) -> Result<Negotiated<ListResponse<Included<#model>>>, QueryError> {
+                let (limit, offset) = parse_pagination(limit, offset).map_err(QueryError::Invalid)?;
+                #name::reads(include, ids, filter, limit, offset, tx).await
+            }
+
+            #[rocket::post("/_get?<include>", data = "<ids>")]
+            pub async fn reads_by_ids(ids: Negotiated<IdsRequest>, include: Option<&str>, tx: Tx<'_, Db>) -> Result<Negotiated<ByIds<Included<#model>>>, QueryError> {
+                #name::reads_by_ids(ids, include, tx).await
+            }
+
+            #[rocket::get("/_schema")]
+            pub fn schema() -> Negotiated<ResourceSchemas> {
+                #name::schema()
+            }
+
+            #[rocket::get("/_stream?<format>&<ids>&<filter>&<limit>&<offset>")]
+            pub fn stream<'r>(format: Option<StreamFormat>, ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<&str>, offset: Option<&str>, conn: Connection<'r, Db>) -> Result<StreamResponse<'r>, BadRequest<String>> {
+                let (limit, offset) = parse_pagination(limit, offset).map_err(BadRequest)?;
+                #name::stream(format.unwrap_or(StreamFormat::Json), ids, filter, limit, offset, conn)
+            }
+
+            #[rocket::get("/export.csv?<ids>&<filter>&<limit>&<offset>")]
+            pub fn export_csv<'r>(ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<&str>, offset: Option<&str>, conn: Connection<'r, Db>) -> Result<StreamResponse<'r>, BadRequest<String>> {
+                let (limit, offset) = parse_pagination(limit, offset).map_err(BadRequest)?;
+                #name::stream(StreamFormat::Csv, ids, filter, limit, offset, conn)
+            }
+
+            #[rocket::get("/export.ndjson?<ids>&<filter>&<limit>&<offset>")]
+            pub fn export_ndjson<'r>(ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<&str>, offset: Option<&str>, conn: Connection<'r, Db>) -> Result<StreamResponse<'r>, BadRequest<String>> {
+                let (limit, offset) = parse_pagination(limit, offset).map_err(BadRequest)?;
+                #name::stream(StreamFormat::Ndjson, ids, filter, limit, offset, conn)
+            }
+
+            #[rocket::get("/export.arrow?<ids>&<filter>&<limit>&<offset>")]
+            pub fn export_arrow<'r>(ids: Option<&str>, filter: std::collections::BTreeMap<String, String>, limit: Option<&str>, offset: Option<&str>, conn: Connection<'r, Db>) -> Result<StreamResponse<'r>, BadRequest<String>> {
+                let (limit, offset) = parse_pagination(limit, offset).map_err(BadRequest)?;
+                #name::stream(StreamFormat::Arrow, ids, filter, limit, offset, conn)
+            }
+
+            #[rocket::post("/import?<dry_run>", data = "<body>")]
+            pub async fn import(body: ImportBody, dry_run: Option<bool>, tx: Tx<'_, Db>) -> Result<Custom<Negotiated<ImportReport>>, WriteError> {
+                #name::import(body, dry_run, tx).await
+            }
+
+            #[rocket::get("/<obj_id>?<include>")]
+            pub async fn read(obj_id: i32, include: Option<&str>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Included<#model>>>, QueryError> {
+                #name::read(obj_id, include, tx).await
+            }
+
+            #[rocket::post("/", data = "<obj>")]
+            pub async fn post(obj: Negotiated<#post_model>, tx: Tx<'_, Db>, uri: &Origin<'_>, idempotency_key: Option<IdempotencyKey>) -> CreatedResponse<#model> {
+                #name::post(obj, tx, uri, idempotency_key).await
+            }
+
+            #[rocket::patch("/<obj_id>", data = "<obj>")]
+            pub async fn patch(obj_id: i32, obj: Negotiated<#partial_model>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<#model>>, WriteError> {
+                #name::patch(obj_id, obj, tx).await
+            }
+
+            #[rocket::delete("/<obj_id>")]
+            pub async fn delete(obj_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, DeleteError> {
+                #name::delete(obj_id, tx).await
+            }
+        }
+
+        impl generic_crud_trait::ApiOperations for #name {
+            type Resource = #model;
+
+            fn operations(paths: &generic_crud_trait::ApiPaths) -> Vec<generic_crud_trait::ApiOperation> {
+                generic_crud_trait::crud_operations::<#model>(paths)
+            }
+        }
+
+        #[async_trait]
+        impl<Parent> CRUDNestedControllerTrait<Parent, #model, #post_model> for #name
+        where
+            Parent: Send + Sync + 'static,
+            #service: CRUDNestedServiceTrait<Parent, #model, #post_model>,
+        {
+
+            async fn reads_by_parent(parent_id: i32, tx: Tx<'_, Db>) -> Option<Negotiated<Vec<#model>>> {
+                let objs = <#service as CRUDNestedServiceTrait<Parent, #model, #post_model>>::get_all_by_parent(parent_id, &*tx).await;
+                objs.map(|objs| Negotiated(objs))
+            }
+
+            async fn read_by_parent(parent_id: i32, obj_id: i32, tx: Tx<'_, Db>) -> Option<Negotiated<#model>> {
+                let obj = <#service as CRUDNestedServiceTrait<Parent, #model, #post_model>>::get_by_parent(parent_id, obj_id, &*tx).await;
+                obj.map(|obj| Negotiated(obj))
+            }
+
+            async fn post_for_parent(parent_id: i32, obj: Negotiated<#post_model>, tx: Tx<'_, Db>, uri: &Origin<'_>, idempotency_key: Option<IdempotencyKey>) -> Option<CreatedResponse<#model>> {
+                let obj = obj.into_inner();
+                let request = match IdempotentRequest::begin(idempotency_key, uri, &obj, &*tx).await {
+                    Ok(request) => request,
+                    Err(replay) => return Some(replay),
+                };
+                let obj = match <#service as CRUDNestedServiceTrait<Parent, #model, #post_model>>::create_for_parent(parent_id, obj, &*tx).await {
+                    Ok(obj) => obj?,
+                    Err(err) => return Some(Idempotent::Response(Err(err))),
+                };
+                let location = format!("{}/{}", uri.to_string(), obj.obj_id());
+                if let Err(err) = request.complete(Status::Created, Some(&location), &obj, &*tx).await {
+                    return Some(Idempotent::Failed(err));
+                }
+                Some(Idempotent::Response(Ok(Created::new(location).body(Negotiated(obj)))))
+            }
+
+        }
+
+        #[async_trait]
+        impl<M, Key> CRUDUpsertControllerTrait<M, Key, #post_model> for #name
+        where
+            M: NaturalKey<Key = Key> + serde::Serialize + Send + 'static,
+            Key: serde::Serialize + Send + 'static,
+            #service: CRUDUpsertServiceTrait<M, #post_model>,
+        {
+            async fn upsert_by_key(key: Key, obj: Negotiated<Map<String, Value>>, tx: Tx<'_, Db>) -> Result<Custom<Negotiated<M>>, Custom<String>> {
+                // The key from the query takes precedence over the body
+                let mut obj = obj.into_inner();
+                if let Value::Object(key) = serde_json::to_value(key).unwrap() {
+                    obj.extend(key);
+                }
+                let form: #post_model = serde_json::from_value(Value::Object(obj))
+                    .map_err(|err| Custom(Status::UnprocessableEntity, err.to_string()))?;
+
+                let (obj, inserted) = <#service as CRUDUpsertServiceTrait<M, #post_model>>::upsert(form, &*tx).await
+                    .map_err(|err| Custom(err.status(), err.to_string()))?;
+                let status = if inserted { Status::Created } else { Status::Ok };
+                Ok(Custom(status, Negotiated(obj)))
+            }
+
+            async fn upsert_many(objs: Negotiated<Vec<#post_model>>, tx: Tx<'_, Db>) -> Result<Negotiated<Vec<Upserted<M>>>, WriteError> {
+                let objs = <#service as CRUDUpsertServiceTrait<M, #post_model>>::upsert_many(objs.into_inner(), &*tx).await?;
+                Ok(Negotiated(objs.into_iter().map(|(data, inserted)| Upserted { inserted, data }).collect()))
+            }
+        }
+
+        #[async_trait]
+        impl<RelatedEntity, RelatedModel> CRUDManyToManyControllerTrait<RelatedEntity, RelatedModel> for #name
+        where
+            RelatedEntity: Send + Sync + 'static,
+            RelatedModel: serde::Serialize + Send + 'static,
+            #service: CRUDManyToManyServiceTrait<RelatedEntity, RelatedModel>,
+        {
+
+            async fn reads_linked(obj_id: i32, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Vec<RelatedModel>>>, WriteError> {
+                let objs = <#service as CRUDManyToManyServiceTrait<RelatedEntity, RelatedModel>>::get_linked(obj_id, &*tx).await?;
+                Ok(objs.map(|objs| Negotiated(objs)))
+            }
+
+            async fn replace_links(obj_id: i32, related_ids: Negotiated<Vec<i32>>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Vec<RelatedModel>>>, WriteError> {
+                let objs = <#service as CRUDManyToManyServiceTrait<RelatedEntity, RelatedModel>>::replace_links(obj_id, related_ids.into_inner(), &*tx).await?;
+                Ok(objs.map(|objs| Negotiated(objs)))
+            }
+
+            async fn link(obj_id: i32, related_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, WriteError> {
+                let res = <#service as CRUDManyToManyServiceTrait<RelatedEntity, RelatedModel>>::link(obj_id, related_id, &*tx).await?;
+                Ok(res.map(|_res| NoContent))
+            }
+
+            async fn unlink(obj_id: i32, related_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, WriteError> {
+                let res = <#service as CRUDManyToManyServiceTrait<RelatedEntity, RelatedModel>>::unlink(obj_id, related_id, &*tx).await?;
+                Ok(res.map(|_res| NoContent))
+            }
+
+        }
+
+    };
+
+    TokenStream::from(impl_block)
+}
+
+#[proc_macro_derive(CRUDClientImpl, attributes(module, mount))]
+pub fn crud_client(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let module = get_attribute_value(&input, "module").expect("#[module = \"foo\"] attribute not found");
+    let mount = get_attribute_value(&input, "mount").expect("#[mount = \"/api/foos\"] attribute not found");
+    let module_capitalized = heck::AsUpperCamelCase(&module);
+    let models_module = format!("crate::{}::models", module);
+
+    let model = build_type_path(&format!("{}::{}", models_module, module_capitalized));
+    let post_model = build_type_path(&format!("{}::Post{}", models_module, module_capitalized));
+    let partial_model = build_type_path(&format!("{}::Partial{}", models_module, module_capitalized));
+
+    let impl_block = quote! {
+
+        impl #name {
+            pub fn new(api: crate::ApiClient) -> Self {
+                #name { api }
             }
+        }
 
+        impl crate::CRUDClientTrait<#model, #post_model, #partial_model> for #name {
+            const PATH: &'static str = #mount;
+
+            fn api(&self) -> &crate::ApiClient {
+                &self.api
+            }
         }
 
     };
@@ -236,27 +1403,40 @@ pub fn crud_service(input: TokenStream) -> TokenStream {
     let entity_module = format!("crate::entity::{}", module);
 
     let model = build_type_path(&format!("{}::{}", models_module, module_capitalized));
-    let active_model = build_type_path(&format!("{}::ActiveModel", entity_module));
     let model_from_entity = build_type_path(&format!("{}::{}::from_entity", models_module, module_capitalized));
+    let active_model = build_type_path(&format!("{}::ActiveModel", entity_module));
+    let model_before_insert = build_type_path(&format!("{}::{}::before_insert", models_module, module_capitalized));
+    let model_before_update = build_type_path(&format!("{}::{}::before_update", models_module, module_capitalized));
+    let model_before_delete = build_type_path(&format!("{}::{}::before_delete", models_module, module_capitalized));
     let post_model = build_type_path(&format!("{}::Post{}", models_module, module_capitalized));
     let partial_model = build_type_path(&format!("{}::Partial{}", models_module, module_capitalized));
     let entity = build_type_path(&format!("{}::Entity", entity_module));
 
     let impl_block = quote! {
 
-        use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, EntityTrait, IntoActiveModel};
+        use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, IdenStatic, IntoActiveModel, ModelTrait, PaginatorTrait, PrimaryKeyToColumn, PrimaryKeyTrait, QueryFilter, QueryOrder, QueryTrait, Related, TransactionTrait};
+        use sea_orm::{Iterable, QuerySelect, StreamTrait};
+        use rocket::futures::{future, stream, StreamExt};
+        use rocket::futures::stream::BoxStream;
 
-        use generic_crud_trait::FromEntity;
+        use generic_crud_trait::BeforeSave;
+        use generic_crud_trait::{FromEntity, ObjectId};
         use generic_crud_trait::CRUDServiceTrait;
+        use generic_crud_trait::{BelongsTo, CRUDNestedServiceTrait};
+        use generic_crud_trait::{CRUDManyToManyServiceTrait, ManyToMany};
+        use generic_crud_trait::{DeleteError, DeletePolicies, SoftDelete};
+        use generic_crud_trait::{CRUDUpsertServiceTrait, NaturalKey};
         use generic_crud_trait::ToActiveModel;
+        use generic_crud_trait::{CheckParents, ImportError, InvalidValue, WriteError, IMPORT_BATCH_SIZE};
+        use generic_crud_trait::EntityStream;
         use db::Db;
         use #entity as CrudEntity;
 
         #[async_trait]
         impl CRUDServiceTrait<#model, #post_model, #partial_model> for #name {
 
-           async fn get_all(db: &DatabaseConnection) -> Vec<#model> {
-                let objs = CrudEntity::find()
+           async fn get_all<C: ConnectionTrait + TransactionTrait>(db: &C) -> Vec<#model> {
+                let objs = #name::not_deleted(CrudEntity::find())
                     .all(db)
                     .await
                     .unwrap();
@@ -264,8 +1444,41 @@ pub fn crud_service(input: TokenStream) -> TokenStream {
                 objs.into_iter().map(|obj| #model_from_entity(obj)).collect()
             }
 
-            async fn get_by_id(obj_id: i32, db: &DatabaseConnection) -> Option<#model> {
-                let res = CrudEntity::find_by_id(obj_id).one(db).await;
+            async fn get_page<C: ConnectionTrait + TransactionTrait>(condition: sea_orm::Condition, offset: u64, limit: Option<u64>, db: &C) -> Vec<#model> {
+                let mut query = #name::not_deleted(CrudEntity::find())
+                    .filter(condition)
+                    .order_by_asc(Self::id_column())
+                    .offset(offset);
+                if let Some(limit) = limit {
+                    query = query.limit(limit);
+                }
+                let objs = query.all(db)
+                    .await
+                    .unwrap();
+
+                objs.into_iter().map(|obj| #model_from_entity(obj)).collect()
+            }
+
+            fn stream_all<C: ConnectionTrait + StreamTrait>(obj_ids: Option<Vec<i32>>, condition: sea_orm::Condition, offset: u64, limit: Option<u64>, db: &C) -> BoxStream<'_, Result<#model, sea_orm::DbErr>> {
+                Box::pin(Self::stream_entities(obj_ids, condition, offset, limit, db).map(|row| row.map(#model_from_entity)))
+            }
+
+            async fn get_by_ids<C: ConnectionTrait + TransactionTrait>(obj_ids: &[i32], db: &C) -> Vec<#model> {
+                if obj_ids.is_empty() {
+                    return Vec::new();
+                }
+
+                let objs = #name::not_deleted(CrudEntity::find())
+                    .filter(#name::id_column().is_in(obj_ids.iter().copied()))
+                    .all(db)
+                    .await
+                    .unwrap();
+
+                objs.into_iter().map(|obj| #model_from_entity(obj)).collect()
+            }
+
+            async fn get_by_id<C: ConnectionTrait + TransactionTrait>(obj_id: i32, db: &C) -> Option<#model> {
+                let res = #name::not_deleted(CrudEntity::find_by_id(obj_id)).one(db).await;
 
                 match res {
                     Ok(res) => res.map(|obj| #model_from_entity(obj)),
@@ -273,39 +1486,489 @@ pub fn crud_service(input: TokenStream) -> TokenStream {
                 }
             }
 
-            async fn create(form: #post_model, db: &DatabaseConnection) -> #model {
-                let obj = form.into_active_model();
+            async fn create<C: ConnectionTrait + TransactionTrait>(form: #post_model, db: &C) -> Result<#model, WriteError> {
+                let mut obj = form.into_active_model();
+                #model_before_insert(&mut obj);
+                #name::check_parents(&obj, db).await?;
 
-                let obj = obj.insert(db).await.unwrap();
+                let obj = obj.insert(db).await?;
 
-                #model_from_entity(obj)
+                Ok(#model_from_entity(obj))
             }
 
-            async fn update(obj_id: i32, form: #partial_model, db: &DatabaseConnection) -> Option<#model> {
-                let obj = CrudEntity::find_by_id(obj_id).one(db).await.unwrap()?;
+            async fn update<C: ConnectionTrait + TransactionTrait>(obj_id: i32, form: #partial_model, db: &C) -> Result<Option<#model>, WriteError> {
+                let mut obj = form.into_active_model();
+                #model_before_update(&mut obj);
 
-                let obj = form.into_active_model(obj);
+                // Nothing to update, an empty SET clause is not valid SQL
+                if <CrudEntity as EntityTrait>::Column::iter().all(|column| obj.is_not_set(column)) {
+                    return Ok(Self::get_by_id(obj_id, db).await);
+                }
+                #name::check_parents(&obj, db).await?;
 
-                let obj = obj.update(db).await.unwrap();
+                // Only the fields sent in the request are set
+                let mut update = #name::not_deleted(CrudEntity::update_many().set(obj))
+                    .filter(#name::id_column().eq(obj_id))
+                    .into_query();
+                update.returning(generic_crud_trait::returning_all::<CrudEntity>());
 
-                Some(#model_from_entity(obj))
+                let obj = CrudEntity::find()
+                    .from_raw_sql(db.get_database_backend().build(&update))
+                    .one(db)
+                    .await?;
+
+                Ok(obj.map(|obj| #model_from_entity(obj)))
+            }
+
+            async fn delete<C: ConnectionTrait + TransactionTrait>(obj_id: i32, db: &C) -> Result<Option<()>, DeleteError> {
+                // Dropping the transaction on an early return rolls it back
+                let txn = db.begin().await?;
+
+                #model_before_delete(obj_id, &txn).await?;
+
+                let statement = match <#model as SoftDelete>::deleted_at_column() {
+                    Some(deleted_at) => {
+                        let mut update = #name::not_deleted(CrudEntity::update_many())
+                            .col_expr(deleted_at, sea_orm::sea_query::Expr::current_timestamp())
+                            .filter(#name::id_column().eq(obj_id))
+                            .into_query();
+                        update.returning(generic_crud_trait::returning_all::<CrudEntity>());
+                        txn.get_database_backend().build(&update)
+                    }
+                    None => {
+                        let mut delete = CrudEntity::delete_many()
+                            .filter(#name::id_column().eq(obj_id))
+                            .into_query();
+                        delete.returning(generic_crud_trait::returning_all::<CrudEntity>());
+                        txn.get_database_backend().build(&delete)
+                    }
+                };
+                let obj = CrudEntity::find()
+                    .from_raw_sql(statement)
+                    .one(&txn)
+                    .await?;
+                if obj.is_none() {
+                    return Ok(None);
+                }
+
+                txn.commit().await?;
+
+                Ok(Some(()))
+            }
+
+            async fn create_many<C: ConnectionTrait + TransactionTrait>(forms: Vec<(usize, #post_model)>, db: &C) -> Result<Vec<ImportError>, WriteError> {
+                let mut errors = vec![];
+                // A multi-row INSERT sets the same columns in every row, a batch ends at the first row setting other columns
+                // so that the rows are still inserted in the order of the body
+                let mut batches: Vec<(Vec<bool>, Vec<(usize, #active_model)>)> = vec![];
+                for (line, form) in forms {
+                    let mut obj = form.into_active_model();
+                    #model_before_insert(&mut obj);
+                    match Self::check_parents(&obj, db).await {
+                        Ok(()) => {}
+                        Err(WriteError::Invalid(invalid)) => {
+                            errors.push(ImportError { line, error: invalid.error, field: invalid.field });
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    }
+                    let columns: Vec<bool> = <CrudEntity as EntityTrait>::Column::iter()
+                        .map(|column| !obj.is_not_set(column))
+                        .collect();
+                    match batches.last_mut() {
+                        Some((batch_columns, batch)) if *batch_columns == columns && batch.len() < IMPORT_BATCH_SIZE => batch.push((line, obj)),
+                        _ => batches.push((columns, vec![(line, obj)])),
+                    }
+                }
+
+                for (_columns, batch) in &batches {
+                    let savepoint = db.begin().await?;
+                    let res = CrudEntity::insert_many(batch.iter().map(|(_line, obj)| obj.clone()))
+                        .exec(&savepoint)
+                        .await;
+                    if res.is_ok() {
+                        savepoint.commit().await?;
+                        continue;
+                    }
+                    savepoint.rollback().await?;
+
+                    // Insert the rows of a failed batch one by one to find the failing ones
+                    for (line, obj) in batch {
+                        let savepoint = db.begin().await?;
+                        match obj.clone().insert(&savepoint).await {
+                            Ok(_obj) => savepoint.commit().await?,
+                            Err(err) => {
+                                savepoint.rollback().await?;
+                                errors.push(ImportError { line: *line, error: err.to_string(), field: None });
+                            }
+                        }
+                    }
+                }
+                Ok(errors)
+            }
+
+        }
+
+        impl EntityStream for #name {
+            type Entity = CrudEntity;
+
+            fn stream_entities<C: ConnectionTrait + StreamTrait>(obj_ids: Option<Vec<i32>>, condition: sea_orm::Condition, offset: u64, limit: Option<u64>, db: &C) -> BoxStream<'_, Result<<CrudEntity as EntityTrait>::Model, sea_orm::DbErr>> {
+                let mut query = #name::not_deleted(CrudEntity::find())
+                    .filter(condition)
+                    .order_by_asc(#name::id_column())
+                    .offset(offset);
+                if let Some(obj_ids) = obj_ids {
+                    query = query.filter(#name::id_column().is_in(obj_ids));
+                }
+                if let Some(limit) = limit {
+                    query = query.limit(limit);
+                }
+                let rows = stream::once(async move { query.stream(db).await })
+                    .map(|rows| match rows {
+                        Ok(rows) => rows.left_stream(),
+                        Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+                    })
+                    .flatten();
+
+                // The stream ends with the first database error
+                let objs = rows.scan(false, |failed, row| {
+                    if *failed {
+                        return future::ready(None);
+                    }
+                    if let Err(err) = &row {
+                        error!("Cannot stream {}: {}", #module, err);
+                        *failed = true;
+                    }
+                    future::ready(Some(row))
+                });
+                Box::pin(objs)
+            }
+        }
+
+        impl #name {
+            fn id_column() -> <CrudEntity as EntityTrait>::Column {
+                <CrudEntity as EntityTrait>::PrimaryKey::iter().next().unwrap().into_column()
+            }
+
+            /// Update the columns set by `obj` on conflict, except the natural key and the insert only columns
+            fn upsert_on_conflict<M: NaturalKey<Column = <CrudEntity as EntityTrait>::Column>>(obj: &#active_model) -> sea_orm::sea_query::OnConflict {
+                let key_columns = M::key_columns();
+                let insert_only_columns = M::insert_only_columns();
+                let skipped: Vec<&str> = key_columns.iter()
+                    .chain(insert_only_columns.iter())
+                    .map(|column| column.as_str())
+                    .collect();
+                let mut update_columns: Vec<_> = <CrudEntity as EntityTrait>::Column::iter()
+                    .filter(|column| !obj.is_not_set(*column) && !skipped.contains(&column.as_str()))
+                    .collect();
+                // DO UPDATE needs a column to set so that the existing row is returned
+                if update_columns.is_empty() {
+                    update_columns = key_columns.clone();
+                }
+                sea_orm::sea_query::OnConflict::columns(key_columns)
+                    .update_columns(update_columns)
+                    .to_owned()
+            }
+
+            /// Reject a `#[belongsTo]` field set to a missing parent, naming the field
+            async fn check_parents<C: ConnectionTrait>(obj: &#active_model, db: &C) -> Result<(), WriteError> {
+                match <#model as CheckParents<#active_model>>::missing_parent(obj, db).await? {
+                    Some(field) => Err(WriteError::Invalid(InvalidValue::missing_parent(field))),
+                    None => Ok(()),
+                }
+            }
+
+            /// Filter out the soft deleted rows
+            fn not_deleted<Q: QueryFilter>(query: Q) -> Q {
+                match <#model as SoftDelete>::deleted_at_column() {
+                    Some(deleted_at) => query.filter(deleted_at.is_null()),
+                    None => query,
+                }
             }
+        }
+
+        #[async_trait]
+        impl<M> CRUDUpsertServiceTrait<M, #post_model> for #name
+        where
+            M: NaturalKey<Column = <CrudEntity as EntityTrait>::Column>
+                + FromEntity<<CrudEntity as EntityTrait>::Model>
+                + BeforeSave<#active_model>
+                + Send
+                + 'static,
+        {
+            async fn upsert<C: ConnectionTrait + TransactionTrait>(form: #post_model, db: &C) -> Result<(M, bool), WriteError> {
+                let mut obj = form.into_active_model();
+                M::before_insert(&mut obj);
+                #name::check_parents(&obj, db).await?;
+
+                let on_conflict = #name::upsert_on_conflict::<M>(&obj);
+                let mut insert = CrudEntity::insert(obj)
+                    .on_conflict(on_conflict)
+                    .into_query();
+                let mut returning = generic_crud_trait::returning_columns::<CrudEntity>();
+                // xmax is only set on the rows updated by ON CONFLICT
+                returning.push(sea_orm::sea_query::Expr::cust("(xmax = 0) AS inserted"));
+                insert.returning(sea_orm::sea_query::Query::returning().exprs(returning));
+
+                // DO UPDATE always returns the row
+                let row = db.query_one(db.get_database_backend().build(&insert)).await?
+                    .ok_or_else(|| WriteError::Database("The upsert returned no row".to_owned()))?;
+                let obj = <<CrudEntity as EntityTrait>::Model as sea_orm::FromQueryResult>::from_query_result(&row, "")?;
+                let inserted: bool = row.try_get("", "inserted")?;
+
+                Ok((M::from_entity(obj), inserted))
+            }
+
+            async fn upsert_many<C: ConnectionTrait + TransactionTrait>(forms: Vec<#post_model>, db: &C) -> Result<Vec<(M, bool)>, WriteError> {
+                // The values of the key columns, as text to compare them
+                let key_of = |values: Vec<Option<sea_orm::Value>>| format!("{:?}", values);
+
+                // Dropping the transaction on an early return rolls it back
+                let txn = db.begin().await?;
+
+                // A multi-row INSERT sets the same columns in every row, a batch ends at the first row setting other columns
+                let mut keys: std::collections::HashMap<String, usize> = Default::default();
+                let mut batches: Vec<(Vec<bool>, Vec<(String, #active_model)>)> = vec![];
+                for (index, form) in forms.into_iter().enumerate() {
+                    let mut obj = form.into_active_model();
+                    M::before_insert(&mut obj);
+                    #name::check_parents(&obj, &txn).await?;
+
+                    // ON CONFLICT DO UPDATE can't update the same row twice in a statement
+                    let key = key_of(M::key_columns().into_iter().map(|column| obj.get(column).into_value()).collect());
+                    if let Some(first) = keys.insert(key.clone(), index) {
+                        return Err(WriteError::Invalid(InvalidValue {
+                            error: format!("The objects {} and {} have the same natural key", first, index),
+                            constraint: None,
+                            field: None,
+                        }));
+                    }
+
+                    let columns: Vec<bool> = <CrudEntity as EntityTrait>::Column::iter()
+                        .map(|column| !obj.is_not_set(column))
+                        .collect();
+                    match batches.last_mut() {
+                        Some((batch_columns, batch)) if *batch_columns == columns && batch.len() < IMPORT_BATCH_SIZE => batch.push((key, obj)),
+                        _ => batches.push((columns, vec![(key, obj)])),
+                    }
+                }
+
+                let mut objs: Vec<Option<(M, bool)>> = std::iter::repeat_with(|| None).take(keys.len()).collect();
+                for (_columns, batch) in batches {
+                    let (batch_keys, batch): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+                    let on_conflict = #name::upsert_on_conflict::<M>(&batch[0]);
+                    let mut insert = CrudEntity::insert_many(batch)
+                        .on_conflict(on_conflict)
+                        .into_query();
+                    let mut returning = generic_crud_trait::returning_columns::<CrudEntity>();
+                    // xmax is only set on the rows updated by ON CONFLICT
+                    returning.push(sea_orm::sea_query::Expr::cust("(xmax = 0) AS inserted"));
+                    insert.returning(sea_orm::sea_query::Query::returning().exprs(returning));
+
+                    // The returned rows are matched to the objects by their key, RETURNING having no defined order
+                    let rows = txn.query_all(txn.get_database_backend().build(&insert)).await?;
+                    if rows.len() != batch_keys.len() {
+                        return Err(WriteError::Database("The upsert didn't return a row for every object".to_owned()));
+                    }
+                    for row in rows {
+                        let obj = <<CrudEntity as EntityTrait>::Model as sea_orm::FromQueryResult>::from_query_result(&row, "")?;
+                        let inserted: bool = row.try_get("", "inserted")?;
+                        let key = key_of(M::key_columns().into_iter().map(|column| Some(obj.get(column))).collect());
+                        let index = keys.get(&key)
+                            .ok_or_else(|| WriteError::Database(format!("The upsert returned an unexpected key {}", key)))?;
+                        objs[*index] = Some((M::from_entity(obj), inserted));
+                    }
+                }
+
+                txn.commit().await?;
+
+                objs.into_iter()
+                    .map(|obj| obj.ok_or_else(|| WriteError::Database("The upsert didn't return a row for every object".to_owned())))
+                    .collect()
+            }
+        }
+
+        #[async_trait]
+        impl<Parent> CRUDNestedServiceTrait<Parent, #model, #post_model> for #name
+        where
+            Parent: EntityTrait + Related<CrudEntity>,
+            <Parent::PrimaryKey as PrimaryKeyTrait>::ValueType: From<i32>,
+            #model: BelongsTo<Parent, Column = <CrudEntity as EntityTrait>::Column>,
+        {
+
+            async fn get_all_by_parent<C: ConnectionTrait + TransactionTrait>(parent_id: i32, db: &C) -> Option<Vec<#model>> {
+                let parent = Parent::find_by_id(parent_id.into()).one(db).await.unwrap()?;
 
-            async fn delete(obj_id: i32, db: &DatabaseConnection) -> Option<()> {
-                let obj: #active_model = CrudEntity::find_by_id(obj_id)
+                let objs = #name::not_deleted(parent.find_related(CrudEntity))
+                    .all(db)
+                    .await
+                    .unwrap();
+
+                Some(objs.into_iter().map(|obj| #model_from_entity(obj)).collect())
+            }
+
+            async fn get_by_parent<C: ConnectionTrait + TransactionTrait>(parent_id: i32, obj_id: i32, db: &C) -> Option<#model> {
+                let parent_column = <#model as BelongsTo<Parent>>::parent_column();
+
+                let obj = #name::not_deleted(CrudEntity::find_by_id(obj_id))
+                    .filter(parent_column.eq(parent_id))
                     .one(db)
                     .await
-                    .expect(&format!("Cannot find {} with the specified ID.", #module))
-                    .map(Into::into)?;
+                    .unwrap()?;
+
+                Some(#model_from_entity(obj))
+            }
+
+            async fn create_for_parent<C: ConnectionTrait + TransactionTrait>(parent_id: i32, form: #post_model, db: &C) -> Result<Option<#model>, WriteError> {
+                if Parent::find_by_id(parent_id.into()).one(db).await?.is_none() {
+                    return Ok(None);
+                }
+
+                let mut obj = form.into_active_model();
+                obj.set(<#model as BelongsTo<Parent>>::parent_column(), parent_id.into());
+                #model_before_insert(&mut obj);
+                #name::check_parents(&obj, db).await?;
+
+                let obj = obj.insert(db).await?;
 
-                obj.delete(db).await
-                    .map(|_| ())
-                    .map_err(|err|
-                        rocket::response::status::NotFound(format!("Cannot delete {}: {}", #module, err))
+                Ok(Some(#model_from_entity(obj)))
+            }
+
+        }
+
+        #[async_trait]
+        impl<RelatedEntity, RelatedModel> CRUDManyToManyServiceTrait<RelatedEntity, RelatedModel> for #name
+        where
+            RelatedEntity: EntityTrait,
+            RelatedEntity::Model: Sync,
+            RelatedModel: FromEntity<RelatedEntity::Model> + ObjectId + Send,
+            CrudEntity: Related<RelatedEntity>,
+            #model: ManyToMany<RelatedEntity, RelatedModel = RelatedModel>,
+            <<#model as ManyToMany<RelatedEntity>>::Via as EntityTrait>::Model:
+                IntoActiveModel<<#model as ManyToMany<RelatedEntity>>::ViaActiveModel>,
+        {
+
+            async fn get_linked<C: ConnectionTrait + TransactionTrait>(obj_id: i32, db: &C) -> Result<Option<Vec<RelatedModel>>, WriteError> {
+                let obj = match CrudEntity::find_by_id(obj_id).one(db).await? {
+                    Some(obj) => obj,
+                    None => return Ok(None),
+                };
+
+                let objs = obj.find_related(RelatedEntity::default())
+                    .all(db)
+                    .await?;
+
+                Ok(Some(objs.into_iter().map(|obj| RelatedModel::from_entity(obj)).collect()))
+            }
+
+            async fn replace_links<C: ConnectionTrait + TransactionTrait>(obj_id: i32, related_ids: Vec<i32>, db: &C) -> Result<Option<Vec<RelatedModel>>, WriteError> {
+                let (own_column, related_column) = <#model as ManyToMany<RelatedEntity>>::via_columns();
+                let mut related_ids = related_ids;
+                related_ids.sort_unstable();
+                related_ids.dedup();
+
+                // Dropping the transaction on an early return rolls it back
+                let txn = db.begin().await?;
+
+                let obj = match CrudEntity::find_by_id(obj_id).one(&txn).await? {
+                    Some(obj) => obj,
+                    None => return Ok(None),
+                };
+
+                let found: std::collections::HashSet<i32> = RelatedEntity::find()
+                    .filter(#name::related_pk::<RelatedEntity>()?.is_in(related_ids.clone()))
+                    .all(&txn)
+                    .await?
+                    .into_iter()
+                    .map(|obj| RelatedModel::from_entity(obj).obj_id())
+                    .collect();
+                let unknown: Vec<String> = related_ids.iter()
+                    .filter(|related_id| !found.contains(related_id))
+                    .map(|related_id| related_id.to_string())
+                    .collect();
+                if !unknown.is_empty() {
+                    return Err(WriteError::Invalid(InvalidValue {
+                        error: format!("Unknown related ids: {}", unknown.join(", ")),
+                        constraint: None,
+                        field: None,
+                    }));
+                }
+
+                <#model as ManyToMany<RelatedEntity>>::Via::delete_many()
+                    .filter(own_column.eq(obj_id))
+                    .exec(&txn)
+                    .await?;
+
+                if !related_ids.is_empty() {
+                    let links = related_ids.into_iter().map(|related_id| {
+                        let mut link = <#model as ManyToMany<RelatedEntity>>::ViaActiveModel::default();
+                        link.set(own_column, obj_id.into());
+                        link.set(related_column, related_id.into());
+                        link
+                    });
+                    <#model as ManyToMany<RelatedEntity>>::Via::insert_many(links)
+                        .exec_without_returning(&txn)
+                        .await?;
+                }
+
+                let objs = obj.find_related(RelatedEntity::default())
+                    .all(&txn)
+                    .await?;
+
+                txn.commit().await?;
+
+                Ok(Some(objs.into_iter().map(|obj| RelatedModel::from_entity(obj)).collect()))
+            }
+
+            async fn link<C: ConnectionTrait + TransactionTrait>(obj_id: i32, related_id: i32, db: &C) -> Result<Option<()>, WriteError> {
+                let (own_column, related_column) = <#model as ManyToMany<RelatedEntity>>::via_columns();
+
+                let related_pk = #name::related_pk::<RelatedEntity>()?;
+                if CrudEntity::find_by_id(obj_id).one(db).await?.is_none()
+                    || RelatedEntity::find().filter(related_pk.eq(related_id)).one(db).await?.is_none()
+                {
+                    return Ok(None);
+                }
+
+                let mut link = <#model as ManyToMany<RelatedEntity>>::ViaActiveModel::default();
+                link.set(own_column, obj_id.into());
+                link.set(related_column, related_id.into());
+
+                // Linking twice is a no-op
+                <#model as ManyToMany<RelatedEntity>>::Via::insert(link)
+                    .on_conflict(
+                        sea_orm::sea_query::OnConflict::columns([own_column, related_column])
+                            .do_nothing()
+                            .to_owned()
                     )
-                    .ok()
+                    .exec_without_returning(db)
+                    .await?;
+
+                Ok(Some(()))
             }
 
+            async fn unlink<C: ConnectionTrait + TransactionTrait>(obj_id: i32, related_id: i32, db: &C) -> Result<Option<()>, WriteError> {
+                let (own_column, related_column) = <#model as ManyToMany<RelatedEntity>>::via_columns();
+
+                let res = <#model as ManyToMany<RelatedEntity>>::Via::delete_many()
+                    .filter(own_column.eq(obj_id))
+                    .filter(related_column.eq(related_id))
+                    .exec(db)
+                    .await?;
+
+                Ok((res.rows_affected > 0).then_some(()))
+            }
+
+        }
+
+        impl #name {
+            /// Primary key column of a related entity
+            fn related_pk<RelatedEntity: EntityTrait>() -> Result<RelatedEntity::Column, WriteError> {
+                RelatedEntity::PrimaryKey::iter()
+                    .next()
+                    .map(|pk| pk.into_column())
+                    .ok_or_else(|| WriteError::Database("The related entity has no primary key".to_owned()))
+            }
         }
 
     };
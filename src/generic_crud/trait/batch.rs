@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{self, Value};
+use sea_orm::DatabaseTransaction;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{CRUDServiceTrait, DeleteError, WriteError};
+
+/// An operation of a `POST /api/_batch` request.
+///
+/// Any `{"$ref": "<index>.<field>"}` object in `id` or `body` is replaced by the field of
+/// the result of an earlier operation, e.g. `"owner_id": {"$ref": "0.id"}`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperation {
+    Create { resource: String, body: Value },
+    Update { resource: String, id: Value, body: Value },
+    Delete { resource: String, id: Value },
+}
+
+/// The outcome of a batch operation.
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchResult {
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchResult {
+    fn ok(status: Status, body: Option<Value>) -> Self {
+        BatchResult { status: status.code, body, error: None }
+    }
+
+    fn err(status: Status, error: String) -> Self {
+        BatchResult { status: status.code, body: None, error: Some(error) }
+    }
+
+    /// The violated constraint is the body of a 409 or 422
+    fn write_err(err: WriteError) -> Self {
+        let body = match &err {
+            WriteError::Duplicate(duplicate) => Some(serde_json::to_value(duplicate).unwrap()),
+            WriteError::Invalid(invalid) => Some(serde_json::to_value(invalid).unwrap()),
+            WriteError::Database(_) => None,
+        };
+        BatchResult { status: err.status().code, body, error: Some(err.to_string()) }
+    }
+}
+
+/// A CRUD resource usable in batch operations, with JSON in and out.
+#[async_trait]
+pub trait BatchResource: Send + Sync {
+    async fn create(&self, body: Value, txn: &DatabaseTransaction) -> BatchResult;
+    async fn update(&self, obj_id: i32, body: Value, txn: &DatabaseTransaction) -> BatchResult;
+    async fn delete(&self, obj_id: i32, txn: &DatabaseTransaction) -> BatchResult;
+}
+
+/// [`BatchResource`] calling the [`CRUDServiceTrait`] implementation of a service.
+#[allow(clippy::type_complexity)]
+pub struct CrudResource<Service, Model, CreateModel, PartialModel>(
+    PhantomData<fn() -> (Service, Model, CreateModel, PartialModel)>,
+);
+
+#[async_trait]
+impl<Service, Model, CreateModel, PartialModel> BatchResource for CrudResource<Service, Model, CreateModel, PartialModel>
+where
+    Service: CRUDServiceTrait<Model, CreateModel, PartialModel>,
+    Model: Serialize + Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+    PartialModel: DeserializeOwned + Send + 'static,
+{
+    async fn create(&self, body: Value, txn: &DatabaseTransaction) -> BatchResult {
+        let form: CreateModel = match serde_json::from_value(body) {
+            Ok(form) => form,
+            Err(err) => return BatchResult::err(Status::UnprocessableEntity, err.to_string()),
+        };
+        match Service::create(form, txn).await {
+            Ok(obj) => BatchResult::ok(Status::Created, Some(serde_json::to_value(obj).unwrap())),
+            Err(err) => BatchResult::write_err(err),
+        }
+    }
+
+    async fn update(&self, obj_id: i32, body: Value, txn: &DatabaseTransaction) -> BatchResult {
+        let form: PartialModel = match serde_json::from_value(body) {
+            Ok(form) => form,
+            Err(err) => return BatchResult::err(Status::UnprocessableEntity, err.to_string()),
+        };
+        match Service::update(obj_id, form, txn).await {
+            Ok(Some(obj)) => BatchResult::ok(Status::Ok, Some(serde_json::to_value(obj).unwrap())),
+            Ok(None) => BatchResult::err(Status::NotFound, format!("No object with id {}", obj_id)),
+            Err(err) => BatchResult::write_err(err),
+        }
+    }
+
+    async fn delete(&self, obj_id: i32, txn: &DatabaseTransaction) -> BatchResult {
+        match Service::delete(obj_id, txn).await {
+            Ok(Some(())) => BatchResult::ok(Status::NoContent, None),
+            Ok(None) => BatchResult::err(Status::NotFound, format!("No object with id {}", obj_id)),
+            Err(DeleteError::Blocked(blocking)) => BatchResult {
+                status: Status::Conflict.code,
+                body: Some(serde_json::to_value(blocking).unwrap()),
+                error: Some("The object has children preventing its deletion".to_owned()),
+            },
+            Err(DeleteError::Write(err)) => BatchResult::write_err(err),
+        }
+    }
+}
+
+/// The resources available to `POST /api/_batch`, by name.
+#[derive(Default)]
+pub struct BatchRegistry {
+    resources: HashMap<String, Box<dyn BatchResource>>,
+}
+
+impl BatchRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the service of a CRUD resource, e.g. `.resource::<CarService, _, _, _>("cars")`.
+    pub fn resource<Service, Model, CreateModel, PartialModel>(mut self, name: &str) -> Self
+    where
+        CrudResource<Service, Model, CreateModel, PartialModel>: BatchResource + 'static,
+    {
+        self.resources.insert(name.to_owned(), Box::new(CrudResource::<Service, Model, CreateModel, PartialModel>(PhantomData)));
+        self
+    }
+
+    /// Run the operations in order, stopping at the first failure.
+    ///
+    /// Returns the status of the batch, 200 when every operation succeeded or the status
+    /// of the failed one, along with the results of the executed operations.
+    pub async fn execute(&self, operations: Vec<BatchOperation>, txn: &DatabaseTransaction) -> (Status, Vec<BatchResult>) {
+        let mut results: Vec<BatchResult> = Vec::with_capacity(operations.len());
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let result = match self.execute_one(operation, &results, txn).await {
+                Ok(result) => result,
+                Err(error) => BatchResult::err(Status::BadRequest, format!("Operation {}: {}", index, error)),
+            };
+            let failed = !(200..300).contains(&result.status);
+            let status = result.status;
+            results.push(result);
+            if failed {
+                return (Status::from_code(status).unwrap_or(Status::BadRequest), results);
+            }
+        }
+
+        (Status::Ok, results)
+    }
+
+    async fn execute_one(&self, operation: BatchOperation, results: &[BatchResult], txn: &DatabaseTransaction) -> Result<BatchResult, String> {
+        let resource = |name: &str| self.resources.get(name)
+            .ok_or_else(|| format!("Unknown resource `{}`", name));
+
+        Ok(match operation {
+            BatchOperation::Create { resource: name, body } => {
+                let body = resolve_references(body, results)?;
+                resource(&name)?.create(body, txn).await
+            }
+            BatchOperation::Update { resource: name, id, body } => {
+                let obj_id = parse_id(resolve_references(id, results)?)?;
+                let body = resolve_references(body, results)?;
+                resource(&name)?.update(obj_id, body, txn).await
+            }
+            BatchOperation::Delete { resource: name, id } => {
+                let obj_id = parse_id(resolve_references(id, results)?)?;
+                resource(&name)?.delete(obj_id, txn).await
+            }
+        })
+    }
+}
+
+fn parse_id(id: Value) -> Result<i32, String> {
+    id.as_i64()
+        .and_then(|id| i32::try_from(id).ok())
+        .ok_or_else(|| format!("Invalid id {}", id))
+}
+
+/// Replace the `{"$ref": "<index>.<field>"}` objects of `value` by the referenced values.
+///
+/// Strings are never references, so that values starting with a `$` are sent as they are.
+fn resolve_references(value: Value, results: &[BatchResult]) -> Result<Value, String> {
+    match value {
+        Value::Object(map) if map.len() == 1 && map.contains_key("$ref") => {
+            let reference = match &map["$ref"] {
+                Value::String(reference) => reference,
+                reference => return Err(format!("Invalid reference {}", reference)),
+            };
+            let (index, path) = reference.split_once('.')
+                .ok_or_else(|| format!("Invalid reference `{}`", reference))?;
+            let result = index.parse::<usize>().ok()
+                .and_then(|index| results.get(index))
+                .ok_or_else(|| format!("Reference `{}` to an unknown operation", reference))?;
+            let mut value = result.body.as_ref();
+            for key in path.split('.') {
+                value = value.and_then(|value| value.get(key));
+            }
+            value.cloned().ok_or_else(|| format!("Reference `{}` to an unknown field", reference))
+        }
+        Value::Array(values) => values.into_iter()
+            .map(|value| resolve_references(value, results))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Value::Object(map) => map.into_iter()
+            .map(|(key, value)| resolve_references(value, results).map(|value| (key, value)))
+            .collect::<Result<_, _>>()
+            .map(Value::Object),
+        value => Ok(value),
+    }
+}
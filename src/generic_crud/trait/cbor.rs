@@ -0,0 +1,73 @@
+//! CBOR encoding of JSON values with `ciborium`, see <https://www.rfc-editor.org/rfc/rfc8949>.
+
+use std::io;
+
+use ciborium::de::Error;
+use ciborium::value::Value as Cbor;
+use rocket::serde::json::serde_json::{Map, Number, Value};
+
+/// Deepest nesting of arrays and maps accepted by [`decode`].
+const MAX_DEPTH: usize = 128;
+
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut bytes = vec![];
+    // Writing to a vector cannot fail
+    ciborium::into_writer(value, &mut bytes).unwrap();
+    bytes
+}
+
+/// Decode a single CBOR item, byte strings being decoded as arrays of bytes and tags being ignored.
+pub fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = bytes;
+    let value: Cbor = ciborium::de::from_reader_with_recursion_limit(&mut reader, MAX_DEPTH).map_err(error)?;
+    if !reader.is_empty() {
+        return Err(format!("Trailing bytes at offset {}", bytes.len() - reader.len()));
+    }
+    json(value)
+}
+
+fn json(value: Cbor) -> Result<Value, String> {
+    Ok(match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(value) => Value::Bool(value),
+        Cbor::Integer(value) => {
+            let value = i128::from(value);
+            u64::try_from(value).map(Value::from)
+                .or_else(|_err| i64::try_from(value).map(Value::from))
+                .unwrap_or_else(|_err| float(value as f64))
+        }
+        Cbor::Float(value) => float(value),
+        Cbor::Text(value) => Value::String(value),
+        Cbor::Bytes(value) => Value::from(value),
+        Cbor::Tag(_tag, value) => json(*value)?,
+        Cbor::Array(values) => Value::Array(values.into_iter().map(json).collect::<Result<_, _>>()?),
+        Cbor::Map(entries) => {
+            let mut values = Map::new();
+            for (key, value) in entries {
+                let key = match key {
+                    Cbor::Text(key) => key,
+                    key => return Err(format!("Expected a text key, found {:?}", key)),
+                };
+                values.insert(key, json(value)?);
+            }
+            Value::Object(values)
+        }
+        value => return Err(format!("Unsupported item {:?}", value)),
+    })
+}
+
+fn error(err: Error<io::Error>) -> String {
+    match err {
+        Error::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => "Unexpected end of input".to_owned(),
+        Error::Io(err) => err.to_string(),
+        Error::Syntax(offset) => format!("Invalid item at offset {}", offset),
+        Error::Semantic(Some(offset), message) => format!("{} at offset {}", message, offset),
+        Error::Semantic(None, message) => message,
+        Error::RecursionLimitExceeded => "Too deeply nested".to_owned(),
+    }
+}
+
+/// NaN and infinite floats, which JSON cannot represent, are decoded as null.
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rocket::futures::stream::{self, BoxStream};
+use rocket::futures::StreamExt;
+use rocket::serde::json::serde_json::{self, Value};
+use sea_orm::{ColumnType, Condition, ConnectionTrait, DbErr, EntityTrait, StreamTrait};
+use serde::Serialize;
+
+use crate::FieldNames;
+
+/// Number of rows of each record batch of an Arrow file.
+pub const ARROW_BATCH_SIZE: usize = 1024;
+
+/// Implemented by the services, streams the rows of the resource with the given ids and matching `condition` as entity models,
+/// ordered by id and skipping the first `offset` ones.
+///
+/// A database error is the last item of the stream.
+pub trait EntityStream {
+    type Entity: EntityTrait;
+
+    fn stream_entities<C: ConnectionTrait + StreamTrait>(obj_ids: Option<Vec<i32>>, condition: Condition, offset: u64, limit: Option<u64>, db: &C) -> BoxStream<'_, Result<<Self::Entity as EntityTrait>::Model, DbErr>>;
+}
+
+/// Arrow type of an entity column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArrowType {
+    Bool,
+    Int { bit_width: u8, signed: bool },
+    Float32,
+    Float64,
+    Utf8,
+    /// Days since the epoch
+    Date32,
+    /// Microseconds since the epoch, in UTC when `utc` is set
+    Timestamp { utc: bool },
+}
+
+impl ArrowType {
+    /// Columns of an unsupported type are exported as their JSON representation.
+    pub fn of(column_type: &ColumnType) -> ArrowType {
+        match column_type {
+            ColumnType::Boolean => ArrowType::Bool,
+            ColumnType::TinyInteger => ArrowType::Int { bit_width: 8, signed: true },
+            ColumnType::SmallInteger => ArrowType::Int { bit_width: 16, signed: true },
+            ColumnType::Integer => ArrowType::Int { bit_width: 32, signed: true },
+            ColumnType::BigInteger => ArrowType::Int { bit_width: 64, signed: true },
+            ColumnType::TinyUnsigned => ArrowType::Int { bit_width: 8, signed: false },
+            ColumnType::SmallUnsigned => ArrowType::Int { bit_width: 16, signed: false },
+            ColumnType::Unsigned => ArrowType::Int { bit_width: 32, signed: false },
+            ColumnType::BigUnsigned => ArrowType::Int { bit_width: 64, signed: false },
+            ColumnType::Float => ArrowType::Float32,
+            ColumnType::Double => ArrowType::Float64,
+            ColumnType::Date => ArrowType::Date32,
+            ColumnType::DateTime | ColumnType::Timestamp => ArrowType::Timestamp { utc: false },
+            ColumnType::TimestampWithTimeZone => ArrowType::Timestamp { utc: true },
+            _ => ArrowType::Utf8,
+        }
+    }
+
+    /// The type of a field without a column, from its JSON Schema.
+    pub fn of_schema(schema: &Value) -> ArrowType {
+        let format = schema.get("format").and_then(Value::as_str);
+        match (schema.get("type").and_then(Value::as_str), format) {
+            (Some("boolean"), _) => ArrowType::Bool,
+            (Some("integer"), Some("int32")) => ArrowType::Int { bit_width: 32, signed: true },
+            (Some("integer"), _) => ArrowType::Int { bit_width: 64, signed: true },
+            (Some("number"), Some("float")) => ArrowType::Float32,
+            (Some("number"), _) => ArrowType::Float64,
+            (Some("string"), Some("date")) => ArrowType::Date32,
+            (Some("string"), Some("date-time")) => ArrowType::Timestamp { utc: true },
+            _ => ArrowType::Utf8,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            ArrowType::Bool => DataType::Boolean,
+            ArrowType::Int { bit_width: 8, signed: true } => DataType::Int8,
+            ArrowType::Int { bit_width: 16, signed: true } => DataType::Int16,
+            ArrowType::Int { bit_width: 32, signed: true } => DataType::Int32,
+            ArrowType::Int { signed: true, .. } => DataType::Int64,
+            ArrowType::Int { bit_width: 8, signed: false } => DataType::UInt8,
+            ArrowType::Int { bit_width: 16, signed: false } => DataType::UInt16,
+            ArrowType::Int { bit_width: 32, signed: false } => DataType::UInt32,
+            ArrowType::Int { signed: false, .. } => DataType::UInt64,
+            ArrowType::Float32 => DataType::Float32,
+            ArrowType::Float64 => DataType::Float64,
+            ArrowType::Utf8 => DataType::Utf8,
+            ArrowType::Date32 => DataType::Date32,
+            ArrowType::Timestamp { utc } => DataType::Timestamp(TimeUnit::Microsecond, utc.then(|| "UTC".into())),
+        }
+    }
+
+    /// An array of the serialized field values, converted to this type.
+    fn array(self, values: &[Option<&Value>]) -> ArrayRef {
+        let values = values.iter().map(|value| value.filter(|value| !value.is_null()));
+        match self {
+            ArrowType::Bool => Arc::new(values.map(|value| value.and_then(Value::as_bool)).collect::<BooleanArray>()),
+            ArrowType::Int { bit_width: 8, signed: true } => Arc::new(values.map(|value| integer(value).map(|value| value as i8)).collect::<Int8Array>()),
+            ArrowType::Int { bit_width: 16, signed: true } => Arc::new(values.map(|value| integer(value).map(|value| value as i16)).collect::<Int16Array>()),
+            ArrowType::Int { bit_width: 32, signed: true } => Arc::new(values.map(|value| integer(value).map(|value| value as i32)).collect::<Int32Array>()),
+            ArrowType::Int { signed: true, .. } => Arc::new(values.map(integer).collect::<Int64Array>()),
+            ArrowType::Int { bit_width: 8, signed: false } => Arc::new(values.map(|value| unsigned(value).map(|value| value as u8)).collect::<UInt8Array>()),
+            ArrowType::Int { bit_width: 16, signed: false } => Arc::new(values.map(|value| unsigned(value).map(|value| value as u16)).collect::<UInt16Array>()),
+            ArrowType::Int { bit_width: 32, signed: false } => Arc::new(values.map(|value| unsigned(value).map(|value| value as u32)).collect::<UInt32Array>()),
+            ArrowType::Int { signed: false, .. } => Arc::new(values.map(unsigned).collect::<UInt64Array>()),
+            ArrowType::Float32 => Arc::new(values.map(|value| value.and_then(Value::as_f64).map(|value| value as f32)).collect::<Float32Array>()),
+            ArrowType::Float64 => Arc::new(values.map(|value| value.and_then(Value::as_f64)).collect::<Float64Array>()),
+            ArrowType::Date32 => Arc::new(values.map(|value| value
+                .and_then(Value::as_str)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                .map(|date| days_since_epoch(&date))
+            ).collect::<Date32Array>()),
+            ArrowType::Timestamp { utc } => Arc::new(values.map(|value| value
+                .and_then(Value::as_str)
+                .and_then(timestamp_micros)
+            ).collect::<TimestampMicrosecondArray>().with_timezone_opt(utc.then_some("UTC"))),
+            ArrowType::Utf8 => Arc::new(values.map(|value| value.map(|value| match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })).collect::<StringArray>()),
+        }
+    }
+}
+
+/// Implemented by every model, the name and Arrow type of the serialized fields in the order of [`FieldNames`].
+///
+/// A field mapped to a column has the type of the column, a computed or converted field the type of its JSON Schema.
+pub trait ArrowFields: FieldNames {
+    fn arrow_fields() -> Vec<(&'static str, ArrowType)>;
+}
+
+/// Write the objects as an Arrow IPC file, in record batches of `ARROW_BATCH_SIZE` rows.
+///
+/// The file is written as the objects are streamed, the footer indexing the record batches coming last.
+/// A database error ends the stream before the footer, leaving an incomplete file that readers reject.
+pub fn arrow_file<'a, Model: ArrowFields + Serialize + Send + 'a>(objs: BoxStream<'a, Result<Model, DbErr>>) -> BoxStream<'a, Result<Vec<u8>, DbErr>> {
+    let fields = Model::arrow_fields();
+    let schema = Schema::new(fields.iter()
+        .map(|(name, arrow_type)| Field::new(*name, arrow_type.data_type(), true))
+        .collect::<Vec<_>>());
+    // The writer writes the header right away, each chunk being what it wrote since the previous one
+    let mut writer = FileWriter::try_new(Vec::new(), &schema).unwrap();
+    let header = std::mem::take(writer.get_mut());
+
+    let batches = stream::unfold((writer, Some(objs.chunks(ARROW_BATCH_SIZE))), move |(mut writer, objs)| {
+        let fields = fields.clone();
+        async move {
+            let mut objs = objs?;
+            match objs.next().await {
+                Some(batch) => match batch.into_iter().collect::<Result<Vec<_>, _>>() {
+                    Ok(batch) => {
+                        let rows: Vec<Value> = batch.iter().map(|obj| serde_json::to_value(obj).unwrap()).collect();
+                        let arrays = fields.iter()
+                            .map(|(name, arrow_type)| {
+                                let values: Vec<Option<&Value>> = rows.iter().map(|row| row.get(name)).collect();
+                                arrow_type.array(&values)
+                            })
+                            .collect();
+                        let batch = RecordBatch::try_new(writer.schema().clone(), arrays).unwrap();
+                        writer.write(&batch).unwrap();
+                        let bytes = std::mem::take(writer.get_mut());
+                        Some((Ok(bytes), (writer, Some(objs))))
+                    }
+                    Err(err) => Some((Err(err), (writer, None))),
+                },
+                None => {
+                    writer.finish().unwrap();
+                    let bytes = std::mem::take(writer.get_mut());
+                    Some((Ok(bytes), (writer, None)))
+                }
+            }
+        }
+    });
+    Box::pin(stream::once(async move { Ok(header) }).chain(batches))
+}
+
+fn integer(value: Option<&Value>) -> Option<i64> {
+    value.and_then(Value::as_i64)
+}
+
+fn unsigned(value: Option<&Value>) -> Option<u64> {
+    value.and_then(Value::as_u64)
+}
+
+fn days_since_epoch(date: &NaiveDate) -> i32 {
+    // Days from the common era of 1970-01-01
+    date.num_days_from_ce() - 719_163
+}
+
+fn micros<Tz: TimeZone>(date_time: &DateTime<Tz>) -> i64 {
+    date_time.timestamp() * 1_000_000 + date_time.timestamp_subsec_micros() as i64
+}
+
+/// Microseconds since the epoch of an RFC 3339 timestamp, the timestamps without time zone being taken as UTC.
+fn timestamp_micros(value: &str) -> Option<i64> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(date_time) => Some(micros(&date_time)),
+        Err(_) => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok()
+            .map(|date_time| micros(&Utc.from_utc_datetime(&date_time))),
+    }
+}
@@ -0,0 +1,66 @@
+use rocket::futures::stream::{self, BoxStream};
+use rocket::futures::StreamExt;
+use rocket::serde::json::serde_json::{self, Value};
+use sea_orm::DbErr;
+use serde::Serialize;
+
+/// Message of the marker ending the NDJSON and CSV streams interrupted by a database error.
+const INTERRUPTED: &str = "The stream was interrupted by a database error";
+
+/// Implemented by every model, lists the serialized fields in the order of the struct.
+pub trait FieldNames {
+    fn field_names() -> Vec<&'static str>;
+}
+
+/// CSV header row of a model.
+pub fn csv_header<Model: FieldNames>() -> Vec<u8> {
+    csv_row(Model::field_names().into_iter().map(String::from))
+}
+
+/// CSV row of an object, its fields in the order of the header.
+pub fn csv_record<Model: FieldNames + Serialize>(obj: &Model) -> Vec<u8> {
+    let obj = serde_json::to_value(obj).unwrap();
+    csv_row(Model::field_names().into_iter().map(|name| match obj.get(name) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    }))
+}
+
+/// Last record of a CSV stream interrupted by a database error, its single field not matching the header.
+pub fn csv_interrupted() -> Vec<u8> {
+    csv_row(std::iter::once(format!("error: {}", INTERRUPTED)))
+}
+
+/// Last line of an NDJSON stream interrupted by a database error, an error object rather than a row.
+pub fn ndjson_interrupted() -> Vec<u8> {
+    let mut bytes = serde_json::to_vec(&serde_json::json!({ "error": INTERRUPTED })).unwrap();
+    bytes.push(b'\n');
+    bytes
+}
+
+/// The body of a streaming route, the chunks being followed by `end` once they were all sent.
+///
+/// The status and the first chunks are already sent when a database error ends the stream, so the body ends
+/// with `interrupted` instead, e.g. a JSON array without its closing bracket, to not be taken for a complete one.
+pub fn stream_body<'a>(chunks: BoxStream<'a, Result<Vec<u8>, DbErr>>, end: Vec<u8>, interrupted: Vec<u8>) -> BoxStream<'a, Vec<u8>> {
+    let body = stream::unfold(Some((chunks, end, interrupted)), |state| async move {
+        let (mut chunks, end, interrupted) = state?;
+        match chunks.next().await {
+            Some(Ok(bytes)) => Some((bytes, Some((chunks, end, interrupted)))),
+            Some(Err(_)) => Some((interrupted, None)),
+            None => Some((end, None)),
+        }
+    });
+    Box::pin(body.filter(|bytes| std::future::ready(!bytes.is_empty())))
+}
+
+/// A CSV record, the values containing a separator, a quote or a line break being quoted as described by RFC 4180.
+fn csv_row(values: impl Iterator<Item = String>) -> Vec<u8> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(vec![]);
+    // Writing to a vector cannot fail
+    writer.write_record(values).unwrap();
+    writer.into_inner().unwrap()
+}
@@ -0,0 +1,440 @@
+//! GraphQL schema of the CRUD resources, built at runtime from the JSON Schemas of their models.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_graphql::dynamic::{
+    Enum, Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ResolverContext, Scalar, Schema, SchemaError, Type, TypeRef,
+};
+use async_graphql::{Error, ErrorExtensions, Response, SelectionField, ServerError};
+use rocket::serde::json::serde_json::{self, Value};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DatabaseTransaction, TransactionTrait};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{check_unpaginated, parse_filter, ApiResource, ApiSchema, CRUDServiceTrait, DeleteError, FieldNames, LoadRelations, ObjectId, ToActiveModel, WriteError};
+
+pub use async_graphql::http::GraphiQLSource;
+pub use async_graphql::{Request as GraphQLRequest, Response as GraphQLResponse};
+
+/// Scalar of the fields without a GraphQL equivalent, e.g. JSON columns.
+const JSON_SCALAR: &str = "JSON";
+
+/// Relations of the object types, by type then field name, used to turn a selection into `include` paths.
+struct Relations(HashMap<String, HashMap<String, String>>);
+
+/// The CRUD resources exposed by `POST /api/graphql`, each with its list and get queries
+/// and its create, update and delete mutations.
+pub struct GraphQLRegistry {
+    query: Object,
+    mutation: Object,
+    /// Object types of the models, with the parent modules of their `#[belongsTo]` fields
+    objects: Vec<(Object, Vec<&'static str>)>,
+    types: Vec<Type>,
+    type_names: HashSet<String>,
+    modules: HashMap<&'static str, String>,
+}
+
+impl Default for GraphQLRegistry {
+    fn default() -> Self {
+        GraphQLRegistry {
+            query: Object::new("Query"),
+            mutation: Object::new("Mutation"),
+            objects: vec![],
+            types: vec![],
+            type_names: HashSet::new(),
+            modules: HashMap::new(),
+        }
+    }
+}
+
+impl GraphQLRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the service of a CRUD resource, e.g. `.resource::<CarService, _, _, _, _>("cars")`
+    /// adds the `cars` and `car` queries and the `createCar`, `updateCar` and `deleteCar` mutations.
+    pub fn resource<Service, Model, CreateModel, PartialModel, ActiveModel>(mut self, name: &str) -> Self
+    where
+        Service: CRUDServiceTrait<Model, CreateModel, PartialModel> + 'static,
+        Model: ApiResource + ApiSchema + FieldNames + LoadRelations + ObjectId + Serialize + Sync + 'static,
+        CreateModel: ApiSchema + DeserializeOwned + Send + 'static,
+        PartialModel: ApiSchema + DeserializeOwned + ToActiveModel<ActiveModel> + Send + 'static,
+        ActiveModel: ActiveModelTrait + 'static,
+    {
+        let type_name = Model::NAME;
+        let create_input = format!("{}CreateInput", type_name);
+        let update_input = format!("{}UpdateInput", type_name);
+        let filter_input = format!("{}Filter", type_name);
+
+        let mut object = Object::new(type_name);
+        for (field, ty) in self.properties(type_name, &Model::schema(), true) {
+            object = object.field(property_field(field, ty, false));
+        }
+        self.objects.push((object, Model::parents()));
+        self.modules.insert(Model::MODULE, type_name.to_owned());
+
+        let mut input = InputObject::new(&create_input);
+        for (field, ty) in self.properties(type_name, &CreateModel::schema(), true) {
+            input = input.field(InputValue::new(field, ty));
+        }
+        self.types.push(input.into());
+        // Every field of an update is optional, a missing field being left untouched and `null` clearing it
+        let mut input = InputObject::new(&update_input);
+        let mut filter = InputObject::new(&filter_input);
+        for (field, ty) in self.properties(type_name, &PartialModel::schema(), false) {
+            input = input.field(InputValue::new(&field, ty.clone()));
+            // Filtered on the same fields as the `?filter[field]=value` parameters of the list route
+            if Model::field_names().contains(&field.as_str()) {
+                filter = filter.field(InputValue::new(field, ty));
+            }
+        }
+        self.types.push(input.into());
+        self.types.push(filter.into());
+
+        let resource = GraphQLResource::<Service, Model, CreateModel, PartialModel, ActiveModel>(PhantomData);
+        self.query = self.query
+            .field(resource.list(name, type_name, &filter_input))
+            .field(resource.get(Model::MODULE, type_name));
+        self.mutation = self.mutation
+            .field(resource.create(&format!("create{}", type_name), type_name, &create_input))
+            .field(resource.update(&format!("update{}", type_name), type_name, &update_input))
+            .field(resource.delete(&format!("delete{}", type_name)));
+        self
+    }
+
+    /// GraphQL types of the properties of an object schema, non-null when required unless `required` is false.
+    fn properties(&mut self, type_name: &str, schema: &Value, required: bool) -> Vec<(String, TypeRef)> {
+        let required_fields: Vec<&str> = match schema.get("required") {
+            Some(Value::Array(fields)) if required => fields.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        let properties = match schema.get("properties") {
+            Some(Value::Object(properties)) => properties,
+            _ => return vec![],
+        };
+        properties.iter()
+            .map(|(field, schema)| {
+                let ty = self.graphql_type(&format!("{}{}", type_name, upper_camel_case(field)), schema);
+                let nullable = schema.get("nullable") == Some(&Value::Bool(true));
+                let ty = if required_fields.contains(&field.as_str()) && !nullable {
+                    TypeRef::NonNull(Box::new(ty))
+                } else {
+                    ty
+                };
+                (field.clone(), ty)
+            })
+            .collect()
+    }
+
+    /// GraphQL type of a field schema, an enum being declared as `enum_name`.
+    fn graphql_type(&mut self, enum_name: &str, schema: &Value) -> TypeRef {
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            let values: Vec<&str> = values.iter().filter_map(Value::as_str).collect();
+            if values.iter().all(|value| is_graphql_name(value)) {
+                if self.type_names.insert(enum_name.to_owned()) {
+                    self.types.push(Enum::new(enum_name).items(values).into());
+                }
+                return TypeRef::named(enum_name);
+            }
+        }
+        match schema.get("type").and_then(Value::as_str) {
+            Some("boolean") => TypeRef::named(TypeRef::BOOLEAN),
+            // Int is a 32 bits integer in GraphQL
+            Some("integer") if schema.get("format").and_then(Value::as_str) == Some("int32") => TypeRef::named(TypeRef::INT),
+            Some("integer") | Some("number") => TypeRef::named(TypeRef::FLOAT),
+            Some("string") => TypeRef::named(TypeRef::STRING),
+            Some("array") => {
+                let items = schema.get("items").cloned().unwrap_or_default();
+                TypeRef::List(Box::new(self.graphql_type(enum_name, &items)))
+            }
+            _ => TypeRef::named(JSON_SCALAR),
+        }
+    }
+
+    /// Build the schema, the `#[belongsTo]` fields whose parent is not registered being left out.
+    pub fn finish(self) -> Result<GraphQLSchema, SchemaError> {
+        let mut relations = HashMap::new();
+        let mut schema = Schema::build("Query", Some("Mutation"), None);
+        for (mut object, parents) in self.objects {
+            let mut object_relations = HashMap::new();
+            for parent in parents {
+                if let Some(parent_type) = self.modules.get(parent) {
+                    object = object.field(property_field(parent.to_owned(), TypeRef::named(parent_type), true));
+                    object_relations.insert(parent.to_owned(), parent_type.clone());
+                }
+            }
+            relations.insert(object.type_name().to_owned(), object_relations);
+            schema = schema.register(object);
+        }
+        for ty in self.types {
+            schema = schema.register(ty);
+        }
+
+        schema
+            .register(Scalar::new(JSON_SCALAR).description("Any JSON value"))
+            .register(self.query)
+            .register(self.mutation)
+            .data(Relations(relations))
+            .finish()
+            .map(GraphQLSchema)
+    }
+}
+
+/// The executable GraphQL schema of the registered resources.
+pub struct GraphQLSchema(Schema);
+
+impl GraphQLSchema {
+    /// Run a request in a transaction, committed when the response has no error and rolled back otherwise.
+    pub async fn execute(&self, request: GraphQLRequest, db: &DatabaseConnection) -> GraphQLResponse {
+        let txn = match db.begin().await {
+            Ok(txn) => Arc::new(txn),
+            Err(err) => return Response::from_errors(vec![ServerError::new(format!("Cannot begin the transaction: {}", err), None)]),
+        };
+        let mut response = self.0.execute(request.data(txn.clone())).await;
+
+        // The request data holding the other reference is dropped once executed
+        let txn = match Arc::try_unwrap(txn) {
+            Ok(txn) => txn,
+            Err(_) => return Response::from_errors(vec![ServerError::new("The transaction is still borrowed, rolled back", None)]),
+        };
+        let result = if response.is_ok() {
+            txn.commit().await
+        } else {
+            txn.rollback().await
+        };
+        if let Err(err) = result {
+            response.errors.push(ServerError::new(format!("Cannot end the transaction: {}", err), None));
+        }
+        response
+    }
+
+    /// The schema in the GraphQL schema definition language.
+    pub fn sdl(&self) -> String {
+        self.0.sdl()
+    }
+}
+
+/// Queries and mutations calling the [`CRUDServiceTrait`] implementation of a service.
+#[allow(clippy::type_complexity)]
+struct GraphQLResource<Service, Model, CreateModel, PartialModel, ActiveModel>(
+    PhantomData<fn() -> (Service, Model, CreateModel, PartialModel, ActiveModel)>,
+);
+
+impl<Service, Model, CreateModel, PartialModel, ActiveModel> GraphQLResource<Service, Model, CreateModel, PartialModel, ActiveModel>
+where
+    Service: CRUDServiceTrait<Model, CreateModel, PartialModel> + 'static,
+    Model: ApiResource + FieldNames + LoadRelations + ObjectId + Serialize + Sync + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+    PartialModel: DeserializeOwned + ToActiveModel<ActiveModel> + Send + 'static,
+    ActiveModel: ActiveModelTrait + 'static,
+{
+    fn list(&self, name: &str, type_name: &'static str, filter_input: &str) -> Field {
+        Field::new(name, TypeRef::named_nn_list_nn(type_name), move |ctx| FieldFuture::new(async move {
+            let txn = transaction(&ctx)?;
+            let offset = ctx.args.get("offset").filter(|offset| !offset.is_null()).map(|offset| offset.u64()).transpose()?;
+            let limit = ctx.args.get("limit").filter(|limit| !limit.is_null()).map(|limit| limit.u64()).transpose()?;
+            let filter = filter_argument(&ctx)?;
+
+            let objs = match ctx.args.get("ids").filter(|ids| !ids.is_null()) {
+                Some(ids) => {
+                    check_unpaginated(limit, offset).map_err(Error::new)?;
+                    if !filter.is_empty() {
+                        return Err(Error::new("`filter` can't be combined with `ids`"));
+                    }
+                    let ids = ids.deserialize::<Vec<i32>>()?;
+                    let mut objs = Service::get_by_ids(&ids, txn).await;
+                    // In the requested order
+                    objs.sort_by_key(|obj| ids.iter().position(|id| *id == obj.obj_id()));
+                    objs
+                }
+                None if limit.is_some() || offset.is_some() || !filter.is_empty() => {
+                    let condition = parse_filter::<Model, PartialModel, ActiveModel>(&filter).map_err(Error::new)?;
+                    Service::get_page(condition, offset.unwrap_or(0), limit, txn).await
+                }
+                None => Service::get_all(txn).await,
+            };
+            let objs = with_relations(&ctx, type_name, objs).await?;
+            Ok(Some(FieldValue::list(objs.into_iter().map(FieldValue::owned_any))))
+        }))
+            .description(format!("The {} objects, ordered by id unless filtered by ids", type_name))
+            .argument(InputValue::new("ids", TypeRef::named_nn_list(TypeRef::INT))
+                .description("Restrict the objects to these ids, in this order, which can't be combined with the other arguments"))
+            .argument(InputValue::new("filter", TypeRef::named(filter_input))
+                .description("Keep the objects equal to these values, null keeping the objects without a value"))
+            .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)))
+            .argument(InputValue::new("offset", TypeRef::named(TypeRef::INT)))
+    }
+
+    fn get(&self, name: &str, type_name: &'static str) -> Field {
+        Field::new(name, TypeRef::named(type_name), move |ctx| FieldFuture::new(async move {
+            let txn = transaction(&ctx)?;
+            let obj = match Service::get_by_id(id_argument(&ctx)?, txn).await {
+                Some(obj) => obj,
+                None => return Ok(None),
+            };
+            Ok(with_relations(&ctx, type_name, vec![obj]).await?.pop().map(FieldValue::owned_any))
+        }))
+            .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::INT)))
+    }
+
+    fn create(&self, name: &str, type_name: &'static str, input: &str) -> Field {
+        Field::new(name, TypeRef::named_nn(type_name), move |ctx| FieldFuture::new(async move {
+            let txn = transaction(&ctx)?;
+            let form: CreateModel = input_argument(&ctx)?;
+            let obj = match Service::create(form, txn).await {
+                Ok(obj) => obj,
+                Err(err) => return Err(write_error(err)),
+            };
+            Ok(with_relations(&ctx, type_name, vec![obj]).await?.pop().map(FieldValue::owned_any))
+        }))
+            .argument(InputValue::new("input", TypeRef::named_nn(input)))
+    }
+
+    fn update(&self, name: &str, type_name: &'static str, input: &str) -> Field {
+        Field::new(name, TypeRef::named(type_name), move |ctx| FieldFuture::new(async move {
+            let txn = transaction(&ctx)?;
+            let form: PartialModel = input_argument(&ctx)?;
+            let obj = match Service::update(id_argument(&ctx)?, form, txn).await {
+                Ok(Some(obj)) => obj,
+                Ok(None) => return Ok(None),
+                Err(err) => return Err(write_error(err)),
+            };
+            Ok(with_relations(&ctx, type_name, vec![obj]).await?.pop().map(FieldValue::owned_any))
+        }))
+            .description("The updated object, null when it doesn't exist")
+            .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::INT)))
+            .argument(InputValue::new("input", TypeRef::named_nn(input)))
+    }
+
+    fn delete(&self, name: &str) -> Field {
+        Field::new(name, TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| FieldFuture::new(async move {
+            let txn = transaction(&ctx)?;
+            match Service::delete(id_argument(&ctx)?, txn).await {
+                Ok(deleted) => Ok(Some(FieldValue::value(deleted.is_some()))),
+                Err(DeleteError::Blocked(blocking)) => {
+                    let blocking = async_graphql::Value::from_json(serde_json::to_value(blocking)?)?;
+                    Err(Error::new("The object has children preventing its deletion")
+                        .extend_with(|_err, extensions| extensions.set("children", blocking)))
+                }
+                Err(DeleteError::Write(err)) => Err(write_error(err)),
+            }
+        }))
+            .description("Whether the object existed")
+            .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::INT)))
+    }
+}
+
+fn transaction<'a>(ctx: &ResolverContext<'a>) -> Result<&'a DatabaseTransaction, Error> {
+    ctx.ctx.data::<Arc<DatabaseTransaction>>().map(AsRef::as_ref)
+}
+
+fn id_argument(ctx: &ResolverContext<'_>) -> Result<i32, Error> {
+    let id = ctx.args.try_get("id")?.i64()?;
+    i32::try_from(id).map_err(|_err| Error::new(format!("Invalid id {}", id)))
+}
+
+/// The `input` argument, with the fields missing from the request left out rather than null.
+fn input_argument<T: DeserializeOwned>(ctx: &ResolverContext<'_>) -> Result<T, Error> {
+    let input = ctx.args.try_get("input")?.as_value().clone().into_json()?;
+    serde_json::from_value(input).map_err(|err| Error::new(err.to_string()))
+}
+
+/// The violated constraint, and the invalid field when known, are set in the extensions of the error.
+fn write_error(err: WriteError) -> Error {
+    let (constraint, field) = match &err {
+        WriteError::Duplicate(duplicate) => (duplicate.constraint.clone(), None),
+        WriteError::Invalid(invalid) => (invalid.constraint.clone(), invalid.field.clone()),
+        WriteError::Database(_) => (None, None),
+    };
+    Error::new(err.to_string()).extend_with(|_err, extensions| {
+        extensions.set("status", err.status().code);
+        if let Some(constraint) = constraint {
+            extensions.set("constraint", constraint);
+        }
+        if let Some(field) = field {
+            extensions.set("field", field);
+        }
+    })
+}
+
+/// The fields set by the `filter` argument as [`parse_filter`] reads them, each value being given as JSON.
+fn filter_argument(ctx: &ResolverContext<'_>) -> Result<BTreeMap<String, String>, Error> {
+    let filter = match ctx.args.get("filter").filter(|filter| !filter.is_null()) {
+        Some(filter) => filter.as_value().clone().into_json()?,
+        None => return Ok(BTreeMap::new()),
+    };
+    Ok(match filter {
+        Value::Object(fields) => fields.into_iter().map(|(field, value)| (field, value.to_string())).collect(),
+        _ => BTreeMap::new(),
+    })
+}
+
+/// The objects as JSON, with the relations selected by the query embedded by [`LoadRelations`].
+async fn with_relations<Model: LoadRelations + Serialize>(ctx: &ResolverContext<'_>, type_name: &str, objs: Vec<Model>) -> Result<Vec<Value>, Error> {
+    let relations = ctx.ctx.data::<Relations>()?;
+    let mut include = vec![];
+    selected_relations(relations, type_name, ctx.ctx.field(), "", &mut include);
+
+    let objs = Model::load_relations(objs, &include, transaction(ctx)?).await
+        .map_err(|err| Error::new(err.to_string()))?;
+    Ok(objs.into_iter().map(|obj| serde_json::to_value(obj).unwrap()).collect())
+}
+
+/// Push the dotted paths of the relations selected under `field`, e.g. `owner` and `owner.address`.
+fn selected_relations(relations: &Relations, type_name: &str, field: SelectionField<'_>, prefix: &str, include: &mut Vec<String>) {
+    let type_relations = match relations.0.get(type_name) {
+        Some(type_relations) => type_relations,
+        None => return,
+    };
+    for selection in field.selection_set() {
+        if let Some(parent_type) = type_relations.get(selection.name()) {
+            let path = format!("{}{}", prefix, selection.name());
+            if !include.contains(&path) {
+                include.push(path.clone());
+            }
+            selected_relations(relations, parent_type, selection, &format!("{}.", path), include);
+        }
+    }
+}
+
+/// A field read from the JSON of its object, a relation being an object read the same way.
+fn property_field(name: String, ty: TypeRef, relation: bool) -> Field {
+    let key = name.clone();
+    Field::new(name, ty, move |ctx| {
+        let value = ctx.parent_value.try_downcast_ref::<Value>()
+            .map(|obj| obj.get(&key).cloned().unwrap_or_default())
+            .and_then(|value| property_value(value, relation));
+        FieldFuture::new(future::ready(value))
+    })
+}
+
+fn property_value(value: Value, relation: bool) -> Result<Option<FieldValue<'static>>, Error> {
+    match value {
+        Value::Null => Ok(None),
+        value if relation => Ok(Some(FieldValue::owned_any(value))),
+        value => Ok(Some(FieldValue::value(async_graphql::Value::from_json(value)?))),
+    }
+}
+
+fn is_graphql_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+/// `fuel_type` gives `FuelType`, as the type names of the CRUD macros.
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+use std::io::Cursor;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rocket::fairing::AdHoc;
+use rocket::http::uri::Origin;
+use rocket::http::{Header, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::serde_json;
+use sea_orm::sea_query::{Alias, Expr, OnConflict, Query};
+use sea_orm::{ConnectionTrait, DbErr, QueryResult};
+use sea_orm_rocket::Database;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use db::Db;
+
+use crate::respond_json;
+
+/// Table storing the responses of the requests made with an `Idempotency-Key` header,
+/// created by the `m20230215_090000_create_idempotency_key_table` migration.
+const TABLE: &str = "idempotency_key";
+
+/// How long the stored responses are replayed, older keys being purged by the [`IdempotencyKey::purge_fairing`].
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Interval between two purges of the expired keys.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The keys stored before this time are expired, and no longer replayed.
+fn expired_before(ttl: Duration) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::from_std(ttl).unwrap()
+}
+
+/// The `Idempotency-Key` header of a request, use `Option<IdempotencyKey>` as request guard.
+pub struct IdempotencyKey(pub String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.headers().get_one("Idempotency-Key") {
+            Some(key) if !key.is_empty() => request::Outcome::Success(IdempotencyKey(key.to_owned())),
+            _ => request::Outcome::Forward(Status::BadRequest),
+        }
+    }
+}
+
+impl IdempotencyKey {
+    /// Delete the keys stored more than `ttl` ago, returns the number of deleted keys.
+    pub async fn purge<C: ConnectionTrait>(ttl: Duration, db: &C) -> Result<u64, DbErr> {
+        let delete = Query::delete()
+            .from_table(Alias::new(TABLE))
+            .and_where(Expr::col(Alias::new("created_at")).lt(expired_before(ttl)))
+            .to_owned();
+        let res = db.execute(db.get_database_backend().build(&delete)).await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Fairing purging the keys older than `IDEMPOTENCY_KEY_TTL` every hour, to attach along with `Db::init()`.
+    pub fn purge_fairing() -> AdHoc {
+        AdHoc::on_liftoff("Idempotency keys purge", |rocket| Box::pin(async move {
+            let conn = Db::fetch(rocket).unwrap().conn.clone();
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(PURGE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err(err) = IdempotencyKey::purge(IDEMPOTENCY_KEY_TTL, &conn).await {
+                        rocket::error!("Cannot purge the idempotency keys: {}", err);
+                    }
+                }
+            });
+        }))
+    }
+}
+
+/// The response of an idempotent route: either a fresh response, or the stored response of a retried request.
+pub enum Idempotent<R> {
+    Response(R),
+    Replay(StoredResponse),
+    /// The key was already used with another request
+    KeyReused,
+    /// The key is held by a request which has not completed
+    InProgress,
+    /// The key could not be claimed, or the response stored
+    Failed(DbErr),
+}
+
+/// A response stored for an `Idempotency-Key`.
+pub struct StoredResponse {
+    pub status: Status,
+    pub location: Option<String>,
+    pub body: String,
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Idempotent<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'o> {
+        match self {
+            Idempotent::Response(res) => res.respond_to(req),
+            Idempotent::Replay(stored) => {
+                let mut res = respond_json(stored.body, req)?;
+                res.set_status(stored.status);
+                res.set_header(Header::new("Idempotent-Replayed", "true"));
+                if let Some(location) = stored.location {
+                    res.set_header(Header::new("Location", location));
+                }
+                Ok(res)
+            }
+            Idempotent::KeyReused => {
+                let body = "The Idempotency-Key was already used with a different request";
+                Response::build()
+                    .status(Status::UnprocessableEntity)
+                    .sized_body(body.len(), Cursor::new(body))
+                    .ok()
+            }
+            Idempotent::InProgress => {
+                let body = "A request with the same Idempotency-Key is in progress";
+                Response::build()
+                    .status(Status::Conflict)
+                    .sized_body(body.len(), Cursor::new(body))
+                    .ok()
+            }
+            Idempotent::Failed(err) => {
+                rocket::error!("Cannot store the Idempotency-Key: {}", err);
+                let body = format!("Database error: {}", err);
+                Response::build()
+                    .status(Status::InternalServerError)
+                    .sized_body(body.len(), Cursor::new(body))
+                    .ok()
+            }
+        }
+    }
+}
+
+/// A request made idempotent by its `Idempotency-Key` header, when it has one.
+///
+/// The key is claimed by inserting its row in the request transaction, the response being stored in the row
+/// when the request completes, so a key is only kept when the request succeeds.
+/// An expired key is claimed again as a new one, even when it is not purged yet.
+/// A concurrent request with the same key waits on the row until the transaction ends, and then replays the response.
+pub struct IdempotentRequest {
+    key: Option<String>,
+    request_hash: String,
+}
+
+impl IdempotentRequest {
+    /// Claim the key of the request, `body` being its deserialized data.
+    ///
+    /// Returns the response to send back when the key was already used.
+    pub async fn begin<C, B, R>(key: Option<IdempotencyKey>, uri: &Origin<'_>, body: &B, db: &C) -> Result<Self, Idempotent<R>>
+    where
+        C: ConnectionTrait,
+        B: Serialize,
+    {
+        let mut hasher = Sha256::new();
+        hasher.update(uri.path().as_str());
+        hasher.update(b"\n");
+        hasher.update(serde_json::to_vec(body).unwrap());
+        let request = IdempotentRequest {
+            key: key.map(|key| key.0),
+            request_hash: hex::encode(hasher.finalize()),
+        };
+
+        let key = match &request.key {
+            Some(key) => key,
+            None => return Ok(request),
+        };
+
+        match request.claim(key, db).await {
+            Ok(None) => Ok(request),
+            Ok(Some(replay)) => Err(replay),
+            Err(err) => Err(Idempotent::Failed(err)),
+        }
+    }
+
+    /// Insert the row of the key, or take over its expired row, returns the response to send back when it is in use.
+    async fn claim<C: ConnectionTrait, R>(&self, key: &str, db: &C) -> Result<Option<Idempotent<R>>, DbErr> {
+        let expired = expired_before(IDEMPOTENCY_KEY_TTL);
+        let claim = Query::insert()
+            .into_table(Alias::new(TABLE))
+            .columns(["key", "request_hash"].map(Alias::new))
+            .values_panic([key.into(), self.request_hash.as_str().into()])
+            .on_conflict(
+                OnConflict::column(Alias::new("key"))
+                    .values([
+                        (Alias::new("request_hash"), self.request_hash.as_str().into()),
+                        (Alias::new("status"), Expr::val(None::<i16>).into()),
+                        (Alias::new("location"), Expr::val(None::<String>).into()),
+                        (Alias::new("response"), Expr::val(None::<String>).into()),
+                        (Alias::new("created_at"), Expr::current_timestamp()),
+                    ])
+                    .action_and_where(Expr::col((Alias::new(TABLE), Alias::new("created_at"))).lt(expired))
+                    .to_owned(),
+            )
+            .returning_col(Alias::new("key"))
+            .to_owned();
+        if db.query_one(db.get_database_backend().build(&claim)).await?.is_some() {
+            return Ok(None);
+        }
+
+        let select = Query::select()
+            .columns(["request_hash", "status", "location", "response"].map(Alias::new))
+            .from(Alias::new(TABLE))
+            .and_where(Expr::col(Alias::new("key")).eq(key))
+            .and_where(Expr::col(Alias::new("created_at")).gte(expired))
+            .to_owned();
+        let row = db.query_one(db.get_database_backend().build(&select)).await?;
+
+        // The row was purged since the claim conflicted, or its response is not stored yet
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(Some(Idempotent::InProgress)),
+        };
+        self.stored_response(&row)
+    }
+
+    /// The response to send back for the stored `row` of the key.
+    fn stored_response<R>(&self, row: &QueryResult) -> Result<Option<Idempotent<R>>, DbErr> {
+        let request_hash: String = row.try_get("", "request_hash")?;
+        if request_hash != self.request_hash {
+            return Ok(Some(Idempotent::KeyReused));
+        }
+        let status: Option<i16> = row.try_get("", "status")?;
+        let body: Option<String> = row.try_get("", "response")?;
+        match (status, body) {
+            (Some(status), Some(body)) => Ok(Some(Idempotent::Replay(StoredResponse {
+                status: Status::new(status as u16),
+                location: row.try_get("", "location")?,
+                body,
+            }))),
+            _ => Ok(Some(Idempotent::InProgress)),
+        }
+    }
+
+    /// Store the response of the request in the claimed row, to be replayed on retries.
+    pub async fn complete<C, T>(&self, status: Status, location: Option<&str>, body: &T, db: &C) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+        T: Serialize,
+    {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
+        let update = Query::update()
+            .table(Alias::new(TABLE))
+            .values([
+                (Alias::new("status"), (status.code as i16).into()),
+                (Alias::new("location"), location.map(str::to_owned).into()),
+                (Alias::new("response"), serde_json::to_string(body).unwrap().into()),
+            ])
+            .and_where(Expr::col(Alias::new("key")).eq(key.as_str()))
+            .to_owned();
+        db.execute(db.get_database_backend().build(&update)).await?;
+        Ok(())
+    }
+}
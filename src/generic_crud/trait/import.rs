@@ -0,0 +1,274 @@
+use std::fmt::Display;
+
+use rocket::data::{self, Data, FromData, Limits};
+use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::http::{ContentType, Status};
+use rocket::outcome::Outcome;
+use rocket::request::Request;
+use rocket::serde::json::serde_json;
+use rocket::tokio::io::AsyncReadExt;
+use rocket::FromForm;
+use serde::de::value::{Error, StrDeserializer};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::{forward_to_deserialize_any, Serialize};
+
+use crate::StreamFormat;
+
+/// Number of rows inserted by each `INSERT` statement of an import.
+pub const IMPORT_BATCH_SIZE: usize = 500;
+
+/// The body of an import route, CSV or NDJSON sent as raw body or as the `file` field of a multipart form.
+///
+/// The format is given by the content type, `text/csv` or `application/x-ndjson`, of the body or of the file.
+/// The size of the body is bounded by the `file` limit of the Rocket configuration.
+pub struct ImportBody {
+    pub format: StreamFormat,
+    pub content: String,
+}
+
+#[derive(FromForm)]
+struct ImportUpload<'r> {
+    file: TempFile<'r>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for ImportBody {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get("file").unwrap_or(Limits::FILE);
+
+        if req.content_type().is_some_and(|content_type| content_type.is_form_data()) {
+            let upload = match Form::<ImportUpload<'r>>::from_data(req, data).await {
+                Outcome::Success(upload) => upload.into_inner(),
+                Outcome::Error((status, errors)) => return Outcome::Error((status, errors.to_string())),
+                Outcome::Forward(forward) => return Outcome::Forward(forward),
+            };
+            let format = match import_format(upload.file.content_type()) {
+                Some(format) => format,
+                None => return unsupported_media_type(),
+            };
+            let mut content = String::new();
+            let read = match upload.file.open().await {
+                Ok(mut file) => file.read_to_string(&mut content).await,
+                Err(err) => Err(err),
+            };
+            return match read {
+                Ok(_) => Outcome::Success(ImportBody { format, content }),
+                Err(err) => Outcome::Error((Status::BadRequest, err.to_string())),
+            };
+        }
+
+        let format = match import_format(req.content_type()) {
+            Some(format) => format,
+            None => return unsupported_media_type(),
+        };
+        match data.open(limit).into_string().await {
+            Ok(content) if content.is_complete() => Outcome::Success(ImportBody { format, content: content.into_inner() }),
+            Ok(_) => Outcome::Error((Status::PayloadTooLarge, format!("The body exceeds the {} limit", limit))),
+            Err(err) => Outcome::Error((Status::BadRequest, err.to_string())),
+        }
+    }
+}
+
+fn import_format(content_type: Option<&ContentType>) -> Option<StreamFormat> {
+    let media_type = content_type?.media_type();
+    [StreamFormat::Csv, StreamFormat::Ndjson]
+        .into_iter()
+        .find(|format| format.content_type().media_type() == media_type)
+}
+
+fn unsupported_media_type<'r>() -> data::Outcome<'r, ImportBody> {
+    Outcome::Error((Status::UnsupportedMediaType, "Expected text/csv or application/x-ndjson".to_owned()))
+}
+
+/// The error of an imported row, `line` being the line of the body where the row starts.
+#[derive(Debug, Serialize)]
+pub struct ImportError {
+    pub line: usize,
+    pub error: String,
+    /// The field holding the invalid value, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+/// The result of an import, the rows being only inserted when none of them has an error.
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub inserted: usize,
+    pub errors: Vec<ImportError>,
+}
+
+/// Deserialize the rows of the body, the CSV header naming the fields of each column.
+///
+/// Returns the rows with their line, and the errors of the rows that could not be deserialized.
+pub fn parse_rows<T: DeserializeOwned>(body: &ImportBody) -> (Vec<(usize, T)>, Vec<ImportError>) {
+    let mut rows = vec![];
+    let mut errors = vec![];
+    let mut push = |line: usize, row: Result<T, String>| match row {
+        Ok(row) => rows.push((line, row)),
+        Err(error) => errors.push(ImportError { line, error, field: None }),
+    };
+
+    match body.format {
+        StreamFormat::Csv => {
+            // Records with fewer or more fields than the header are reported as row errors rather than parse errors
+            let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(body.content.as_bytes());
+            let header = match reader.headers() {
+                Ok(header) => header.clone(),
+                Err(err) => {
+                    push(1, Err(err.to_string()));
+                    return (rows, errors);
+                }
+            };
+            // The position of a record given by the csv crate is before the blank lines preceding it, which it skips
+            let line = |position: Option<&csv::Position>| position.map_or(0, |position| {
+                let (before, after) = body.content.as_bytes().split_at(position.byte() as usize);
+                let blank = after.iter().take_while(|&&byte| byte == b'\r' || byte == b'\n');
+                1 + before.iter().chain(blank).filter(|&&byte| byte == b'\n').count()
+            });
+            for record in reader.records() {
+                match record {
+                    Ok(record) => push(line(record.position()), {
+                        if record.len() != header.len() {
+                            Err(format!("Expected {} fields, found {}", header.len(), record.len()))
+                        } else {
+                            T::deserialize(CsvRecord { fields: header.iter().zip(record.iter()), field: None })
+                                .map_err(|err| err.to_string())
+                        }
+                    }),
+                    Err(err) => push(line(err.position()), Err(err.to_string())),
+                }
+            }
+        }
+        _ => {
+            for (index, line) in body.content.lines().enumerate() {
+                if !line.trim().is_empty() {
+                    push(index + 1, serde_json::from_str(line).map_err(|err| err.to_string()));
+                }
+            }
+        }
+    }
+
+    (rows, errors)
+}
+
+/// A CSV record deserialized as a map of the header fields to the record fields.
+struct CsvRecord<'de, I> {
+    fields: I,
+    field: Option<(&'de str, &'de str)>,
+}
+
+impl<'de, I: Iterator<Item = (&'de str, &'de str)>> de::Deserializer<'de> for CsvRecord<'de, I> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, I: Iterator<Item = (&'de str, &'de str)>> MapAccess<'de> for CsvRecord<'de, I> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.fields.next() {
+            Some((name, value)) => {
+                self.field = Some((name, value));
+                seed.deserialize(StrDeserializer::<Error>::new(name)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (name, value) = self.field.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(CsvField(value)).map_err(|err| de::Error::custom(format!("{}: {}", name, err)))
+    }
+}
+
+/// A CSV field, parsed according to the type of the deserialized field. An empty field is a `None` option.
+struct CsvField<'de>(&'de str);
+
+impl<'de> CsvField<'de> {
+    fn parse<T: std::str::FromStr>(&self, expected: &str) -> Result<T, Error>
+    where
+        T::Err: Display,
+    {
+        self.0
+            .trim()
+            .parse()
+            .map_err(|err| de::Error::custom(format!("invalid {} {:?}: {}", expected, self.0, err)))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident, $expected:literal;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for CsvField<'de> {
+    type Error = Error;
+
+    /// A JSON object or array, e.g. of a JSON column, is deserialized as such.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.trim_start().starts_with(['{', '[']) {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(self.0) {
+                return value.deserialize_any(visitor).map_err(de::Error::custom);
+            }
+        }
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool, "boolean";
+        deserialize_i8 => visit_i8, "integer";
+        deserialize_i16 => visit_i16, "integer";
+        deserialize_i32 => visit_i32, "integer";
+        deserialize_i64 => visit_i64, "integer";
+        deserialize_u8 => visit_u8, "integer";
+        deserialize_u16 => visit_u16, "integer";
+        deserialize_u32 => visit_u32, "integer";
+        deserialize_u64 => visit_u64, "integer";
+        deserialize_f32 => visit_f32, "number";
+        deserialize_f64 => visit_f64, "number";
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        IntoDeserializer::<Error>::into_deserializer(self.0).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char bytes byte_buf unit unit_struct newtype_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
@@ -0,0 +1,93 @@
+//! MessagePack encoding of JSON values with `rmpv`, see <https://github.com/msgpack/msgpack/blob/master/spec.md>.
+
+use std::io;
+
+use rmpv::decode::Error;
+use rmpv::Value as MsgPack;
+use rocket::serde::json::serde_json::{Map, Number, Value};
+
+/// Deepest nesting of arrays and maps accepted by [`decode`].
+const MAX_DEPTH: usize = 128;
+
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut bytes = vec![];
+    // Writing to a vector cannot fail
+    rmpv::encode::write_value(&mut bytes, &msgpack(value)).unwrap();
+    bytes
+}
+
+/// Integers are written in their smallest representation, other numbers as 64 bits floats.
+fn msgpack(value: &Value) -> MsgPack {
+    match value {
+        Value::Null => MsgPack::Nil,
+        Value::Bool(value) => MsgPack::Boolean(*value),
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(value), _) => MsgPack::from(value),
+            (None, Some(value)) => MsgPack::from(value),
+            (None, None) => MsgPack::F64(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => MsgPack::from(value.as_str()),
+        Value::Array(values) => MsgPack::Array(values.iter().map(msgpack).collect()),
+        Value::Object(values) => MsgPack::Map(values.iter()
+            .map(|(key, value)| (MsgPack::from(key.as_str()), msgpack(value)))
+            .collect()),
+    }
+}
+
+/// Decode a single MessagePack value, binary values being decoded as arrays of bytes.
+///
+/// The never used `0xc1` marker is read as nil, as rmpv does.
+pub fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = bytes;
+    // rmpv counts an array or a map twice, as a value and as a container
+    let value = rmpv::decode::read_value_with_max_depth(&mut reader, 2 * MAX_DEPTH).map_err(error)?;
+    if !reader.is_empty() {
+        return Err(format!("Trailing bytes at offset {}", bytes.len() - reader.len()));
+    }
+    json(value)
+}
+
+fn json(value: MsgPack) -> Result<Value, String> {
+    Ok(match value {
+        MsgPack::Nil => Value::Null,
+        MsgPack::Boolean(value) => Value::Bool(value),
+        MsgPack::Integer(value) => match (value.as_u64(), value.as_i64()) {
+            (Some(value), _) => Value::from(value),
+            (None, Some(value)) => Value::from(value),
+            (None, None) => return Err(format!("Invalid integer {}", value)),
+        },
+        MsgPack::F32(value) => float(value as f64),
+        MsgPack::F64(value) => float(value),
+        MsgPack::String(value) => match value.into_str() {
+            Some(value) => Value::String(value),
+            None => return Err("Invalid UTF-8 string".to_owned()),
+        },
+        MsgPack::Binary(value) => Value::from(value),
+        MsgPack::Array(values) => Value::Array(values.into_iter().map(json).collect::<Result<_, _>>()?),
+        MsgPack::Map(entries) => {
+            let mut values = Map::new();
+            for (key, value) in entries {
+                let key = match key {
+                    MsgPack::String(key) if key.is_str() => key.into_str().unwrap(),
+                    key => return Err(format!("Expected a string key, found {}", key)),
+                };
+                values.insert(key, json(value)?);
+            }
+            Value::Object(values)
+        }
+        MsgPack::Ext(ext_type, _data) => return Err(format!("Unsupported extension type {}", ext_type)),
+    })
+}
+
+fn error(err: Error) -> String {
+    match err {
+        Error::InvalidMarkerRead(err) | Error::InvalidDataRead(err) if err.kind() == io::ErrorKind::UnexpectedEof => "Unexpected end of input".to_owned(),
+        Error::DepthLimitExceeded => "Too deeply nested".to_owned(),
+        err => err.to_string(),
+    }
+}
+
+/// NaN and infinite floats, which JSON cannot represent, are decoded as null.
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
@@ -0,0 +1,193 @@
+use std::io::Cursor;
+use std::ops::{Deref, DerefMut};
+
+use rocket::data::{self, Data, FromData, Limits};
+use rocket::http::{ContentType, Header, MediaType, Status};
+use rocket::outcome::Outcome;
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::serde_json::{self, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{cbor, msgpack};
+
+/// Format of a request or response body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyFormat {
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl BodyFormat {
+    /// The format of a media type, `application/x-msgpack` being accepted for MessagePack.
+    pub fn of(media_type: &MediaType) -> Option<BodyFormat> {
+        match (media_type.top().as_str(), media_type.sub().as_str()) {
+            ("application", "json") => Some(BodyFormat::Json),
+            ("application", "msgpack" | "x-msgpack" | "vnd.msgpack") => Some(BodyFormat::MsgPack),
+            ("application", "cbor") => Some(BodyFormat::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> ContentType {
+        match self {
+            BodyFormat::Json => ContentType::JSON,
+            BodyFormat::MsgPack => ContentType::new("application", "msgpack"),
+            BodyFormat::Cbor => ContentType::new("application", "cbor"),
+        }
+    }
+
+    /// Name of the Rocket configuration limit of a request body.
+    fn limit(self) -> &'static str {
+        match self {
+            BodyFormat::Json => "json",
+            BodyFormat::MsgPack => "msgpack",
+            BodyFormat::Cbor => "cbor",
+        }
+    }
+
+    /// The format asked by the `Accept` header of a request, JSON by default.
+    ///
+    /// The media types are picked by weight, a supported one taking precedence over `*/*` and `application/*`
+    /// which stand for JSON. `None` when the header lists none of them.
+    pub fn accepted(req: &Request<'_>) -> Option<BodyFormat> {
+        let accept = match req.accept() {
+            Some(accept) => accept,
+            None => return Some(BodyFormat::Json),
+        };
+        let mut accepted: Option<((f32, bool), BodyFormat)> = None;
+        for media_type in accept.iter() {
+            let weight = media_type.weight_or(1.0);
+            let media_type = media_type.media_type();
+            let (format, specific) = match (BodyFormat::of(media_type), media_type.top().as_str(), media_type.sub().as_str()) {
+                (Some(format), _, _) => (format, true),
+                (None, "*", "*") | (None, "application", "*") => (BodyFormat::Json, false),
+                _ => continue,
+            };
+            if weight > 0.0 && accepted.is_none_or(|(preference, _format)| (weight, specific) > preference) {
+                accepted = Some(((weight, specific), format));
+            }
+        }
+        accepted.map(|(_preference, format)| format)
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<Vec<u8>, serde_json::Error> {
+        match self {
+            BodyFormat::Json => serde_json::to_vec(value),
+            BodyFormat::MsgPack => Ok(msgpack::encode(&serde_json::to_value(value)?)),
+            BodyFormat::Cbor => Ok(cbor::encode(&serde_json::to_value(value)?)),
+        }
+    }
+
+    /// Decode a body as a JSON value, the error describing why the body is malformed.
+    pub fn decode(self, bytes: &[u8]) -> Result<Value, String> {
+        match self {
+            BodyFormat::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+            BodyFormat::MsgPack => msgpack::decode(bytes),
+            BodyFormat::Cbor => cbor::decode(bytes),
+        }
+    }
+}
+
+/// A JSON, MessagePack or CBOR body, used in place of `Json` by the CRUD routes.
+///
+/// As request data, it is read in the format of the `Content-Type` of the request, JSON by default,
+/// any other content type failing with a 415.
+/// As response, it is written in the format preferred by the `Accept` header of the request, JSON by default,
+/// a header accepting none of the formats failing with a 406.
+#[derive(Debug)]
+pub struct Negotiated<T>(pub T);
+
+impl<T> Negotiated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Negotiated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Negotiated<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for Negotiated<T> {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let format = match req.content_type() {
+            None => BodyFormat::Json,
+            Some(content_type) => match BodyFormat::of(content_type.media_type()) {
+                Some(format) => format,
+                None => {
+                    let err = format!("Unsupported content type {}, expected JSON, MessagePack or CBOR", content_type.media_type());
+                    return Outcome::Error((Status::UnsupportedMediaType, err));
+                }
+            },
+        };
+        let limit = req.limits().get(format.limit()).unwrap_or(Limits::JSON);
+
+        let bytes = match data.open(limit).into_bytes().await {
+            Ok(bytes) if bytes.is_complete() => bytes.into_inner(),
+            Ok(_) => return Outcome::Error((Status::PayloadTooLarge, format!("The body exceeds the {} limit", limit))),
+            Err(err) => return Outcome::Error((Status::BadRequest, err.to_string())),
+        };
+
+        // A malformed body is a bad request, a body of the wrong shape an unprocessable entity
+        let value = match format {
+            BodyFormat::Json => {
+                return match serde_json::from_slice(&bytes) {
+                    Ok(value) => Outcome::Success(Negotiated(value)),
+                    Err(err) if err.is_data() => Outcome::Error((Status::UnprocessableEntity, err.to_string())),
+                    Err(err) => Outcome::Error((Status::BadRequest, err.to_string())),
+                };
+            }
+            format => format.decode(&bytes),
+        };
+        match value.map(serde_json::from_value::<T>) {
+            Ok(Ok(value)) => Outcome::Success(Negotiated(value)),
+            Ok(Err(err)) => Outcome::Error((Status::UnprocessableEntity, err.to_string())),
+            Err(err) => Outcome::Error((Status::BadRequest, err)),
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let format = BodyFormat::accepted(req).ok_or(Status::NotAcceptable)?;
+        let bytes = format.serialize(&self.0).map_err(|err| {
+            rocket::error!("Cannot serialize the response: {}", err);
+            Status::InternalServerError
+        })?;
+        Response::build()
+            .header(format.content_type())
+            .header(Header::new("Vary", "Accept"))
+            .sized_body(bytes.len(), Cursor::new(bytes))
+            .ok()
+    }
+}
+
+/// Respond with a JSON body serialized beforehand, transcoded to the format of the `Accept` header.
+pub fn respond_json(body: String, req: &Request<'_>) -> response::Result<'static> {
+    match BodyFormat::accepted(req).ok_or(Status::NotAcceptable)? {
+        BodyFormat::Json => Response::build()
+            .header(ContentType::JSON)
+            .header(Header::new("Vary", "Accept"))
+            .sized_body(body.len(), Cursor::new(body))
+            .ok(),
+        _ => {
+            let value: Value = serde_json::from_str(&body).map_err(|_err| Status::InternalServerError)?;
+            Negotiated(value).respond_to(req)
+        }
+    }
+}
@@ -0,0 +1,711 @@
+//! OpenAPI 3 description of the CRUD routes, see <https://spec.openapis.org/oas/v3.0.3>.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use rocket::http::Method;
+use rocket::Route;
+use rocket::serde::json::serde_json::{self, json, Map, Value};
+use sea_orm::prelude::{Decimal, Uuid};
+use sea_orm::{ColumnDef, ColumnType, Iterable};
+use serde::Serialize;
+
+/// Media types of the bodies read and written by [`Negotiated`](crate::Negotiated).
+const NEGOTIATED_MEDIA_TYPES: &[&str] = &["application/json", "application/msgpack", "application/cbor"];
+
+/// Dialect of the schemas returned by the `_schema` routes.
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema of a request or response body, or of one of its fields.
+pub trait ApiSchema {
+    fn schema() -> Value;
+}
+
+macro_rules! impl_api_schema {
+    ($($ty:ty => $schema:tt),* $(,)?) => {
+        $(
+            impl ApiSchema for $ty {
+                fn schema() -> Value {
+                    json!($schema)
+                }
+            }
+        )*
+    };
+}
+
+impl_api_schema! {
+    bool => {"type": "boolean"},
+    i8 => {"type": "integer", "format": "int32", "minimum": i8::MIN, "maximum": i8::MAX},
+    i16 => {"type": "integer", "format": "int32", "minimum": i16::MIN, "maximum": i16::MAX},
+    i32 => {"type": "integer", "format": "int32"},
+    i64 => {"type": "integer", "format": "int64"},
+    u8 => {"type": "integer", "format": "int32", "minimum": 0, "maximum": u8::MAX},
+    u16 => {"type": "integer", "format": "int32", "minimum": 0, "maximum": u16::MAX},
+    u32 => {"type": "integer", "format": "int64", "minimum": 0, "maximum": u32::MAX},
+    u64 => {"type": "integer", "format": "int64", "minimum": 0},
+    f32 => {"type": "number", "format": "float"},
+    f64 => {"type": "number", "format": "double"},
+    char => {"type": "string", "minLength": 1, "maxLength": 1},
+    String => {"type": "string"},
+    NaiveDate => {"type": "string", "format": "date"},
+    NaiveDateTime => {"type": "string", "example": "2023-01-31T12:00:00"},
+    DateTime<FixedOffset> => {"type": "string", "format": "date-time"},
+    DateTime<Utc> => {"type": "string", "format": "date-time"},
+    Decimal => {"type": "string", "format": "decimal", "example": "12.50"},
+    Uuid => {"type": "string", "format": "uuid"},
+    Value => {},
+}
+
+/// `null` is allowed along with the values of the inner schema.
+impl<T: ApiSchema> ApiSchema for Option<T> {
+    fn schema() -> Value {
+        let mut schema = T::schema();
+        if let Value::Object(schema) = &mut schema {
+            schema.insert("nullable".to_owned(), Value::Bool(true));
+            if let Some(Value::Array(values)) = schema.get_mut("enum") {
+                values.push(Value::Null);
+            }
+        }
+        schema
+    }
+}
+
+/// Also the schema of the `Vec<u8>` of binary columns, serialized as arrays of bytes.
+impl<T: ApiSchema> ApiSchema for Vec<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": T::schema()})
+    }
+}
+
+/// Schema of a fieldless enum serialized as a string, such as an `ActiveEnum`.
+pub fn enum_schema<E: Iterable + Serialize>() -> Value {
+    let values: Vec<Value> = E::iter().map(|value| serde_json::to_value(value).unwrap()).collect();
+    json!({"type": "string", "enum": values})
+}
+
+/// Schema of a model field: the schema of its type, constrained by the type of its column, when it has one.
+pub fn field_schema(mut schema: Value, column: Option<ColumnDef>, read_only: bool, write_only: bool) -> Value {
+    if let Value::Object(schema) = &mut schema {
+        if let Some(column) = column {
+            schema.extend(column_constraints(column.get_column_type()));
+        }
+        if read_only {
+            schema.insert("readOnly".to_owned(), Value::Bool(true));
+        }
+        if write_only {
+            schema.insert("writeOnly".to_owned(), Value::Bool(true));
+        }
+    }
+    schema
+}
+
+/// Bounds of the values of a column narrower than the type of its field, e.g. the length of a `VARCHAR(n)`.
+fn column_constraints(column_type: &ColumnType) -> Map<String, Value> {
+    let bounds = |minimum: i64, maximum: i64| json!({"minimum": minimum, "maximum": maximum});
+    let constraints = match column_type {
+        ColumnType::Char(Some(len)) | ColumnType::String(Some(len)) => json!({"maxLength": len}),
+        ColumnType::TinyInteger => bounds(i8::MIN.into(), i8::MAX.into()),
+        ColumnType::SmallInteger => bounds(i16::MIN.into(), i16::MAX.into()),
+        ColumnType::Integer => bounds(i32::MIN.into(), i32::MAX.into()),
+        ColumnType::TinyUnsigned => bounds(0, u8::MAX.into()),
+        ColumnType::SmallUnsigned => bounds(0, u16::MAX.into()),
+        ColumnType::Unsigned => bounds(0, u32::MAX.into()),
+        _ => return Map::new(),
+    };
+    match constraints {
+        Value::Object(constraints) => constraints,
+        _ => unreachable!(),
+    }
+}
+
+/// The JSON Schema of an OpenAPI 3.0 schema, a `nullable` one allowing the `null` type instead.
+pub fn json_schema(schema: Value) -> Value {
+    let mut schema = match schema {
+        Value::Object(schema) => schema,
+        schema => return schema,
+    };
+    if schema.remove("nullable") == Some(Value::Bool(true)) {
+        if let Some(Value::String(ty)) = schema.remove("type") {
+            schema.insert("type".to_owned(), json!([ty, "null"]));
+        }
+    }
+    for (key, value) in schema.iter_mut() {
+        match (key.as_str(), value) {
+            ("properties", Value::Object(properties)) => {
+                for property in properties.values_mut() {
+                    *property = json_schema(std::mem::take(property));
+                }
+            }
+            ("items" | "additionalProperties", value) => *value = json_schema(std::mem::take(value)),
+            ("oneOf" | "anyOf" | "allOf", Value::Array(schemas)) => {
+                for schema in schemas.iter_mut() {
+                    *schema = json_schema(std::mem::take(schema));
+                }
+            }
+            _ => {}
+        }
+    }
+    Value::Object(schema)
+}
+
+/// Body of the `GET /_schema` routes, the JSON Schemas of a model and of its request bodies.
+#[derive(Clone, Debug, Serialize)]
+pub struct ResourceSchemas {
+    pub model: Value,
+    pub post: Value,
+    pub partial: Value,
+}
+
+impl ResourceSchemas {
+    pub fn of<Model: ApiResource + ApiSchema, Post: ApiSchema, Partial: ApiSchema>() -> Self {
+        let schema = |title: String, schema: Value| {
+            let mut schema = json_schema(schema);
+            if let Value::Object(schema) = &mut schema {
+                schema.insert("$schema".to_owned(), Value::from(JSON_SCHEMA_DIALECT));
+                schema.insert("title".to_owned(), Value::from(title));
+            }
+            schema
+        };
+        ResourceSchemas {
+            model: schema(Model::NAME.to_owned(), Model::schema()),
+            post: schema(format!("Post{}", Model::NAME), Post::schema()),
+            partial: schema(format!("Partial{}", Model::NAME), Partial::schema()),
+        }
+    }
+}
+
+/// Reference to a schema of the components of the document.
+pub fn schema_ref(name: &str) -> Value {
+    json!({"$ref": format!("#/components/schemas/{}", name)})
+}
+
+/// A CRUD model described in the OpenAPI document, implemented by CRUDModel.
+pub trait ApiResource {
+    /// Module of the model, e.g. `car`
+    const MODULE: &'static str;
+    /// Name of the model schema, the request bodies being `Post<NAME>` and `Partial<NAME>`
+    const NAME: &'static str;
+
+    /// Component schemas of the model and of its request bodies, by name
+    fn schemas() -> Vec<(String, Value)>;
+    /// Modules of the `#[belongsTo(parent)]` fields
+    fn parents() -> Vec<&'static str>;
+    /// Modules of the `#[manyToMany(related, via = join_table)]` relations
+    fn many_to_many() -> Vec<&'static str>;
+    /// Schema of the `#[naturalKey(...)]` fields
+    fn natural_key() -> Option<Value>;
+}
+
+/// Operations of the routes of a CRUD controller, implemented by CRUDControllerImpl.
+pub trait ApiOperations {
+    type Resource: ApiResource;
+
+    fn operations(paths: &ApiPaths) -> Vec<ApiOperation>;
+}
+
+/// A registered resource: where it is mounted and the name of its model schema.
+#[derive(Clone, Debug)]
+pub struct ApiPath {
+    pub base: String,
+    pub name: &'static str,
+}
+
+impl ApiPath {
+    /// Last segment of the mount point, used by the nested routes and as tag, e.g. `cars`
+    pub fn segment(&self) -> &str {
+        self.base.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+/// Mount points of the registered resources, by module.
+#[derive(Clone, Debug, Default)]
+pub struct ApiPaths(HashMap<&'static str, ApiPath>);
+
+impl ApiPaths {
+    pub fn get(&self, module: &str) -> Option<&ApiPath> {
+        self.0.get(module)
+    }
+
+    /// Register the resource of `M` as mounted at `base`.
+    pub(crate) fn insert<M: ApiResource>(&mut self, base: &str) {
+        self.0.insert(M::MODULE, ApiPath { base: base.to_owned(), name: M::NAME });
+    }
+}
+
+/// An operation of the document, along with the route it describes.
+#[derive(Clone, Debug)]
+pub struct ApiOperation {
+    method: Method,
+    base: String,
+    /// Path of the route, relative to `base`, without its query
+    path: String,
+    operation: Map<String, Value>,
+}
+
+impl ApiOperation {
+    /// An operation of the route mounted at `base` with the `path` of its attribute, e.g. `/<obj_id>`.
+    ///
+    /// The dynamic segments of the path are described as integer ids.
+    pub fn new(method: Method, base: &str, path: &str, operation_id: &str, summary: &str) -> Self {
+        let mut operation = ApiOperation {
+            method,
+            base: base.to_owned(),
+            path: path.to_owned(),
+            operation: Map::new(),
+        };
+        operation.operation.insert("operationId".to_owned(), Value::from(operation_id));
+        operation.operation.insert("summary".to_owned(), Value::from(summary));
+        operation.operation.insert("responses".to_owned(), Value::Object(Map::new()));
+        for segment in path.split('/') {
+            if let Some(name) = segment.strip_prefix('<').and_then(|name| name.strip_suffix('>')) {
+                operation = operation.parameter(name, "path", true, "", i32::schema());
+            }
+        }
+        operation
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.operation.insert("tags".to_owned(), json!([tag]));
+        self
+    }
+
+    pub fn query(self, name: &str, description: &str, schema: Value) -> Self {
+        self.parameter(name, "query", false, description, schema)
+    }
+
+    /// A `name[field]=value` query parameter per property of `schema`.
+    pub fn deep_object_query(mut self, name: &str, description: &str, schema: Value) -> Self {
+        let parameter = json!({
+            "name": name, "in": "query", "required": false, "style": "deepObject", "explode": true,
+            "description": description, "schema": schema,
+        });
+        push(&mut self.operation, "parameters", parameter);
+        self
+    }
+
+    pub fn parameter(mut self, name: &str, location: &str, required: bool, description: &str, schema: Value) -> Self {
+        let mut parameter = json!({"name": name, "in": location, "required": required, "schema": schema});
+        if !description.is_empty() {
+            parameter["description"] = Value::from(description);
+        }
+        push(&mut self.operation, "parameters", parameter);
+        self
+    }
+
+    /// The `Idempotency-Key` header replaying the stored response of a retried request.
+    pub fn idempotent(self) -> Self {
+        let description = "Replays the response of the first request made with the same key";
+        self.parameter("Idempotency-Key", "header", false, description, String::schema())
+    }
+
+    /// A JSON, MessagePack or CBOR request body.
+    pub fn body(self, schema: Value) -> Self {
+        let media_types = NEGOTIATED_MEDIA_TYPES.iter().map(|media_type| (*media_type, schema.clone()));
+        self.body_content(media_types)
+    }
+
+    pub fn body_content<'a>(mut self, media_types: impl IntoIterator<Item = (&'a str, Value)>) -> Self {
+        let content = content(media_types);
+        self.operation.insert("requestBody".to_owned(), json!({"required": true, "content": content}));
+        self
+    }
+
+    /// A response without body.
+    pub fn status(self, status: u16, description: &str) -> Self {
+        self.response_content(status, description, [])
+    }
+
+    /// A JSON, MessagePack or CBOR response body.
+    pub fn response(self, status: u16, description: &str, schema: Value) -> Self {
+        let media_types = NEGOTIATED_MEDIA_TYPES.iter().map(|media_type| (*media_type, schema.clone()));
+        self.response_content(status, description, media_types)
+    }
+
+    pub fn response_content<'a>(mut self, status: u16, description: &str, media_types: impl IntoIterator<Item = (&'a str, Value)>) -> Self {
+        let mut response = json!({"description": description});
+        let content = content(media_types);
+        if !content.is_empty() {
+            response["content"] = Value::Object(content);
+        }
+        if let Some(Value::Object(responses)) = self.operation.get_mut("responses") {
+            responses.insert(status.to_string(), response);
+        }
+        self
+    }
+
+    /// Whether the operation describes a route, comparing their methods and paths.
+    fn describes(&self, route: &Route) -> bool {
+        let path = match self.path.as_str() {
+            "/" => self.base.clone(),
+            path => format!("{}{}", self.base.trim_end_matches('/'), path),
+        };
+        self.method == route.method && path.trim_end_matches('/') == route.uri.path().trim_end_matches('/')
+    }
+
+    /// Path of the operation in the document, `{name}` standing for the dynamic segments.
+    fn document_path(&self) -> String {
+        let path = format!("{}{}", self.base.trim_end_matches('/'), self.path);
+        let segments: Vec<String> = path.split('/')
+            .map(|segment| match segment.strip_prefix('<').and_then(|name| name.strip_suffix('>')) {
+                Some(name) => format!("{{{}}}", name),
+                None => segment.to_owned(),
+            })
+            .collect();
+        match segments.join("/").trim_end_matches('/') {
+            "" => "/".to_owned(),
+            path => path.to_owned(),
+        }
+    }
+}
+
+fn push(operation: &mut Map<String, Value>, key: &str, value: Value) {
+    match operation.entry(key).or_insert_with(|| Value::Array(vec![])) {
+        Value::Array(values) => values.push(value),
+        _ => unreachable!(),
+    }
+}
+
+fn content<'a>(media_types: impl IntoIterator<Item = (&'a str, Value)>) -> Map<String, Value> {
+    media_types.into_iter()
+        .map(|(media_type, schema)| (media_type.to_owned(), json!({"schema": schema})))
+        .collect()
+}
+
+/// Operations of the routes of [`CRUDControllerTrait`](crate::CRUDControllerTrait), of the
+/// nested and many to many routes of its relations and of its `PUT /by-key` route.
+///
+/// Only the operations of the mounted routes end up in the document.
+pub fn crud_operations<M: ApiResource>(paths: &ApiPaths) -> Vec<ApiOperation> {
+    let resource = match paths.get(M::MODULE) {
+        Some(resource) => resource,
+        None => return vec![],
+    };
+    let base = resource.base.as_str();
+    let tag = resource.segment();
+    let model = schema_ref(M::NAME);
+    let post = schema_ref(&format!("Post{}", M::NAME));
+    let partial = schema_ref(&format!("Partial{}", M::NAME));
+    let operation = |method, path: &str, operation_id: &str, summary: String| {
+        ApiOperation::new(method, base, path, &format!("{}_{}", operation_id, M::MODULE), &summary).tag(tag)
+    };
+
+    let parents = M::parents();
+    let include = |operation: ApiOperation| {
+        // The resources without #[belongsTo] fields have no relation to embed
+        if parents.is_empty() {
+            return operation;
+        }
+        let description = "Comma separated #[belongsTo] relations to embed, dotted paths including nested relations";
+        operation.query("include", description, json!({"type": "string", "example": parents.join(",")}))
+    };
+    let ids = || json!({"type": "string", "example": "1,5,9"});
+    let ids_description = "Comma separated ids to restrict the objects to";
+    let count = || json!({"type": "integer", "format": "int64", "minimum": 0});
+    let filter_description = "Fields of the update body the objects are equal to, as `filter[field]=value`, `null` matching the objects without a value";
+    let limit_description = "Maximum number of objects, ordered by id";
+    let offset_description = "Number of objects skipped, ordered by id";
+    let by_ids = json!({
+        "type": "object",
+        "required": ["data", "missing"],
+        "properties": {
+            "data": {"type": "array", "items": model},
+            "missing": {"type": "array", "items": i32::schema()},
+        },
+    });
+    let rows = |format: &str| -> Vec<(&'static str, Value)> {
+        match format {
+            "json" => vec![("application/json", json!({"type": "array", "items": model}))],
+            "ndjson" => vec![("application/x-ndjson", json!({"type": "string"}))],
+            "csv" => vec![("text/csv", json!({"type": "string"}))],
+            _ => vec![("application/vnd.apache.arrow.file", json!({"type": "string", "format": "binary"}))],
+        }
+    };
+
+    let mut operations = vec![
+        include(operation(Method::Get, "/", "reads", format!("List the {}", tag)))
+            .query("ids", "Comma separated ids, the response being then the objects found along with the missing ids", ids())
+            .deep_object_query("filter", filter_description, partial.clone())
+            .query("limit", limit_description, count())
+            .query("offset", offset_description, count())
+            .response(200, "The objects", json!({"oneOf": [{"type": "array", "items": model}, by_ids]}))
+            .status(400, "Invalid ids, filter, limit or offset, ids combined with a filter, limit or offset, or unknown relation"),
+        include(operation(Method::Post, "/_get", "reads_by_ids", format!("Get {} by ids", tag)))
+            .body(schema_ref("IdsRequest"))
+            .response(200, "The objects found, in the requested order, and the missing ids", by_ids.clone())
+            .status(400, "Unknown relation"),
+        operation(Method::Get, "/_schema", "schema", format!("JSON Schemas of the {} and of their request bodies", tag))
+            .response(200, "The schemas of the model, of the create body and of the update body", json!({
+                "type": "object",
+                "required": ["model", "post", "partial"],
+                "properties": {
+                    "model": {"type": "object", "description": "JSON Schema (draft 2020-12) of the model"},
+                    "post": {"type": "object", "description": "JSON Schema (draft 2020-12) of the create body"},
+                    "partial": {"type": "object", "description": "JSON Schema (draft 2020-12) of the update body"},
+                },
+            })),
+        operation(Method::Get, "/_stream", "stream", format!("Stream the {}", tag))
+            .query("format", "Format of the body, a JSON array by default", json!({"type": "string", "enum": ["json", "ndjson", "csv", "arrow"]}))
+            .query("ids", ids_description, ids())
+            .deep_object_query("filter", filter_description, partial.clone())
+            .query("limit", limit_description, count())
+            .query("offset", offset_description, count())
+            .response_content(200, "The objects, fetched as the body is sent", ["json", "ndjson", "csv", "arrow"].into_iter().flat_map(rows))
+            .status(400, "Invalid ids, filter, limit or offset, or ids combined with a filter, limit or offset"),
+    ];
+    for (format, summary) in [("csv", "CSV with a header row"), ("ndjson", "newline delimited JSON"), ("arrow", "an Arrow IPC file")] {
+        operations.push(operation(Method::Get, &format!("/export.{}", format), &format!("export_{}", format), format!("Export the {} as {}", tag, summary))
+            .query("ids", ids_description, ids())
+            .deep_object_query("filter", filter_description, partial.clone())
+            .query("limit", limit_description, count())
+            .query("offset", offset_description, count())
+            .response_content(200, "The objects", rows(format))
+            .status(400, "Invalid ids, filter, limit or offset, or ids combined with a filter, limit or offset"));
+    }
+    operations.extend([
+        operation(Method::Post, "/import", "import", format!("Import {} from a CSV or NDJSON file", tag))
+            .query("dry_run", "Validate the rows without inserting them", bool::schema())
+            .body_content([
+                ("text/csv", json!({"type": "string"})),
+                ("application/x-ndjson", json!({"type": "string"})),
+                ("multipart/form-data", json!({
+                    "type": "object",
+                    "required": ["file"],
+                    "properties": {"file": {"type": "string", "format": "binary"}},
+                })),
+            ])
+            .response(200, "All the rows are valid, and inserted unless dry_run is set", schema_ref("ImportReport"))
+            .response(422, "Some rows are invalid, none were inserted", schema_ref("ImportReport"))
+            .status(415, "The file is neither CSV nor NDJSON"),
+        include(operation(Method::Get, "/<obj_id>", "read", format!("Get one of the {}", tag)))
+            .response(200, "The object", model.clone())
+            .status(400, "Unknown relation")
+            .status(404, "Not found"),
+        operation(Method::Post, "/", "post", format!("Create one of the {}", tag))
+            .idempotent()
+            .body(post.clone())
+            .response(201, "The created object, its URI being in the Location header", model.clone())
+            .response(409, "An object with the same unique key exists, or a request with the same Idempotency-Key is in progress (in plain text)", schema_ref("DuplicateKey"))
+            .response(422, "A reference to a missing object or a value failing a check constraint, or an invalid body or Idempotency-Key used with a different request (in plain text)", schema_ref("InvalidValue")),
+        operation(Method::Patch, "/<obj_id>", "patch", format!("Update one of the {}", tag))
+            .body(partial)
+            .response(200, "The updated object", model.clone())
+            .status(404, "Not found")
+            .response(422, "A reference to a missing object or a value failing a check constraint, or an invalid body (in plain text)", schema_ref("InvalidValue")),
+        operation(Method::Delete, "/<obj_id>", "delete", format!("Delete one of the {}", tag))
+            .status(204, "Deleted")
+            .status(404, "Not found")
+            .response(409, "Children prevent the delete, their ids by relation", schema_ref("BlockingChildren"))
+            .response(422, "Children without an onDelete policy reference the object", schema_ref("InvalidValue")),
+    ]);
+
+    for parent in M::parents() {
+        let parent_base = match paths.get(parent) {
+            Some(parent) => parent.base.as_str(),
+            None => continue,
+        };
+        let collection = format!("/<{}_id>/{}", parent, tag);
+        let operation = |method, path: &str, operation_id: &str, summary: String| {
+            let operation_id = format!("{}_{}_by_{}", operation_id, M::MODULE, parent);
+            ApiOperation::new(method, parent_base, path, &operation_id, &summary).tag(tag)
+        };
+        operations.extend([
+            operation(Method::Get, &collection, "reads", format!("List the {} of a {}", tag, parent))
+                .response(200, "The objects", json!({"type": "array", "items": model}))
+                .status(404, "Parent not found"),
+            operation(Method::Get, &format!("{}/<obj_id>", collection), "read", format!("Get one of the {} of a {}", tag, parent))
+                .response(200, "The object", model.clone())
+                .status(404, "Parent or object not found"),
+            operation(Method::Post, &collection, "post", format!("Create one of the {} of a {}", tag, parent))
+                .idempotent()
+                .body(post.clone())
+                .response(201, "The created object, its URI being in the Location header", model.clone())
+                .status(404, "Parent not found")
+                .response(409, "An object with the same unique key exists, or a request with the same Idempotency-Key is in progress (in plain text)", schema_ref("DuplicateKey"))
+                .response(422, "A reference to a missing object or a value failing a check constraint, or an invalid body or Idempotency-Key used with a different request (in plain text)", schema_ref("InvalidValue")),
+        ]);
+    }
+
+    for related in M::many_to_many() {
+        let related_resource = match paths.get(related) {
+            Some(related) => related,
+            None => continue,
+        };
+        let related_model = schema_ref(related_resource.name);
+        let related_tag = related_resource.segment();
+        let collection = format!("/<obj_id>/{}", related_tag);
+        let link = format!("{}/<{}_id>", collection, related);
+        let operation = |method, path: &str, operation_id: &str, summary: String| {
+            let operation_id = format!("{}_{}_{}", operation_id, M::MODULE, related);
+            ApiOperation::new(method, base, path, &operation_id, &summary).tag(tag)
+        };
+        operations.extend([
+            operation(Method::Get, &collection, "reads_linked", format!("List the {} linked to one of the {}", related_tag, tag))
+                .response(200, "The linked objects", json!({"type": "array", "items": related_model}))
+                .status(404, "Not found"),
+            operation(Method::Put, &collection, "replace_links", format!("Replace the {} linked to one of the {}", related_tag, tag))
+                .body(json!({"type": "array", "items": i32::schema()}))
+                .response(200, "The linked objects", json!({"type": "array", "items": related_model}))
+                .status(404, "Not found")
+                .response(422, "Unknown related ids, listed in the error, or an invalid body (in plain text)", schema_ref("InvalidValue")),
+            operation(Method::Post, &link, "link", format!("Link one of the {} to one of the {}", related_tag, tag))
+                .status(204, "Linked")
+                .status(404, "Not found"),
+            operation(Method::Delete, &link, "unlink", format!("Unlink one of the {} from one of the {}", related_tag, tag))
+                .status(204, "Unlinked")
+                .status(404, "Not found"),
+        ]);
+    }
+
+    if let Some(Value::Object(key)) = M::natural_key() {
+        let required: Vec<Value> = key.get("required").and_then(Value::as_array).cloned().unwrap_or_default();
+        let mut upsert = operation(Method::Put, "/by-key", "upsert_by_key", format!("Create or update one of the {} by its natural key", tag));
+        if let Some(Value::Object(properties)) = key.get("properties") {
+            for (name, schema) in properties {
+                let required = required.contains(&Value::from(name.as_str()));
+                upsert = upsert.parameter(name, "query", required, "", schema.clone());
+            }
+        }
+        // The body is a Post* one, the fields of the key being taken from the query
+        let post_name = format!("Post{}", M::NAME);
+        let mut body = M::schemas().into_iter()
+            .find(|(name, _schema)| *name == post_name)
+            .map(|(_name, schema)| schema)
+            .unwrap_or_else(|| json!({"type": "object"}));
+        if let Some(Value::Array(body_required)) = body.get_mut("required") {
+            body_required.retain(|name| !required.contains(name));
+            if body_required.is_empty() {
+                body.as_object_mut().unwrap().remove("required");
+            }
+        }
+        operations.push(upsert
+            .body(body)
+            .response(200, "The updated object", model.clone())
+            .response(201, "The created object", model.clone())
+            .status(422, "Invalid body"));
+
+        let upserted = json!({
+            "type": "object",
+            "required": ["inserted", "data"],
+            "properties": {"inserted": bool::schema(), "data": model},
+        });
+        operations.push(operation(Method::Put, "/_upsert", "upsert_many", format!("Create or update {} by their natural key", tag))
+            .body(json!({"type": "array", "items": post}))
+            .response(200, "The objects in the order of the body, with whether each one was inserted", json!({"type": "array", "items": upserted}))
+            .status(422, "Invalid body, objects with the same natural key, or a missing referenced object"));
+    }
+
+    operations
+}
+
+/// Schemas of the bodies shared by all the resources.
+fn common_schemas() -> Vec<(String, Value)> {
+    vec![
+        ("IdsRequest".to_owned(), json!({
+            "type": "object",
+            "required": ["ids"],
+            "properties": {"ids": {"type": "array", "items": i32::schema()}},
+        })),
+        ("BlockingChildren".to_owned(), json!({
+            "type": "object",
+            "additionalProperties": {"type": "array", "items": i32::schema()},
+        })),
+        ("DuplicateKey".to_owned(), json!({
+            "type": "object",
+            "required": ["error"],
+            "properties": {"error": String::schema(), "constraint": Option::<String>::schema()},
+        })),
+        ("InvalidValue".to_owned(), json!({
+            "type": "object",
+            "required": ["error"],
+            "properties": {"error": String::schema(), "constraint": Option::<String>::schema(), "field": String::schema()},
+        })),
+        ("ImportError".to_owned(), json!({
+            "type": "object",
+            "required": ["line", "error"],
+            "properties": {"line": i64::schema(), "error": String::schema()},
+        })),
+        ("ImportReport".to_owned(), json!({
+            "type": "object",
+            "required": ["dry_run", "rows", "inserted", "errors"],
+            "properties": {
+                "dry_run": bool::schema(),
+                "rows": i64::schema(),
+                "inserted": i64::schema(),
+                "errors": {"type": "array", "items": schema_ref("ImportError")},
+            },
+        })),
+    ]
+}
+
+/// Operations and component schemas of a resource.
+type ResourceDocument = (fn(&ApiPaths) -> Vec<ApiOperation>, fn() -> Vec<(String, Value)>);
+
+/// The resources and operations of the OpenAPI document, built from the mounted routes on ignite.
+pub struct ApiRegistry {
+    title: String,
+    version: String,
+    paths: ApiPaths,
+    resources: Vec<ResourceDocument>,
+    operations: Vec<ApiOperation>,
+    schemas: Vec<(String, Value)>,
+}
+
+impl ApiRegistry {
+    pub fn new(title: &str, version: &str) -> Self {
+        ApiRegistry {
+            title: title.to_owned(),
+            version: version.to_owned(),
+            paths: ApiPaths::default(),
+            resources: vec![],
+            operations: vec![],
+            schemas: common_schemas(),
+        }
+    }
+
+    /// Describe the routes of a controller, its resource being mounted at `base`, e.g. `/api/cars`.
+    pub fn resource<Controller: ApiOperations>(mut self, base: &str) -> Self {
+        self.paths.insert::<Controller::Resource>(base);
+        self.resources.push((Controller::operations, Controller::Resource::schemas));
+        self
+    }
+
+    /// Describe a route that is not part of a resource.
+    pub fn operation(mut self, operation: ApiOperation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    pub fn schema(mut self, name: &str, schema: Value) -> Self {
+        self.schemas.push((name.to_owned(), schema));
+        self
+    }
+
+    /// The document of the operations describing one of the `routes`.
+    pub fn document<'a>(&self, routes: impl Iterator<Item = &'a Route>) -> Value {
+        let routes: Vec<&Route> = routes.collect();
+        let mut schemas: BTreeMap<String, Value> = self.schemas.iter().cloned().collect();
+        let mut operations = self.operations.clone();
+        for (resource_operations, resource_schemas) in &self.resources {
+            operations.extend(resource_operations(&self.paths));
+            schemas.extend(resource_schemas());
+        }
+
+        let mut paths: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+        for operation in operations {
+            if !routes.iter().any(|route| operation.describes(route)) {
+                continue;
+            }
+            let method = operation.method.as_str().to_lowercase();
+            paths.entry(operation.document_path())
+                .or_default()
+                .insert(method, Value::Object(operation.operation));
+        }
+
+        json!({
+            "openapi": "3.0.3",
+            "info": {"title": self.title, "version": self.version},
+            "paths": paths,
+            "components": {"schemas": schemas},
+        })
+    }
+}
@@ -0,0 +1,286 @@
+use rocket::{Build, Data, Request, Rocket, Route};
+use rocket::data::FromData;
+use rocket::form::{Form, FromForm};
+use rocket::http::{Method, Status};
+use rocket::outcome::Outcome;
+use rocket::request::FromRequest;
+use rocket::route::{BoxFuture, Handler};
+use rocket::serde::json::serde_json::{Map, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use db::{Db, Tx};
+
+use crate::{ApiPaths, ApiResource, CRUDManyToManyControllerTrait, CRUDNestedControllerTrait, CRUDUpsertControllerTrait, IdempotencyKey, Negotiated};
+
+/// The value of a request guard, or the outcome of the route when it fails.
+macro_rules! guard {
+    ($req:expr, $data:expr, $guard:ty) => {
+        match <$guard as FromRequest>::from_request($req).await {
+            Outcome::Success(value) => value,
+            Outcome::Error((status, _)) => return Outcome::Error(status),
+            Outcome::Forward(status) => return Outcome::Forward(($data, status)),
+        }
+    };
+}
+
+/// The `n`th segment of the path after the mount point, an invalid one forwarding the request as the generated routes do.
+macro_rules! param {
+    ($req:expr, $data:expr, $n:expr) => {
+        match $req.param($n) {
+            Some(Ok(value)) => value,
+            _ => return Outcome::Forward(($data, Status::UnprocessableEntity)),
+        }
+    };
+}
+
+/// The body of the request, read last as it consumes the data.
+macro_rules! body {
+    ($req:expr, $data:expr, $body:ty) => {
+        match <$body as FromData>::from_data($req, $data).await {
+            Outcome::Success(value) => value,
+            Outcome::Error((status, _)) => return Outcome::Error(status),
+            Outcome::Forward((data, status)) => return Outcome::Forward((data, status)),
+        }
+    };
+}
+
+/// Routes of a CRUD controller, implemented by CRUDControllerImpl.
+pub trait CrudRoutes {
+    type Resource: ApiResource;
+
+    /// The routes of the resource and of its relations, along with the base to mount them at
+    fn routes(paths: &ApiPaths) -> Vec<(String, Vec<Route>)>;
+}
+
+/// Routes of the relations and of the natural key of a model, handled by `Controller`, implemented by CRUDModel.
+pub trait RelationRoutes<Controller> {
+    /// The routes of the natural key and of the relations to registered resources, along with the base to mount them at
+    fn relation_routes(paths: &ApiPaths) -> Vec<(String, Vec<Route>)>;
+}
+
+/// Routes of a resource and of its relations, by base.
+type ResourceRoutes = fn(&ApiPaths) -> Vec<(String, Vec<Route>)>;
+
+/// The CRUD resources of the rocket, the bases of the related resources being needed to mount the relations.
+#[derive(Default)]
+pub struct RouteRegistry {
+    paths: ApiPaths,
+    resources: Vec<ResourceRoutes>,
+}
+
+impl RouteRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mount the routes of a controller, its resource being mounted at `base`, e.g. `/api/cars`.
+    pub fn resource<Controller: CrudRoutes>(mut self, base: &str) -> Self {
+        self.paths.insert::<Controller::Resource>(base);
+        self.resources.push(Controller::routes);
+        self
+    }
+
+    /// Mount the routes of the registered resources, the relations to unregistered resources being left out.
+    pub fn mount(self, rocket: Rocket<Build>) -> Rocket<Build> {
+        self.resources.iter()
+            .flat_map(|routes| routes(&self.paths))
+            .fold(rocket, |rocket, (base, routes)| rocket.mount(base.as_str(), routes))
+    }
+}
+
+/// A route named after the operation of the OpenAPI document describing it.
+fn route<H: Handler>(method: Method, uri: &str, name: String, handler: H) -> Route {
+    let mut route = Route::new(method, uri, handler);
+    route.name = Some(name.into());
+    route
+}
+
+/// Routes of `Model` nested under the parent of its `#[belongsTo(parent)]` field, mounted at the base of the parent
+/// with the segment of the resource, e.g. `GET /api/owners/<owner_id>/cars`.
+pub fn nested_routes<Controller, Model, Parent, CreateModel>(paths: &ApiPaths, parent: &str) -> Option<(String, Vec<Route>)>
+where
+    Controller: CRUDNestedControllerTrait<Parent, Model, CreateModel> + 'static,
+    Model: ApiResource + Serialize + Send + 'static,
+    Parent: Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    let collection = format!("/<{}_id>/{}", parent, paths.get(Model::MODULE)?.segment());
+    let single = format!("{}/<obj_id>", collection);
+    let name = |operation: &str| format!("{}_{}_by_{}", operation, Model::MODULE, parent);
+    Some((paths.get(parent)?.base.clone(), vec![
+        route(Method::Get, &collection, name("reads"), reads_by_parent::<Controller, Model, Parent, CreateModel>),
+        route(Method::Get, &single, name("read"), read_by_parent::<Controller, Model, Parent, CreateModel>),
+        route(Method::Post, &collection, name("post"), post_for_parent::<Controller, Model, Parent, CreateModel>),
+    ]))
+}
+
+/// Routes of a model with a `#[naturalKey(...)]` attribute, mounted at its base, e.g. `PUT /api/cars/by-key`.
+pub fn upsert_routes<Controller, Model, Key, CreateModel>(paths: &ApiPaths) -> Option<(String, Vec<Route>)>
+where
+    Controller: CRUDUpsertControllerTrait<Model, Key, CreateModel> + 'static,
+    Model: ApiResource + Serialize + Send + 'static,
+    Key: for<'a> FromForm<'a> + Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    let name = |operation: &str| format!("{}_{}", operation, Model::MODULE);
+    Some((paths.get(Model::MODULE)?.base.clone(), vec![
+        route(Method::Put, "/by-key", name("upsert_by_key"), upsert_by_key::<Controller, Model, Key, CreateModel>),
+        route(Method::Put, "/_upsert", name("upsert_many"), upsert_many::<Controller, Model, Key, CreateModel>),
+    ]))
+}
+
+/// Routes of the `#[manyToMany(related, via = join_table)]` relation of `Model`, mounted at its base with the
+/// segment of the related resource, e.g. `PUT /api/animals/<obj_id>/caretakers`.
+pub fn link_routes<Controller, Model, Related, RelatedModel>(paths: &ApiPaths, related: &str) -> Option<(String, Vec<Route>)>
+where
+    Controller: CRUDManyToManyControllerTrait<Related, RelatedModel> + 'static,
+    Model: ApiResource,
+    Related: Send + 'static,
+    RelatedModel: Serialize + Send + 'static,
+{
+    let resource = paths.get(Model::MODULE)?;
+    let collection = format!("/<obj_id>/{}", paths.get(related)?.segment());
+    let single = format!("{}/<{}_id>", collection, related);
+    let name = |operation: &str| format!("{}_{}_{}", operation, Model::MODULE, related);
+    Some((resource.base.clone(), vec![
+        route(Method::Get, &collection, name("reads_linked"), reads_linked::<Controller, Related, RelatedModel>),
+        route(Method::Put, &collection, name("replace_links"), replace_links::<Controller, Related, RelatedModel>),
+        route(Method::Post, &single, name("link"), link::<Controller, Related, RelatedModel>),
+        route(Method::Delete, &single, name("unlink"), unlink::<Controller, Related, RelatedModel>),
+    ]))
+}
+
+fn reads_linked<'r, Controller, Related, RelatedModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDManyToManyControllerTrait<Related, RelatedModel> + 'static,
+    Related: Send + 'static,
+    RelatedModel: Serialize + Send + 'static,
+{
+    Box::pin(async move {
+        let obj_id: i32 = param!(req, data, 0);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        Outcome::from(req, <Controller as CRUDManyToManyControllerTrait<Related, RelatedModel>>::reads_linked(obj_id, tx).await)
+    })
+}
+
+fn replace_links<'r, Controller, Related, RelatedModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDManyToManyControllerTrait<Related, RelatedModel> + 'static,
+    Related: Send + 'static,
+    RelatedModel: Serialize + Send + 'static,
+{
+    Box::pin(async move {
+        let obj_id: i32 = param!(req, data, 0);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        let related_ids = body!(req, data, Negotiated<Vec<i32>>);
+        Outcome::from(req, <Controller as CRUDManyToManyControllerTrait<Related, RelatedModel>>::replace_links(obj_id, related_ids, tx).await)
+    })
+}
+
+fn link<'r, Controller, Related, RelatedModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDManyToManyControllerTrait<Related, RelatedModel> + 'static,
+    Related: Send + 'static,
+    RelatedModel: Serialize + Send + 'static,
+{
+    Box::pin(async move {
+        let obj_id: i32 = param!(req, data, 0);
+        let related_id: i32 = param!(req, data, 2);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        Outcome::from(req, <Controller as CRUDManyToManyControllerTrait<Related, RelatedModel>>::link(obj_id, related_id, tx).await)
+    })
+}
+
+fn unlink<'r, Controller, Related, RelatedModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDManyToManyControllerTrait<Related, RelatedModel> + 'static,
+    Related: Send + 'static,
+    RelatedModel: Serialize + Send + 'static,
+{
+    Box::pin(async move {
+        let obj_id: i32 = param!(req, data, 0);
+        let related_id: i32 = param!(req, data, 2);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        Outcome::from(req, <Controller as CRUDManyToManyControllerTrait<Related, RelatedModel>>::unlink(obj_id, related_id, tx).await)
+    })
+}
+
+fn reads_by_parent<'r, Controller, Model, Parent, CreateModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDNestedControllerTrait<Parent, Model, CreateModel> + 'static,
+    Model: Serialize + Send + 'static,
+    Parent: Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    Box::pin(async move {
+        let parent_id: i32 = param!(req, data, 0);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        Outcome::from(req, <Controller as CRUDNestedControllerTrait<Parent, Model, CreateModel>>::reads_by_parent(parent_id, tx).await)
+    })
+}
+
+fn read_by_parent<'r, Controller, Model, Parent, CreateModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDNestedControllerTrait<Parent, Model, CreateModel> + 'static,
+    Model: Serialize + Send + 'static,
+    Parent: Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    Box::pin(async move {
+        let parent_id: i32 = param!(req, data, 0);
+        let obj_id: i32 = param!(req, data, 2);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        Outcome::from(req, <Controller as CRUDNestedControllerTrait<Parent, Model, CreateModel>>::read_by_parent(parent_id, obj_id, tx).await)
+    })
+}
+
+fn post_for_parent<'r, Controller, Model, Parent, CreateModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDNestedControllerTrait<Parent, Model, CreateModel> + 'static,
+    Model: Serialize + Send + 'static,
+    Parent: Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    Box::pin(async move {
+        let parent_id: i32 = param!(req, data, 0);
+        let tx = guard!(req, data, Tx<'r, Db>);
+        let idempotency_key = guard!(req, data, Option<IdempotencyKey>);
+        let obj = body!(req, data, Negotiated<CreateModel>);
+        let created = <Controller as CRUDNestedControllerTrait<Parent, Model, CreateModel>>::post_for_parent(parent_id, obj, tx, req.uri(), idempotency_key).await;
+        Outcome::from(req, created)
+    })
+}
+
+fn upsert_by_key<'r, Controller, Model, Key, CreateModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDUpsertControllerTrait<Model, Key, CreateModel> + 'static,
+    Model: Serialize + Send + 'static,
+    Key: for<'a> FromForm<'a> + Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    Box::pin(async move {
+        // The fields of the key are the query, an incomplete one forwarding the request like an invalid segment
+        let key = match Form::<Key>::parse_iter(req.query_fields()) {
+            Ok(key) => key,
+            Err(_) => return Outcome::Forward((data, Status::UnprocessableEntity)),
+        };
+        let tx = guard!(req, data, Tx<'r, Db>);
+        let obj = body!(req, data, Negotiated<Map<String, Value>>);
+        Outcome::from(req, <Controller as CRUDUpsertControllerTrait<Model, Key, CreateModel>>::upsert_by_key(key, obj, tx).await)
+    })
+}
+
+fn upsert_many<'r, Controller, Model, Key, CreateModel>(req: &'r Request<'_>, data: Data<'r>) -> BoxFuture<'r>
+where
+    Controller: CRUDUpsertControllerTrait<Model, Key, CreateModel> + 'static,
+    Model: Serialize + Send + 'static,
+    Key: for<'a> FromForm<'a> + Send + 'static,
+    CreateModel: DeserializeOwned + Send + 'static,
+{
+    Box::pin(async move {
+        let tx = guard!(req, data, Tx<'r, Db>);
+        let objs = body!(req, data, Negotiated<Vec<CreateModel>>);
+        Outcome::from(req, <Controller as CRUDUpsertControllerTrait<Model, Key, CreateModel>>::upsert_many(objs, tx).await)
+    })
+}
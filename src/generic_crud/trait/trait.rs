@@ -1,35 +1,618 @@
 use async_trait::async_trait;
-use sea_orm_rocket::Connection;
-use rocket::serde::json::Json;
 use rocket::http::uri::Origin;
-use rocket::response::status::{Created, NoContent};
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::response::status::{BadRequest, Conflict, Created, Custom, NoContent};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rocket::futures::stream::BoxStream;
+use rocket::http::ContentType;
+use rocket::response::stream::ByteStream;
+use rocket::FromFormField;
+use rocket::serde::json::serde_json::{self, Map, Value};
+use sea_orm_rocket::Connection;
+use sea_orm::{ActiveModelBehavior, ActiveModelTrait, ColumnTrait, ColumnType, Condition, ConnectionTrait, DatabaseTransaction, DbErr, EntityTrait, Iterable, RuntimeErr, StreamTrait, TransactionTrait};
+use sea_orm::sea_query::{Alias, Expr, Query, ReturningClause, SimpleExpr};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-use sea_orm::{DatabaseConnection};
+use db::{Db, Tx};
 
-use db::Db;
+mod batch;
+mod cbor;
+mod columnar;
+mod export;
+#[cfg(feature = "graphql")]
+mod graphql;
+mod idempotency;
+mod import;
+mod msgpack;
+mod negotiated;
+mod openapi;
+mod routes;
+
+pub use generic_crud_dto::{deserialize_some, ByIds, IdsRequest, Included};
+
+pub use batch::{BatchOperation, BatchRegistry, BatchResource, BatchResult, CrudResource};
+pub use columnar::{arrow_file, ArrowFields, ArrowType, EntityStream, ARROW_BATCH_SIZE};
+pub use export::{csv_header, csv_interrupted, csv_record, ndjson_interrupted, stream_body, FieldNames};
+#[cfg(feature = "graphql")]
+pub use graphql::{GraphQLRegistry, GraphQLRequest, GraphQLResponse, GraphQLSchema, GraphiQLSource};
+pub use idempotency::{IdempotencyKey, Idempotent, IdempotentRequest, StoredResponse, IDEMPOTENCY_KEY_TTL};
+pub use import::{parse_rows, ImportBody, ImportError, ImportReport, IMPORT_BATCH_SIZE};
+pub use negotiated::{respond_json, BodyFormat, Negotiated};
+pub use openapi::{crud_operations, enum_schema, field_schema, json_schema, schema_ref, ApiOperation, ApiOperations, ApiPath, ApiPaths, ApiRegistry, ApiResource, ApiSchema, ResourceSchemas};
+pub use routes::{link_routes, nested_routes, upsert_routes, CrudRoutes, RelationRoutes, RouteRegistry};
 
 #[async_trait]
 pub trait CRUDControllerTrait<Model, CreateModel, PartialModel> {
-    async fn reads(conn: Connection<'_, Db>) -> Json<Vec<Model>>;
-    async fn read(obj_id: i32, conn: Connection<'_, Db>) -> Option<Json<Model>>;
-    async fn post(car: Json<CreateModel>, conn: Connection<'_, Db>, uri: &Origin<'_>) -> Created<Json<Model>>;
-    async fn patch(obj_id: i32, car: Json<PartialModel>, conn: Connection<'_, Db>) -> Option<Json<Model>>;
-    async fn delete(obj_id: i32, conn: Connection<'_, Db>) -> Option<NoContent>;
+    /// All the objects, or the ones matching `filter` ordered by id, paginated when `limit` or `offset` is set
+    async fn reads(include: Option<&str>, ids: Option<&str>, filter: BTreeMap<String, String>, limit: Option<u64>, offset: Option<u64>, tx: Tx<'_, Db>) -> Result<Negotiated<ListResponse<Included<Model>>>, QueryError>;
+    async fn reads_by_ids(ids: Negotiated<IdsRequest>, include: Option<&str>, tx: Tx<'_, Db>) -> Result<Negotiated<ByIds<Included<Model>>>, QueryError>;
+    /// Stream the objects without loading them in memory, outside of the request transaction, filtered and paginated like the list
+    fn stream<'r>(format: StreamFormat, ids: Option<&str>, filter: BTreeMap<String, String>, limit: Option<u64>, offset: Option<u64>, conn: Connection<'r, Db>) -> Result<StreamResponse<'r>, BadRequest<String>>;
+    async fn read(obj_id: i32, include: Option<&str>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Included<Model>>>, QueryError>;
+    async fn post(car: Negotiated<CreateModel>, tx: Tx<'_, Db>, uri: &Origin<'_>, idempotency_key: Option<IdempotencyKey>) -> CreatedResponse<Model>;
+    async fn patch(obj_id: i32, car: Negotiated<PartialModel>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Model>>, WriteError>;
+    async fn delete(obj_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, DeleteError>;
+    /// Insert all the rows of a CSV or NDJSON body, or none of them when a row has an error
+    async fn import(body: ImportBody, dry_run: Option<bool>, tx: Tx<'_, Db>) -> Result<Custom<Negotiated<ImportReport>>, WriteError>;
+    /// JSON Schemas of the model and of its request bodies
+    fn schema() -> Negotiated<ResourceSchemas>;
 }
 
 #[async_trait]
 pub trait CRUDServiceTrait<Model, CreateModel, PartialModel> {
-    async fn get_all(db: &DatabaseConnection) -> Vec<Model>;
-    async fn get_by_id(obj_id: i32, db: &DatabaseConnection) -> Option<Model>;
-    async fn create(form: CreateModel, db: &DatabaseConnection) -> Model;
-    async fn update(obj_id: i32, form: PartialModel, db: &DatabaseConnection) -> Option<Model>;
-    async fn delete(obj_id: i32, db: &DatabaseConnection) -> Option<()>;
+    async fn get_all<C: ConnectionTrait + TransactionTrait>(db: &C) -> Vec<Model>;
+    /// Objects matching `condition` ordered by id, skipping the first `offset` ones
+    async fn get_page<C: ConnectionTrait + TransactionTrait>(condition: Condition, offset: u64, limit: Option<u64>, db: &C) -> Vec<Model>;
+    /// Objects with the given ids and matching `condition`, fetched row by row as the stream is polled, ordered by id
+    /// and skipping the first `offset` ones, a database error ending the stream
+    fn stream_all<C: ConnectionTrait + StreamTrait>(obj_ids: Option<Vec<i32>>, condition: Condition, offset: u64, limit: Option<u64>, db: &C) -> BoxStream<'_, Result<Model, DbErr>>;
+    async fn get_by_id<C: ConnectionTrait + TransactionTrait>(obj_id: i32, db: &C) -> Option<Model>;
+    /// Objects with the given ids, in no particular order
+    async fn get_by_ids<C: ConnectionTrait + TransactionTrait>(obj_ids: &[i32], db: &C) -> Vec<Model>;
+    /// Insert the object, fails when the database rejects it, e.g. when it has the unique key of an existing one
+    async fn create<C: ConnectionTrait + TransactionTrait>(form: CreateModel, db: &C) -> Result<Model, WriteError>;
+    /// `Ok(None)` when the object doesn't exist
+    async fn update<C: ConnectionTrait + TransactionTrait>(obj_id: i32, form: PartialModel, db: &C) -> Result<Option<Model>, WriteError>;
+    /// `Ok(None)` when the object doesn't exist
+    async fn delete<C: ConnectionTrait + TransactionTrait>(obj_id: i32, db: &C) -> Result<Option<()>, DeleteError>;
+    /// Insert the rows in batches of `IMPORT_BATCH_SIZE`, returns the errors of the rows that could not be inserted
+    async fn create_many<C: ConnectionTrait + TransactionTrait>(forms: Vec<(usize, CreateModel)>, db: &C) -> Result<Vec<ImportError>, WriteError>;
+}
+
+#[async_trait]
+pub trait CRUDUpsertControllerTrait<Model, Key, CreateModel> {
+    async fn upsert_by_key(key: Key, obj: Negotiated<Map<String, Value>>, tx: Tx<'_, Db>) -> Result<Custom<Negotiated<Model>>, Custom<String>>;
+    /// Insert or update the objects by their natural key, in a single statement for the objects setting the same fields
+    async fn upsert_many(objs: Negotiated<Vec<CreateModel>>, tx: Tx<'_, Db>) -> Result<Negotiated<Vec<Upserted<Model>>>, WriteError>;
+}
+
+/// An object of a bulk upsert, with whether it was inserted or updated.
+#[derive(Clone, Debug, Serialize)]
+pub struct Upserted<Model> {
+    pub inserted: bool,
+    pub data: Model,
+}
+
+#[async_trait]
+pub trait CRUDUpsertServiceTrait<Model, CreateModel> {
+    /// Insert or update the object with the natural key of `form`, returns whether it was inserted.
+    async fn upsert<C: ConnectionTrait + TransactionTrait>(form: CreateModel, db: &C) -> Result<(Model, bool), WriteError>;
+    /// Insert or update many objects in the order of `forms`, returns whether each one was inserted.
+    ///
+    /// All the objects are rejected with a 422 when two of them have the same natural key, or one references a missing parent.
+    async fn upsert_many<C: ConnectionTrait + TransactionTrait>(forms: Vec<CreateModel>, db: &C) -> Result<Vec<(Model, bool)>, WriteError>;
+}
+
+#[async_trait]
+pub trait CRUDNestedControllerTrait<Parent, Model, CreateModel> {
+    async fn reads_by_parent(parent_id: i32, tx: Tx<'_, Db>) -> Option<Negotiated<Vec<Model>>>;
+    async fn read_by_parent(parent_id: i32, obj_id: i32, tx: Tx<'_, Db>) -> Option<Negotiated<Model>>;
+    async fn post_for_parent(parent_id: i32, obj: Negotiated<CreateModel>, tx: Tx<'_, Db>, uri: &Origin<'_>, idempotency_key: Option<IdempotencyKey>) -> Option<CreatedResponse<Model>>;
+}
+
+#[async_trait]
+pub trait CRUDNestedServiceTrait<Parent, Model, CreateModel> {
+    async fn get_all_by_parent<C: ConnectionTrait + TransactionTrait>(parent_id: i32, db: &C) -> Option<Vec<Model>>;
+    async fn get_by_parent<C: ConnectionTrait + TransactionTrait>(parent_id: i32, obj_id: i32, db: &C) -> Option<Model>;
+    /// `Ok(None)` when the parent doesn't exist
+    async fn create_for_parent<C: ConnectionTrait + TransactionTrait>(parent_id: i32, form: CreateModel, db: &C) -> Result<Option<Model>, WriteError>;
+}
+
+/// Implemented by models to embed the relations requested with `?include=` in their output.
+#[async_trait]
+pub trait LoadRelations: Sized + Send {
+    async fn load_relations<C: ConnectionTrait>(objs: Vec<Self>, include: &[String], db: &C) -> Result<Vec<Included<Self>>, QueryError>;
+}
+
+/// Response of the list routes, a plain array unless specific ids were requested.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum ListResponse<Model> {
+    All(Vec<Model>),
+    ByIds(ByIds<Model>),
+}
+
+/// Body of the streaming list and export routes, with the content type of its format.
+pub type StreamResponse<'r> = (ContentType, ByteStream<BoxStream<'r, Vec<u8>>>);
+
+/// Format of the streaming list and export routes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromFormField)]
+pub enum StreamFormat {
+    /// A JSON array
+    #[field(value = "json")]
+    Json,
+    /// Newline delimited JSON
+    #[field(value = "ndjson")]
+    Ndjson,
+    /// CSV with a header row
+    #[field(value = "csv")]
+    Csv,
+    /// An Arrow IPC file
+    #[field(value = "arrow")]
+    Arrow,
+}
+
+impl StreamFormat {
+    pub fn content_type(self) -> ContentType {
+        match self {
+            StreamFormat::Json => ContentType::JSON,
+            StreamFormat::Ndjson => ContentType::new("application", "x-ndjson"),
+            StreamFormat::Csv => ContentType::CSV,
+            StreamFormat::Arrow => ContentType::new("application", "vnd.apache.arrow.file"),
+        }
+    }
+}
+
+/// Parse the `?ids=1,5,9` query parameter.
+pub fn parse_ids(ids: &str) -> Result<Vec<i32>, String> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse().map_err(|_| format!("Invalid id `{}`", id)))
+        .collect()
+}
+
+/// Parse the `?filter[field]=value` query parameters into the condition of the list routes.
+///
+/// A value is read as the field of the partial model, as JSON or else as a string, so that `filter[year]=2020`
+/// and `filter[brand]=Renault` both match, `null` matching the objects without a value.
+/// Only the returned fields that can be updated are filtered on.
+pub fn parse_filter<Model, PartialModel, A>(filter: &BTreeMap<String, String>) -> Result<Condition, String>
+where
+    Model: FieldNames,
+    PartialModel: DeserializeOwned + ToActiveModel<A>,
+    A: ActiveModelTrait,
+{
+    let field_names = Model::field_names();
+    let mut condition = Condition::all();
+    for (field, value) in filter {
+        if !field_names.contains(&field.as_str()) {
+            return Err(format!("Unknown filter field `{}`", field));
+        }
+        let parsed = serde_json::from_str(value).ok().into_iter().chain([Value::String(value.clone())])
+            .find_map(|json| {
+                let body = Value::Object(Map::from_iter([(field.clone(), json.clone())]));
+                serde_json::from_value::<PartialModel>(body).ok().map(|partial| (json, partial.into_active_model()))
+            });
+        let (json, obj) = parsed.ok_or_else(|| format!("Invalid value `{}` of the `{}` filter", value, field))?;
+        let (column, value) = <A::Entity as EntityTrait>::Column::iter()
+            .find_map(|column| obj.get(column).into_value().map(|value| (column, value)))
+            .ok_or_else(|| format!("`{}` can't be filtered on", field))?;
+        condition = condition.add(if json.is_null() { column.is_null() } else { column.eq(value) });
+    }
+    Ok(condition)
+}
+
+/// Parse the `?limit=` and `?offset=` query parameters, a negative or non numeric value being rejected.
+pub fn parse_pagination(limit: Option<&str>, offset: Option<&str>) -> Result<(Option<u64>, Option<u64>), String> {
+    let parse = |name: &str, value: Option<&str>| value
+        .map(|value| value.trim().parse().map_err(|_| format!("Invalid {} `{}`, a non-negative integer is expected", name, value)))
+        .transpose();
+    Ok((parse("limit", limit)?, parse("offset", offset)?))
+}
+
+/// The objects fetched with `?ids=` are in the requested order, which `?limit=` and `?offset=` can't page.
+pub fn check_unpaginated(limit: Option<u64>, offset: Option<u64>) -> Result<(), String> {
+    if limit.is_some() || offset.is_some() {
+        return Err("`limit` and `offset` can't be combined with `ids`".to_owned());
+    }
+    Ok(())
+}
+
+/// A read rejected for its query parameters, or failed in the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryError {
+    /// An invalid `ids`, `filter` or `include` parameter, answered with a 400
+    Invalid(String),
+    /// A failed query, answered with a 500
+    Database(String),
+}
+
+impl QueryError {
+    pub fn unknown_relation(relation: &str) -> Self {
+        QueryError::Invalid(format!("Unknown relation `{}`", relation))
+    }
+}
+
+impl From<DbErr> for QueryError {
+    fn from(err: DbErr) -> Self {
+        QueryError::Database(err.to_string())
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Invalid(err) => f.write_str(err),
+            QueryError::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for QueryError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            QueryError::Invalid(err) => BadRequest(err).respond_to(req),
+            QueryError::Database(err) => {
+                rocket::error!("Cannot read the objects: {}", err);
+                Custom(Status::InternalServerError, format!("Database error: {}", err)).respond_to(req)
+            }
+        }
+    }
+}
+
+/// Split an `?include=owner,owner.address` query parameter into relation paths.
+pub fn parse_includes(include: Option<&str>) -> Vec<String> {
+    include.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Group relation paths by their first segment, `owner.address` gives `owner => [address]`.
+pub fn group_includes(include: &[String]) -> BTreeMap<String, Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in include {
+        let (relation, nested) = match path.split_once('.') {
+            Some((relation, nested)) => (relation, Some(nested)),
+            None => (path.as_str(), None),
+        };
+        let group = groups.entry(relation.to_owned()).or_default();
+        if let Some(nested) = nested {
+            group.push(nested.to_owned());
+        }
+    }
+    groups
+}
+
+#[async_trait]
+pub trait CRUDManyToManyControllerTrait<Related, RelatedModel> {
+    async fn reads_linked(obj_id: i32, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Vec<RelatedModel>>>, WriteError>;
+    async fn replace_links(obj_id: i32, related_ids: Negotiated<Vec<i32>>, tx: Tx<'_, Db>) -> Result<Option<Negotiated<Vec<RelatedModel>>>, WriteError>;
+    async fn link(obj_id: i32, related_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, WriteError>;
+    async fn unlink(obj_id: i32, related_id: i32, tx: Tx<'_, Db>) -> Result<Option<NoContent>, WriteError>;
+}
+
+#[async_trait]
+pub trait CRUDManyToManyServiceTrait<Related, RelatedModel> {
+    /// `Ok(None)` when the object doesn't exist
+    async fn get_linked<C: ConnectionTrait + TransactionTrait>(obj_id: i32, db: &C) -> Result<Option<Vec<RelatedModel>>, WriteError>;
+    /// `Ok(None)` when the object doesn't exist, the unknown related ids being rejected with a 422 listing them
+    async fn replace_links<C: ConnectionTrait + TransactionTrait>(obj_id: i32, related_ids: Vec<i32>, db: &C) -> Result<Option<Vec<RelatedModel>>, WriteError>;
+    /// `Ok(None)` when the object or the related one doesn't exist
+    async fn link<C: ConnectionTrait + TransactionTrait>(obj_id: i32, related_id: i32, db: &C) -> Result<Option<()>, WriteError>;
+    /// `Ok(None)` when the objects weren't linked
+    async fn unlink<C: ConnectionTrait + TransactionTrait>(obj_id: i32, related_id: i32, db: &C) -> Result<Option<()>, WriteError>;
 }
 
 pub trait FromEntity<EntityModel> {
     fn from_entity(entity: EntityModel) -> Self;
 }
 
-pub trait ToActiveModel<ActiveModel, Model> {
-    fn into_active_model(self, placeholder: Model) -> ActiveModel;
-}
\ No newline at end of file
+/// `RETURNING` clause of all the columns of an entity, enums cast as text as in SeaORM selects.
+pub fn returning_all<E: EntityTrait>() -> ReturningClause {
+    Query::returning().exprs(returning_columns::<E>())
+}
+
+/// The expressions of [`returning_all`].
+pub fn returning_columns<E: EntityTrait>() -> Vec<SimpleExpr> {
+    E::Column::iter()
+        .map(|column| match column.def().get_column_type() {
+            ColumnType::Enum { .. } => Expr::col(column).as_enum(Alias::new("text")),
+            _ => Expr::col(column).into(),
+        })
+        .collect()
+}
+
+/// Implemented by the Partial* structs, the fields missing from the request are left `NotSet`.
+pub trait ToActiveModel<ActiveModel> {
+    fn into_active_model(self) -> ActiveModel;
+}
+
+/// Implemented by models with a `#[belongsTo(parent)]` field, `Parent` being the parent entity.
+pub trait BelongsTo<Parent: EntityTrait> {
+    type Column: ColumnTrait;
+
+    /// Column of the child entity referencing the parent
+    fn parent_column() -> Self::Column;
+}
+
+/// Implemented by every model, returning its `#[idField]`.
+pub trait ObjectId {
+    fn obj_id(&self) -> i32;
+}
+
+/// Implemented by every model to check the parents of its `#[belongsTo(parent)]` fields before a write.
+#[async_trait]
+pub trait CheckParents<ActiveModel> {
+    /// The first `#[belongsTo]` field set by `obj` to the id of a missing parent
+    async fn missing_parent<C: ConnectionTrait>(obj: &ActiveModel, db: &C) -> Result<Option<&'static str>, DbErr>;
+}
+
+/// Implemented by models with a `#[manyToMany(related, via = join_table)]` attribute, `Related` being the related entity.
+pub trait ManyToMany<Related: EntityTrait> {
+    /// Join table entity
+    type Via: EntityTrait;
+    type ViaActiveModel: ActiveModelTrait<Entity = Self::Via> + ActiveModelBehavior + Send;
+    /// Model returned for the related entity
+    type RelatedModel: FromEntity<Related::Model> + Send;
+
+    /// Columns of the join table referencing this model and the related entity
+    fn via_columns() -> (<Self::Via as EntityTrait>::Column, <Self::Via as EntityTrait>::Column);
+}
+
+/// Implemented by models with a `#[naturalKey(...)]` attribute.
+pub trait NaturalKey {
+    /// Query parameters of the `by-key` route
+    type Key: Serialize + Send;
+    type Column: ColumnTrait;
+
+    /// Columns of the unique index identifying an object
+    fn key_columns() -> Vec<Self::Column>;
+    /// Columns set on insert that an upsert must not overwrite
+    fn insert_only_columns() -> Vec<Self::Column>;
+}
+
+/// Implemented by every model, soft deletable models have a `#[deletedAt]` field.
+pub trait SoftDelete {
+    type Column: ColumnTrait;
+
+    /// Column set when a row is soft deleted
+    fn deleted_at_column() -> Option<Self::Column>;
+}
+
+/// Implemented by the soft deletable models, the children of an `onDelete = softCascade` relation must be.
+#[diagnostic::on_unimplemented(
+    message = "onDelete = softCascade requires a #[deletedAt] field on `{Self}`",
+    label = "`{Self}` has no #[deletedAt] field",
+)]
+pub trait SoftDeletable: SoftDelete {
+    fn deleted_at_column() -> Self::Column;
+}
+
+/// Implemented by every model to apply the `onDelete` policies of its `#[hasMany(child, onDelete = ...)]` relations.
+#[async_trait]
+pub trait DeletePolicies {
+    /// Called within the transaction deleting `obj_id`, before it is deleted.
+    async fn before_delete(obj_id: i32, txn: &DatabaseTransaction) -> Result<(), DeleteError>;
+}
+
+/// Ids of the children preventing a delete, by relation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct BlockingChildren(pub BTreeMap<String, Vec<i32>>);
+
+/// A delete prevented by children or rejected by the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeleteError {
+    /// Children with an `onDelete = restrict` policy prevent the delete, answered with a 409 listing them
+    Blocked(BlockingChildren),
+    /// Rejected by the database, e.g. by the foreign key of children without a policy
+    Write(WriteError),
+}
+
+impl From<DbErr> for DeleteError {
+    fn from(err: DbErr) -> Self {
+        DeleteError::Write(WriteError::from_db_err(&err))
+    }
+}
+
+impl DeleteError {
+    pub fn status(&self) -> Status {
+        match self {
+            DeleteError::Blocked(_) => Status::Conflict,
+            DeleteError::Write(err) => err.status(),
+        }
+    }
+}
+
+impl fmt::Display for DeleteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeleteError::Blocked(_) => write!(f, "The object has children preventing its deletion"),
+            DeleteError::Write(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for DeleteError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            DeleteError::Blocked(blocking) => Conflict(Negotiated(blocking)).respond_to(req),
+            DeleteError::Write(err) => err.respond_to(req),
+        }
+    }
+}
+
+/// A null of the SQL type of `column_type`, as Postgres doesn't cast a null parameter of another type.
+pub fn null_value(column_type: &ColumnType) -> sea_orm::Value {
+    match column_type {
+        ColumnType::Boolean => sea_orm::Value::Bool(None),
+        ColumnType::TinyInteger => sea_orm::Value::TinyInt(None),
+        ColumnType::SmallInteger => sea_orm::Value::SmallInt(None),
+        ColumnType::Integer => sea_orm::Value::Int(None),
+        ColumnType::BigInteger => sea_orm::Value::BigInt(None),
+        ColumnType::TinyUnsigned => sea_orm::Value::TinyUnsigned(None),
+        ColumnType::SmallUnsigned => sea_orm::Value::SmallUnsigned(None),
+        ColumnType::Unsigned => sea_orm::Value::Unsigned(None),
+        ColumnType::BigUnsigned => sea_orm::Value::BigUnsigned(None),
+        ColumnType::Float => sea_orm::Value::Float(None),
+        ColumnType::Double => sea_orm::Value::Double(None),
+        ColumnType::Decimal(_) | ColumnType::Money(_) => sea_orm::Value::Decimal(None),
+        ColumnType::Date => sea_orm::Value::ChronoDate(None),
+        ColumnType::Time => sea_orm::Value::ChronoTime(None),
+        ColumnType::DateTime | ColumnType::Timestamp => sea_orm::Value::ChronoDateTime(None),
+        ColumnType::TimestampWithTimeZone => sea_orm::Value::ChronoDateTimeWithTimeZone(None),
+        ColumnType::Uuid => sea_orm::Value::Uuid(None),
+        ColumnType::Json | ColumnType::JsonBinary => sea_orm::Value::Json(None),
+        ColumnType::Binary | ColumnType::TinyBinary | ColumnType::MediumBinary | ColumnType::LongBinary => sea_orm::Value::Bytes(None),
+        _ => sea_orm::Value::String(None),
+    }
+}
+
+/// Unique constraint, e.g. a natural key, violated by an insert.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DuplicateKey {
+    pub error: String,
+    pub constraint: Option<String>,
+}
+
+impl DuplicateKey {
+    /// The violated constraint when `err` is a unique violation (SQLSTATE 23505), `None` for any other error.
+    pub fn from_db_err(err: &DbErr) -> Option<Self> {
+        match WriteError::from_db_err(err) {
+            WriteError::Duplicate(duplicate) => Some(duplicate),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.constraint {
+            Some(constraint) => write!(f, "{} ({})", self.error, constraint),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// Foreign key or check constraint violated by a write.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InvalidValue {
+    pub error: String,
+    pub constraint: Option<String>,
+    /// The field holding the invalid value, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl InvalidValue {
+    /// A `#[belongsTo]` field set to the id of a missing parent
+    pub fn missing_parent(field: &str) -> Self {
+        InvalidValue {
+            error: "The referenced object does not exist".to_owned(),
+            constraint: None,
+            field: Some(field.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.field, &self.constraint) {
+            (Some(field), _) => write!(f, "{} ({})", self.error, field),
+            (None, Some(constraint)) => write!(f, "{} ({})", self.error, constraint),
+            (None, None) => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// A write rejected by the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WriteError {
+    /// A unique constraint is violated, answered with a 409
+    Duplicate(DuplicateKey),
+    /// A foreign key or check constraint is violated, answered with a 422
+    Invalid(InvalidValue),
+    /// Any other database error, answered with a 500
+    Database(String),
+}
+
+impl WriteError {
+    /// Classify `err` by its SQLSTATE: unique (23505), foreign key (23503) and check (23514) violations.
+    pub fn from_db_err(err: &DbErr) -> Self {
+        let database_error = match err {
+            DbErr::Exec(RuntimeErr::SqlxError(err)) | DbErr::Query(RuntimeErr::SqlxError(err)) => err.as_database_error(),
+            _ => None,
+        };
+        let (code, constraint) = match database_error {
+            Some(db_err) => (db_err.code(), db_err.constraint().map(String::from)),
+            None => return WriteError::Database(err.to_string()),
+        };
+        let invalid = |error: &str| WriteError::Invalid(InvalidValue { error: error.to_owned(), constraint: constraint.clone(), field: None });
+        match code.as_deref() {
+            Some("23505") => WriteError::Duplicate(DuplicateKey {
+                error: "An object with the same unique key already exists".to_owned(),
+                constraint: constraint.clone(),
+            }),
+            Some("23503") => invalid("A foreign key constraint is violated"),
+            Some("23514") => invalid("A value is not allowed by a check constraint"),
+            _ => WriteError::Database(err.to_string()),
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            WriteError::Duplicate(_) => Status::Conflict,
+            WriteError::Invalid(_) => Status::UnprocessableEntity,
+            WriteError::Database(_) => Status::InternalServerError,
+        }
+    }
+}
+
+impl From<DbErr> for WriteError {
+    fn from(err: DbErr) -> Self {
+        WriteError::from_db_err(&err)
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Duplicate(duplicate) => duplicate.fmt(f),
+            WriteError::Invalid(invalid) => invalid.fmt(f),
+            WriteError::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for WriteError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            WriteError::Duplicate(duplicate) => Conflict(Negotiated(duplicate)).respond_to(req),
+            WriteError::Invalid(invalid) => Custom(Status::UnprocessableEntity, Negotiated(invalid)).respond_to(req),
+            WriteError::Database(err) => {
+                rocket::error!("Cannot write the object: {}", err);
+                Custom(Status::InternalServerError, format!("Database error: {}", err)).respond_to(req)
+            }
+        }
+    }
+}
+
+/// Response of the create routes, a 409 when the object has the unique key of an existing one,
+/// a 422 when it references a missing object or fails a check constraint.
+pub type CreatedResponse<Model> = Idempotent<Result<Created<Negotiated<Model>>, WriteError>>;
+
+/// Server-side values applied to an active model right before it is written.
+pub trait BeforeSave<ActiveModel> {
+    fn before_insert(obj: &mut ActiveModel);
+    fn before_update(obj: &mut ActiveModel);
+}
+
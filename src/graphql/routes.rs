@@ -0,0 +1,43 @@
+use rocket::{Build, Rocket, State};
+use rocket::response::content::RawHtml;
+use rocket::serde::json::Json;
+use sea_orm_rocket::Connection;
+
+use db::Db;
+use generic_crud_trait::{GraphQLRegistry, GraphQLRequest, GraphQLResponse, GraphQLSchema, GraphiQLSource};
+
+use crate::{animal, car, caretaker, owner};
+
+/// Every request runs in its own transaction, rolled back when the response has errors.
+#[post("/graphql", data = "<request>")]
+async fn graphql(request: Json<GraphQLRequest>, schema: &State<GraphQLSchema>, conn: Connection<'_, Db>) -> Json<GraphQLResponse> {
+    Json(schema.execute(request.into_inner(), conn.into_inner()).await)
+}
+
+#[get("/graphql")]
+fn graphiql() -> RawHtml<String> {
+    RawHtml(GraphiQLSource::build().endpoint("/api/graphql").title("rocket_api").finish())
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    let schema = GraphQLRegistry::new()
+        .resource::<car::service::CarService, _, _, _, _>("cars")
+        .resource::<animal::service::AnimalService, _, _, _, _>("animals")
+        .resource::<owner::service::OwnerService, _, _, _, _>("owners")
+        .resource::<caretaker::service::CaretakerService, _, _, _, _>("caretakers")
+        .finish()
+        .expect("Invalid GraphQL schema");
+
+    // GraphiQL is enabled by the `graphiql` key of Rocket.toml, in debug builds only by default
+    let graphiql_enabled = rocket.figment().extract_inner::<bool>("graphiql").unwrap_or(cfg!(debug_assertions));
+
+    let rocket = rocket
+        .manage(schema)
+        .mount("/api", routes![graphql]);
+
+    if graphiql_enabled {
+        rocket.mount("/api", routes![graphiql])
+    } else {
+        rocket
+    }
+}
@@ -0,0 +1,56 @@
+#[macro_use] extern crate rocket;
+
+pub use rocket_api_models::entity;
+
+pub mod animal;
+pub mod batch;
+pub mod car;
+pub mod caretaker;
+pub mod export;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod openapi;
+pub mod owner;
+
+use migration::MigratorTrait;
+use rocket::fairing::AdHoc;
+use sea_orm_rocket::Database;
+use db::{Db, Tx};
+use generic_crud_trait::{IdempotencyKey, RouteRegistry};
+
+
+#[get("/")]
+fn health_check() -> &'static str {
+    "OK"
+}
+
+async fn run_migrations(rocket: rocket::Rocket<rocket::Build>) -> rocket::fairing::Result {
+    let conn = &Db::fetch(&rocket).unwrap().conn;
+    let _ = migration::Migrator::up(conn, None).await;
+    Ok(rocket)
+}
+
+pub fn rocket() -> rocket::Rocket<rocket::Build> {
+    let mut rocket = rocket::build()
+        .attach(Db::init())
+        .attach(Tx::<Db>::fairing())
+        .attach(IdempotencyKey::purge_fairing())
+        .attach(AdHoc::try_on_ignite("Migrations", run_migrations))
+        .mount("/api", routes![health_check]);
+
+    // The routes of the relations are mounted at the bases of the related resources, hence registered together
+    rocket = RouteRegistry::new()
+        .resource::<car::controller::CarController>("/api/cars")
+        .resource::<animal::controller::AnimalController>("/api/animals")
+        .resource::<owner::controller::OwnerController>("/api/owners")
+        .resource::<caretaker::controller::CaretakerController>("/api/caretakers")
+        .mount(rocket);
+    rocket = batch::routes::fuel(rocket);
+    rocket = openapi::routes::fuel(rocket);
+    #[cfg(feature = "graphql")]
+    {
+        rocket = graphql::routes::fuel(rocket);
+    }
+
+    rocket
+}
@@ -1,43 +1,20 @@
-#[macro_use] extern crate rocket;
-
-pub mod entity;
-pub mod animal;
-pub mod car;
-
-use migration::MigratorTrait;
-use rocket::fairing::AdHoc;
-use sea_orm_rocket::Database;
-use db::Db;
-
-
-#[get("/")]
-fn health_check() -> &'static str {
-    "OK"
-}
-
-async fn run_migrations(rocket: rocket::Rocket<rocket::Build>) -> rocket::fairing::Result {
-    let conn = &Db::fetch(&rocket).unwrap().conn;
-    let _ = migration::Migrator::up(conn, None).await;
-    Ok(rocket)
-}
-
-fn rocket() -> rocket::Rocket<rocket::Build> {
-    let mut rocket = rocket::build()
-        .attach(Db::init())
-        .attach(AdHoc::try_on_ignite("Migrations", run_migrations))
-        .mount("/api", routes![health_check]);
-
-    rocket = car::routes::fuel(rocket);
-    rocket = animal::routes::fuel(rocket);
-
-    rocket
-}
+use rocket_api::export;
 
 #[rocket::main]
+#[allow(clippy::result_large_err)]
 async fn main() -> Result<(), rocket::Error> {
     dotenvy::dotenv().ok();
 
-    let _rocket = rocket()
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(String::as_str) == Some("export") {
+        if let Err(err) = export::cli::run(&args[2..]).await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let _rocket = rocket_api::rocket()
         .ignite().await?
         .launch().await?;
 
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+/// Columns managed by the `#[createdAt]` and `#[updatedAt]` CRUDModel attributes.
+#[derive(Iden)]
+pub enum Timestamps {
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Add `created_at` and `updated_at` columns to an existing table.
+///
+/// Both columns default to the current timestamp so that rows already present in the table
+/// get a value without a data migration.
+pub async fn add_timestamps<T>(manager: &SchemaManager<'_>, table: T) -> Result<(), DbErr>
+where
+    T: IntoTableRef,
+{
+    manager
+        .alter_table(
+            Table::alter()
+                .table(table)
+                .add_column(
+                    ColumnDef::new(Timestamps::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null()
+                        .default(Expr::current_timestamp()),
+                )
+                .add_column(
+                    ColumnDef::new(Timestamps::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null()
+                        .default(Expr::current_timestamp()),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+/// Drop the columns added by [`add_timestamps`].
+pub async fn drop_timestamps<T>(manager: &SchemaManager<'_>, table: T) -> Result<(), DbErr>
+where
+    T: IntoTableRef,
+{
+    manager
+        .alter_table(
+            Table::alter()
+                .table(table)
+                .drop_column(Timestamps::CreatedAt)
+                .drop_column(Timestamps::UpdatedAt)
+                .to_owned(),
+        )
+        .await
+}
+
+/// Create the unique index backing a `#[naturalKey(...)]` CRUDModel attribute, required by the upserts.
+pub async fn create_natural_key_index<T, C, I>(manager: &SchemaManager<'_>, name: &str, table: T, columns: I) -> Result<(), DbErr>
+where
+    T: IntoTableRef,
+    C: IntoIndexColumn,
+    I: IntoIterator<Item = C>,
+{
+    let mut index = Index::create();
+    index.name(name).table(table).unique();
+    for column in columns {
+        index.col(column);
+    }
+    manager.create_index(index).await
+}
+
+/// Drop the index created by [`create_natural_key_index`].
+pub async fn drop_natural_key_index<T>(manager: &SchemaManager<'_>, name: &str, table: T) -> Result<(), DbErr>
+where
+    T: IntoTableRef,
+{
+    manager
+        .drop_index(Index::drop().name(name).table(table).to_owned())
+        .await
+}
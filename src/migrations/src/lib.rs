@@ -1,7 +1,17 @@
 pub use sea_orm_migration::prelude::*;
 
+pub mod helpers;
+
 mod m20220101_000001_create_cars_table;
 mod m20221227_085209_create_animal_table;
+mod m20230105_101500_add_timestamps;
+mod m20230112_093000_add_car_fuel_type_and_purchased_at;
+mod m20230120_141000_create_owner_table;
+mod m20230201_110000_create_caretaker_tables;
+mod m20230215_090000_create_idempotency_key_table;
+mod m20230301_100000_add_car_natural_key;
+mod m20230315_090000_add_car_price_tracker_and_specs;
+mod m20230320_090000_alter_idempotency_key_claims;
 
 pub struct Migrator;
 
@@ -11,6 +21,14 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_create_cars_table::Migration),
             Box::new(m20221227_085209_create_animal_table::Migration),
+            Box::new(m20230105_101500_add_timestamps::Migration),
+            Box::new(m20230112_093000_add_car_fuel_type_and_purchased_at::Migration),
+            Box::new(m20230120_141000_create_owner_table::Migration),
+            Box::new(m20230201_110000_create_caretaker_tables::Migration),
+            Box::new(m20230215_090000_create_idempotency_key_table::Migration),
+            Box::new(m20230301_100000_add_car_natural_key::Migration),
+            Box::new(m20230315_090000_add_car_price_tracker_and_specs::Migration),
+            Box::new(m20230320_090000_alter_idempotency_key_claims::Migration),
         ]
     }
 }
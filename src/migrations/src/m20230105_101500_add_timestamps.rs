@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::{add_timestamps, drop_timestamps};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_timestamps(manager, Car::Table).await?;
+        add_timestamps(manager, Animal::Table).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_timestamps(manager, Animal::Table).await?;
+        drop_timestamps(manager, Car::Table).await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Car {
+    Table,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Animal {
+    Table,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(FuelType::Table)
+                    .values([FuelType::Diesel, FuelType::Electric, FuelType::Hybrid, FuelType::Petrol])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Car::Table)
+                    .add_column(
+                        ColumnDef::new(Car::FuelType)
+                            .enumeration(FuelType::Table, [FuelType::Diesel, FuelType::Electric, FuelType::Hybrid, FuelType::Petrol])
+                            .not_null()
+                            .default("petrol"),
+                    )
+                    .add_column(ColumnDef::new(Car::PurchasedAt).timestamp_with_time_zone().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Car::Table)
+                    .drop_column(Car::FuelType)
+                    .drop_column(Car::PurchasedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(FuelType::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Car {
+    Table,
+    FuelType,
+    PurchasedAt,
+}
+
+#[derive(Iden)]
+enum FuelType {
+    #[iden = "fuel_type"]
+    Table,
+    Diesel,
+    Electric,
+    Hybrid,
+    Petrol,
+}
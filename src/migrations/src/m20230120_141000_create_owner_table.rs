@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::add_timestamps;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Owner::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Owner::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Owner::Name).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        add_timestamps(manager, Owner::Table).await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Car::Table)
+                    .add_column(ColumnDef::new(Car::OwnerId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_car_owner")
+                            .from_tbl(Car::Table)
+                            .from_col(Car::OwnerId)
+                            .to_tbl(Owner::Table)
+                            .to_col(Owner::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Car::Table)
+                    .drop_foreign_key(Alias::new("fk_car_owner"))
+                    .drop_column(Car::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Owner::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Owner {
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(Iden)]
+enum Car {
+    Table,
+    OwnerId,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::add_timestamps;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Caretaker::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Caretaker::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Caretaker::Name).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        add_timestamps(manager, Caretaker::Table).await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AnimalCaretaker::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(AnimalCaretaker::AnimalId).integer().not_null())
+                    .col(ColumnDef::new(AnimalCaretaker::CaretakerId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(AnimalCaretaker::AnimalId)
+                            .col(AnimalCaretaker::CaretakerId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_animal_caretaker_animal")
+                            .from(AnimalCaretaker::Table, AnimalCaretaker::AnimalId)
+                            .to(Animal::Table, Animal::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_animal_caretaker_caretaker")
+                            .from(AnimalCaretaker::Table, AnimalCaretaker::CaretakerId)
+                            .to(Caretaker::Table, Caretaker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AnimalCaretaker::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Caretaker::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Caretaker {
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(Iden)]
+enum AnimalCaretaker {
+    Table,
+    AnimalId,
+    CaretakerId,
+}
+
+#[derive(Iden)]
+enum Animal {
+    Table,
+    Id,
+}
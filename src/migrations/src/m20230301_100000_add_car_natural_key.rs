@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::{create_natural_key_index, drop_natural_key_index};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        create_natural_key_index(
+            manager,
+            "idx_car_natural_key",
+            Car::Table,
+            [Car::Brand, Car::Model, Car::Year],
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_natural_key_index(manager, "idx_car_natural_key", Car::Table).await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Car {
+    Table,
+    Brand,
+    Model,
+    Year,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Car::Table)
+                    .add_column(ColumnDef::new(Car::Price).decimal_len(10, 2).null())
+                    .add_column(ColumnDef::new(Car::TrackerId).uuid().null())
+                    .add_column(ColumnDef::new(Car::Specs).json_binary().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Car::Table)
+                    .drop_column(Car::Price)
+                    .drop_column(Car::TrackerId)
+                    .drop_column(Car::Specs)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Car {
+    Table,
+    Price,
+    TrackerId,
+    Specs,
+}
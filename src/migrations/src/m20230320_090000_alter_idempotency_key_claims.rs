@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A key is claimed before its response is known
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotencyKey::Table)
+                    .modify_column(ColumnDef::new(IdempotencyKey::Status).small_integer().null())
+                    .modify_column(ColumnDef::new(IdempotencyKey::Response).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The expired keys are purged by creation date
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_idempotency_key_created_at")
+                    .table(IdempotencyKey::Table)
+                    .col(IdempotencyKey::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_idempotency_key_created_at")
+                    .table(IdempotencyKey::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotencyKey::Table)
+                    .modify_column(ColumnDef::new(IdempotencyKey::Status).small_integer().not_null())
+                    .modify_column(ColumnDef::new(IdempotencyKey::Response).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum IdempotencyKey {
+    Table,
+    Status,
+    Response,
+    CreatedAt,
+}
@@ -0,0 +1,22 @@
+use std::cmp::{Eq, PartialEq};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use generic_crud_proc_macro::CRUDModel;
+#[cfg(not(feature = "server"))]
+use generic_crud_proc_macro::CRUDDto;
+
+use crate::prelude::DateTimeWithTimeZone;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(CRUDModel))]
+#[cfg_attr(not(feature = "server"), derive(CRUDDto))]
+#[module = "animal"]
+#[manyToMany(caretaker, via = animal_caretaker)]
+pub struct Animal {
+    #[idField] pub id: i32,
+    pub race: String,
+    pub name: String,
+    pub age: i32,
+    #[createdAt] pub created_at: DateTimeWithTimeZone,
+    #[updatedAt] pub updated_at: DateTimeWithTimeZone,
+}
\ No newline at end of file
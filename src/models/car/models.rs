@@ -0,0 +1,36 @@
+use std::cmp::{Eq, PartialEq};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use generic_crud_proc_macro::CRUDModel;
+#[cfg(not(feature = "server"))]
+use generic_crud_proc_macro::CRUDDto;
+
+use crate::entity::sea_orm_active_enums::FuelType;
+use crate::prelude::{DateTimeWithTimeZone, Decimal, Json, Uuid};
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(CRUDModel))]
+#[cfg_attr(not(feature = "server"), derive(CRUDDto))]
+#[module = "car"]
+#[naturalKey(brand, model, year)]
+pub struct Car {
+    #[idField] pub id: i32,
+    pub brand: String,
+    pub model: String,
+    pub year: i32,
+    #[default] pub fuel_type: FuelType,
+    pub purchased_at: Option<DateTimeWithTimeZone>,
+    #[belongsTo(owner)] pub owner_id: Option<i32>,
+    pub price: Option<Decimal>,
+    pub tracker_id: Option<Uuid>,
+    pub specs: Option<Json>,
+    #[createdAt] pub created_at: DateTimeWithTimeZone,
+    #[updatedAt] pub updated_at: DateTimeWithTimeZone,
+}
+
+#[cfg(feature = "server")]
+impl generic_crud_trait::ApiSchema for FuelType {
+    fn schema() -> rocket::serde::json::serde_json::Value {
+        generic_crud_trait::enum_schema::<Self>()
+    }
+}
@@ -0,0 +1,20 @@
+use std::cmp::{Eq, PartialEq};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use generic_crud_proc_macro::CRUDModel;
+#[cfg(not(feature = "server"))]
+use generic_crud_proc_macro::CRUDDto;
+
+use crate::prelude::DateTimeWithTimeZone;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(CRUDModel))]
+#[cfg_attr(not(feature = "server"), derive(CRUDDto))]
+#[module = "caretaker"]
+#[manyToMany(animal, via = animal_caretaker)]
+pub struct Caretaker {
+    #[idField] pub id: i32,
+    pub name: String,
+    #[createdAt] pub created_at: DateTimeWithTimeZone,
+    #[updatedAt] pub updated_at: DateTimeWithTimeZone,
+}
@@ -4,16 +4,25 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
-#[sea_orm(table_name = "animal")]
+#[sea_orm(table_name = "owner")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    pub race: String,
     pub name: String,
-    pub age: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::car::Entity")]
+    Car,
+}
+
+impl Related<super::car::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Car.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,34 @@
+//! The models of the API and their request bodies, shared by the server and its clients.
+//!
+//! The `server` feature adds the SeaORM entities and the CRUDModel implementations on top of them,
+//! the clients only get the `Post` and `Partial` bodies of the CRUDDto derive.
+
+#[cfg(feature = "server")]
+#[macro_use] extern crate rocket;
+
+pub mod entity;
+
+pub mod animal {
+    pub mod models;
+}
+
+pub mod car {
+    pub mod models;
+}
+
+pub mod caretaker {
+    pub mod models;
+}
+
+pub mod owner {
+    pub mod models;
+}
+
+/// The column types of the models, the ones of `sea_orm::prelude` without depending on SeaORM.
+pub mod prelude {
+    pub use rust_decimal::Decimal;
+    pub use serde_json::Value as Json;
+    pub use uuid::Uuid;
+
+    pub type DateTimeWithTimeZone = chrono::DateTime<chrono::FixedOffset>;
+}
@@ -0,0 +1,82 @@
+use rocket::{Build, Rocket, State};
+use rocket::fairing::AdHoc;
+use rocket::http::Method;
+use rocket::response::content::{RawHtml, RawJson};
+use rocket::serde::json::serde_json::{self, json};
+
+use generic_crud_trait::{schema_ref, ApiOperation, ApiRegistry};
+
+use crate::{animal, car, caretaker, owner};
+
+/// The OpenAPI document, serialized once all the routes are mounted.
+pub struct ApiDocument(pub String);
+
+#[get("/openapi.json")]
+fn openapi(document: &State<ApiDocument>) -> RawJson<String> {
+    RawJson(document.0.clone())
+}
+
+/// Interactive documentation of the OpenAPI document, with no external assets.
+#[get("/docs")]
+fn docs() -> RawHtml<&'static str> {
+    RawHtml(include_str!("docs.html"))
+}
+
+fn batch_operation() -> ApiOperation {
+    let results = json!({"type": "array", "items": schema_ref("BatchResult")});
+    let operation = ApiOperation::new(Method::Post, "/api", "/_batch", "batch", "Run create, update and delete operations in a single transaction")
+        .tag("batch")
+        .idempotent()
+        .body(json!({"type": "array", "items": schema_ref("BatchOperation")}))
+        .response(200, "The result of each operation", results.clone());
+    // A failed operation rolls back the whole batch, its status being the status of the response
+    [400, 404, 409, 422].into_iter().fold(operation, |operation, status| {
+        operation.response(status, "The results up to the first failed operation, all of them rolled back", results.clone())
+    })
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    let registry = ApiRegistry::new("rocket_api", env!("CARGO_PKG_VERSION"))
+        .resource::<car::controller::CarController>("/api/cars")
+        .resource::<animal::controller::AnimalController>("/api/animals")
+        .resource::<owner::controller::OwnerController>("/api/owners")
+        .resource::<caretaker::controller::CaretakerController>("/api/caretakers")
+        .operation(batch_operation())
+        .schema("BatchOperation", json!({
+            "type": "object",
+            "required": ["op", "resource"],
+            "description": "Any `{\"$ref\": \"<index>.<field>\"}` object of `id` or `body` is replaced by the field of the result of an earlier operation",
+            "properties": {
+                "op": {"type": "string", "enum": ["create", "update", "delete"]},
+                "resource": {"type": "string", "enum": ["cars", "animals", "owners", "caretakers"]},
+                "id": {"oneOf": [{"type": "integer"}, {"type": "object", "description": "A reference to the result of an earlier operation"}]},
+                "body": {"type": "object"},
+            },
+        }))
+        .schema("BatchResult", json!({
+            "type": "object",
+            "required": ["status"],
+            "properties": {
+                "status": {"type": "integer"},
+                "body": {"type": "object"},
+                "error": {"type": "string"},
+            },
+        }));
+
+    // The docs page is enabled by the `api_docs` key of Rocket.toml, in debug builds only by default
+    let docs_enabled = rocket.figment().extract_inner::<bool>("api_docs").unwrap_or(cfg!(debug_assertions));
+
+    // The document only describes the routes actually mounted, hence built on ignite
+    let rocket = rocket
+        .attach(AdHoc::on_ignite("OpenAPI document", |rocket| async move {
+            let document = registry.document(rocket.routes());
+            rocket.manage(ApiDocument(serde_json::to_string(&document).unwrap()))
+        }))
+        .mount("/api", routes![openapi]);
+
+    if docs_enabled {
+        rocket.mount("/api", routes![docs])
+    } else {
+        rocket
+    }
+}
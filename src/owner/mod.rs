@@ -0,0 +1,3 @@
+pub mod controller;
+pub mod service;
+pub use rocket_api_models::owner::models;
\ No newline at end of file
@@ -0,0 +1,5 @@
+use generic_crud_proc_macro::CRUDServiceImpl;
+
+#[derive(CRUDServiceImpl)]
+#[module = "owner"]
+pub struct OwnerService;
\ No newline at end of file
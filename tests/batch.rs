@@ -0,0 +1,47 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn references_are_ref_objects() {
+    let client = client().await;
+
+    let name = unique();
+    let operations = json!([
+        {"op": "create", "resource": "owners", "body": {"name": name}},
+        {"op": "create", "resource": "cars", "body": {"brand": "Batch", "model": "$0.id", "year": 2012, "owner_id": {"$ref": "0.id"}}},
+        {"op": "update", "resource": "cars", "id": {"$ref": "1.id"}, "body": {"model": unique(), "specs": {"price": "$5"}}},
+    ]);
+    let response = client.post("/api/_batch").json(&operations).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let results: Value = response.into_json().await.unwrap();
+
+    let owner = &results[0]["body"];
+    let car = &results[1]["body"];
+    assert_eq!(car["owner_id"], owner["id"]);
+    // Strings starting with a `$` are values, not references
+    assert_eq!(car["model"], "$0.id");
+    assert_eq!(results[2]["body"]["id"], car["id"]);
+    assert_eq!(results[2]["body"]["specs"], json!({"price": "$5"}));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn invalid_references_fail_the_batch() {
+    let client = client().await;
+
+    for reference in [json!("0.missing"), json!("5.id"), json!("id"), json!(0)] {
+        let operations = json!([
+            {"op": "create", "resource": "owners", "body": {"name": unique()}},
+            {"op": "delete", "resource": "owners", "id": {"$ref": reference}},
+        ]);
+        let response = client.post("/api/_batch").json(&operations).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", reference);
+        let results: Value = response.into_json().await.unwrap();
+        assert!(results[1]["error"].as_str().unwrap().starts_with("Operation 1: "));
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn missing_parent_is_unprocessable() {
+    let client = client().await;
+
+    let car = json!({"brand": "Orphan", "model": unique(), "year": 2020, "owner_id": i32::MAX});
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["field"], "owner_id");
+
+    let operations = json!([{"op": "create", "resource": "cars", "body": car}]);
+    let response = client.post("/api/_batch").json(&operations).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let results: Value = response.into_json().await.unwrap();
+    assert_eq!(results[0]["body"]["field"], "owner_id");
+
+    let response = client.post("/api/cars").json(&json!({"brand": "Orphan", "model": unique(), "year": 2020})).dispatch().await;
+    let car: Value = response.into_json().await.unwrap();
+    let uri = format!("/api/cars/{}", car["id"]);
+    let response = client.patch(uri.as_str()).json(&json!({"owner_id": i32::MAX})).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["field"], "owner_id");
+
+    // The car is left unchanged
+    let response = client.get(uri.as_str()).dispatch().await;
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["owner_id"], Value::Null);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn existing_or_null_parent_is_accepted() {
+    let client = client().await;
+
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+
+    let car = json!({"brand": "Owned", "model": unique(), "year": 2021, "owner_id": owner["id"]});
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let car: Value = response.into_json().await.unwrap();
+    assert_eq!(car["owner_id"], owner["id"]);
+
+    let response = client.patch(format!("/api/cars/{}", car["id"])).json(&json!({"owner_id": null})).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let car: Value = response.into_json().await.unwrap();
+    assert_eq!(car["owner_id"], Value::Null);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn nested_routes_only_return_the_children() {
+    let client = client().await;
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+    let cars = format!("/api/owners/{}/cars", owner["id"]);
+
+    let response = client.post(&cars).json(&json!({"brand": "Nested", "model": unique(), "year": 2021})).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let location = response.headers().get_one("Location").unwrap().to_owned();
+    let car: Value = response.into_json().await.unwrap();
+    assert_eq!(car["owner_id"], owner["id"]);
+    assert_eq!(location, format!("{}/{}", cars, car["id"]));
+
+    let response = client.post("/api/cars").json(&json!({"brand": "Nested", "model": unique(), "year": 2021})).dispatch().await;
+    let orphan: Value = response.into_json().await.unwrap();
+
+    let response = client.get(&cars).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<Value>().await.unwrap(), json!([car]));
+    let response = client.get(&location).dispatch().await;
+    assert_eq!(response.into_json::<Value>().await.unwrap(), car);
+    let response = client.get(format!("{}/{}", cars, orphan["id"])).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let missing = format!("/api/owners/{}/cars", i32::MAX);
+    assert_eq!(client.get(&missing).dispatch().await.status(), Status::NotFound);
+    let response = client.post(&missing).json(&json!({"brand": "Nested", "model": unique(), "year": 2021})).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn nested_routes_are_generated_from_belongs_to() {
+    let rocket = rocket_api::rocket();
+    let routes: Vec<(String, String)> = rocket.routes()
+        .map(|route| (route.method.to_string(), route.uri.path().to_string()))
+        .collect();
+
+    // Mounted at the base of the parent, with the segment of the children
+    for (method, path) in [
+        ("GET", "/api/owners/<owner_id>/cars"),
+        ("GET", "/api/owners/<owner_id>/cars/<obj_id>"),
+        ("POST", "/api/owners/<owner_id>/cars"),
+    ] {
+        assert!(routes.contains(&(method.to_owned(), path.to_owned())), "{} {} missing from {:?}", method, path, routes);
+    }
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::serde_json::{json, Value};
+
+use generic_crud_trait::BodyFormat;
+
+use common::{client, unique};
+
+const FORMATS: [BodyFormat; 2] = [BodyFormat::MsgPack, BodyFormat::Cbor];
+
+fn round_trip(format: BodyFormat, value: &Value) -> Value {
+    let bytes = format.serialize(value).unwrap();
+    format.decode(&bytes).unwrap()
+}
+
+/// The body of a response in `format`, checking its content type.
+async fn decoded(response: LocalResponse<'_>, format: BodyFormat) -> Value {
+    assert_eq!(response.content_type(), Some(format.content_type()));
+    let bytes = response.into_bytes().await.unwrap();
+    format.decode(&bytes).unwrap()
+}
+
+/// Create a car with a body in `format`, the response being asked in the same format.
+async fn create_car(client: &Client, format: BodyFormat, car: &Value) -> Value {
+    let response = client.post("/api/cars")
+        .header(format.content_type())
+        .header(Header::new("Accept", format.content_type().to_string()))
+        .body(format.serialize(car).unwrap())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created, "{:?}", format);
+    decoded(response, format).await
+}
+
+/// `depth` arrays nested in one another, the innermost one being empty.
+fn nested(format: BodyFormat, depth: usize) -> Vec<u8> {
+    let (array_of_one, empty_array) = match format {
+        BodyFormat::MsgPack => (0x91, 0x90),
+        _ => (0x81, 0x80),
+    };
+    let mut bytes = vec![array_of_one; depth - 1];
+    bytes.push(empty_array);
+    bytes
+}
+
+#[test]
+fn values_round_trip() {
+    let values = [
+        json!(null),
+        json!(true),
+        json!({
+            "id": 1,
+            "brand": "Škoda",
+            "price": "12.50",
+            "year": -2019,
+            "ratio": 1.5,
+            "specs": {"doors": [2, 4], "extras": {}, "tags": []},
+            "owner_id": null,
+        }),
+        json!([0, 23, 24, 255, 256, 65535, 65536, u32::MAX, u64::MAX, -1, -24, -25, -129, -32769, i64::MIN]),
+        json!([0.1, -1e300, f64::MIN_POSITIVE]),
+        json!("x".repeat(70_000)),
+        Value::Array((0..70_000).map(Value::from).collect()),
+        Value::Object((0..20).map(|key| (key.to_string(), Value::from(key))).collect()),
+    ];
+    for format in FORMATS {
+        for value in &values {
+            assert_eq!(&round_trip(format, value), value, "{:?}", format);
+        }
+    }
+}
+
+#[test]
+fn cbor_items_are_decoded() {
+    let decode = |bytes: &[u8]| BodyFormat::Cbor.decode(bytes).unwrap();
+
+    assert_eq!(decode(&[0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x82, 0x02, 0x03]), json!({"a": 1, "b": [2, 3]}));
+    // Indefinite length array and text
+    assert_eq!(decode(&[0x9f, 0x01, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xff]), json!([1, "ab"]));
+    // Byte strings are arrays of bytes, tags are ignored
+    assert_eq!(decode(&[0x42, 0x01, 0x02]), json!([1, 2]));
+    assert_eq!(decode(&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]), json!(1363896240));
+    // Half floats, NaN being null
+    assert_eq!(decode(&[0xf9, 0x3e, 0x00]), json!(1.5));
+    assert_eq!(decode(&[0xf9, 0x7e, 0x00]), json!(null));
+}
+
+#[test]
+fn msgpack_values_are_decoded() {
+    let decode = |bytes: &[u8]| BodyFormat::MsgPack.decode(bytes).unwrap();
+
+    assert_eq!(decode(&[0x82, 0xa1, b'a', 0x01, 0xa1, b'b', 0x92, 0x02, 0xff]), json!({"a": 1, "b": [2, -1]}));
+    // Binary values are arrays of bytes
+    assert_eq!(decode(&[0xc4, 0x02, 0x01, 0x02]), json!([1, 2]));
+    assert_eq!(decode(&[0xca, 0x3f, 0xc0, 0x00, 0x00]), json!(1.5));
+    assert_eq!(decode(&[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]), json!(u64::MAX));
+}
+
+#[test]
+fn truncated_input_is_rejected() {
+    let value = json!({"brand": "Truncated", "year": 2020, "tags": ["a", "b"]});
+    for format in FORMATS {
+        let bytes = format.serialize(&value).unwrap();
+        for len in 0..bytes.len() {
+            let err = format.decode(&bytes[..len]).unwrap_err();
+            assert_eq!(err, "Unexpected end of input", "{:?} truncated to {} bytes", format, len);
+        }
+    }
+}
+
+#[test]
+fn trailing_bytes_are_rejected() {
+    for format in FORMATS {
+        let mut bytes = format.serialize(&json!([1, 2])).unwrap();
+        let len = bytes.len();
+        bytes.push(0x00);
+        assert_eq!(format.decode(&bytes).unwrap_err(), format!("Trailing bytes at offset {}", len));
+    }
+}
+
+#[test]
+fn deep_nesting_is_rejected() {
+    for format in FORMATS {
+        assert!(format.decode(&nested(format, 128)).is_ok(), "{:?}", format);
+        assert_eq!(format.decode(&nested(format, 129)).unwrap_err(), "Too deeply nested", "{:?}", format);
+        // Deep enough to overflow the stack without a limit
+        assert_eq!(format.decode(&nested(format, 1_000_000)).unwrap_err(), "Too deeply nested", "{:?}", format);
+    }
+}
+
+#[test]
+fn non_string_keys_are_rejected() {
+    // {1: 2}
+    let err = BodyFormat::Cbor.decode(&[0xa1, 0x01, 0x02]).unwrap_err();
+    assert!(err.starts_with("Expected a text key"), "{}", err);
+    let err = BodyFormat::MsgPack.decode(&[0x81, 0x01, 0x02]).unwrap_err();
+    assert!(err.starts_with("Expected a string key"), "{}", err);
+
+    // {[]: 2}, {null: 2}
+    assert!(BodyFormat::Cbor.decode(&[0xa1, 0x80, 0x02]).is_err());
+    assert!(BodyFormat::MsgPack.decode(&[0x81, 0xc0, 0x02]).is_err());
+}
+
+#[test]
+fn invalid_items_are_rejected() {
+    // Invalid UTF-8 strings
+    assert!(BodyFormat::Cbor.decode(&[0x62, 0xff, 0xfe]).is_err());
+    assert!(BodyFormat::MsgPack.decode(&[0xa2, 0xff, 0xfe]).is_err());
+    // Reserved additional information, a break outside of an indefinite length item
+    assert!(BodyFormat::Cbor.decode(&[0x1c]).is_err());
+    assert!(BodyFormat::Cbor.decode(&[0xff]).is_err());
+    // Extension types
+    assert_eq!(BodyFormat::MsgPack.decode(&[0xd4, 0x01, 0x00]).unwrap_err(), "Unsupported extension type 1");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn cars_are_created_and_read_in_each_format() {
+    let client = client().await;
+    for format in FORMATS {
+        let car = json!({"brand": "Format", "model": unique(), "year": 2020, "specs": {"doors": 4, "tags": ["a"]}});
+        let created = create_car(&client, format, &car).await;
+        for field in ["brand", "model", "year", "specs"] {
+            assert_eq!(created[field], car[field], "{:?} {}", format, field);
+        }
+
+        let response = client.get(format!("/api/cars/{}", created["id"]))
+            .header(Header::new("Accept", format.content_type().to_string()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(decoded(response, format).await, created, "{:?}", format);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn accept_header_is_negotiated() {
+    let client = client().await;
+    let car = create_car(&client, BodyFormat::Json, &json!({"brand": "Format", "model": unique(), "year": 2020})).await;
+    let uri = format!("/api/cars/{}", car["id"]);
+
+    for (accept, format) in [
+        ("application/cbor;q=0.5, application/msgpack", BodyFormat::MsgPack),
+        ("application/x-msgpack;q=0.2, application/cbor;q=0.9", BodyFormat::Cbor),
+        // A supported format takes precedence over a wildcard of the same weight
+        ("*/*, application/cbor", BodyFormat::Cbor),
+        ("text/html, application/xhtml+xml, */*;q=0.8", BodyFormat::Json),
+        ("application/*", BodyFormat::Json),
+    ] {
+        let response = client.get(&uri).header(Header::new("Accept", accept)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok, "{}", accept);
+        assert_eq!(response.headers().get_one("Vary"), Some("Accept"));
+        assert_eq!(decoded(response, format).await, car, "{}", accept);
+    }
+
+    let response = client.get(&uri).dispatch().await;
+    assert_eq!(decoded(response, BodyFormat::Json).await, car);
+
+    for accept in ["application/xml", "text/html, application/msgpack;q=0"] {
+        let response = client.get(&uri).header(Header::new("Accept", accept)).dispatch().await;
+        assert_eq!(response.status(), Status::NotAcceptable, "{}", accept);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn unsupported_content_type_is_rejected() {
+    let client = client().await;
+    for content_type in [ContentType::XML, ContentType::Plain, ContentType::Form] {
+        let response = client.post("/api/cars")
+            .header(content_type.clone())
+            .body(r#"{"brand": "Format", "model": "Unsupported", "year": 2020}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnsupportedMediaType, "{}", content_type);
+    }
+
+    // A malformed body is a bad request, a body of the wrong shape an unprocessable entity
+    for format in FORMATS {
+        let body = format.serialize(&json!({"brand": "Format", "year": "recent"})).unwrap();
+        let truncated = body[..body.len() - 1].to_vec();
+        let response = client.post("/api/cars").header(format.content_type()).body(truncated).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{:?}", format);
+        let response = client.post("/api/cars").header(format.content_type()).body(body).dispatch().await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{:?}", format);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn idempotent_replay_is_negotiated() {
+    let client = client().await;
+    let car = json!({"brand": "Format", "model": unique(), "year": 2020});
+    let key = format!("body-format-{}", unique());
+    let post = |format: BodyFormat| {
+        client.post("/api/cars")
+            .header(Header::new("Idempotency-Key", key.clone()))
+            .header(format.content_type())
+            .header(Header::new("Accept", format.content_type().to_string()))
+            .body(format.serialize(&car).unwrap())
+    };
+
+    let response = post(BodyFormat::MsgPack).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let location = response.headers().get_one("Location").map(str::to_owned);
+    let created = decoded(response, BodyFormat::MsgPack).await;
+
+    // The same request in another format is replayed in the format it accepts
+    let response = post(BodyFormat::Cbor).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.headers().get_one("Location").map(str::to_owned), location);
+    assert_eq!(decoded(response, BodyFormat::Cbor).await, created);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+use sea_orm::prelude::Decimal;
+
+use common::{client, unique};
+
+fn decimal(value: &Value) -> Decimal {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn create_read_patch_round_trip() {
+    let client = client().await;
+
+    let body = json!({
+        "brand": "Columns",
+        "model": unique(),
+        "year": 2019,
+        "fuel_type": "hybrid",
+        "purchased_at": "2023-01-31T11:00:00Z",
+        "price": "12345.67",
+        "tracker_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "specs": {"doors": 5, "colors": ["red", "blue"], "towbar": false},
+    });
+    let response = client.post("/api/cars").json(&body).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let created: Value = response.into_json().await.unwrap();
+    for (key, value) in body.as_object().unwrap() {
+        if key != "price" {
+            assert_eq!(&created[key], value, "{}", key);
+        }
+    }
+    // The decimals are read back with the scale of the Postgres numeric digits, e.g. "12345.6700"
+    assert_eq!(decimal(&created["price"]), decimal(&body["price"]));
+
+    let uri = format!("/api/cars/{}", created["id"]);
+    let read: Value = client.get(&uri).dispatch().await.into_json().await.unwrap();
+    assert_eq!(read, created);
+
+    let patch = json!({
+        "fuel_type": "electric",
+        "price": "9999.5",
+        "tracker_id": "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8",
+        "specs": {"doors": 3},
+    });
+    let response = client.patch(&uri).json(&patch).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let patched: Value = response.into_json().await.unwrap();
+    assert_eq!(patched["fuel_type"], "electric");
+    assert_eq!(decimal(&patched["price"]), decimal(&patch["price"]));
+    assert_eq!(patched["tracker_id"], patch["tracker_id"]);
+    assert_eq!(patched["specs"], patch["specs"]);
+    assert_eq!(patched["purchased_at"], created["purchased_at"]);
+
+    // `null` clears the nullable columns, the missing keys being left untouched
+    let patch = json!({"purchased_at": null, "price": null, "tracker_id": null, "specs": null});
+    let patched: Value = client.patch(&uri).json(&patch).dispatch().await.into_json().await.unwrap();
+    for key in ["purchased_at", "price", "tracker_id", "specs"] {
+        assert_eq!(patched[key], Value::Null, "{}", key);
+    }
+    assert_eq!(patched["fuel_type"], "electric");
+
+    let read: Value = client.get(&uri).dispatch().await.into_json().await.unwrap();
+    assert_eq!(read, patched);
+    assert_eq!(client.delete(&uri).dispatch().await.status(), Status::NoContent);
+}
@@ -0,0 +1,33 @@
+// Each test binary uses its own subset of the helpers
+#![allow(dead_code)]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rocket::local::asynchronous::Client;
+use rocket::tokio::sync::Mutex;
+use sea_orm::DatabaseConnection;
+
+/// A local client of the API, connected to the database of `DATABASE_URL`.
+///
+/// The tests using it are `#[ignore]`d, and run with `cargo test -- --include-ignored`.
+pub async fn client() -> Client {
+    // The migrations run when the rocket ignites, one at a time
+    static IGNITE: Mutex<()> = Mutex::const_new(());
+
+    if std::env::var("DATABASE_URL").is_err() {
+        panic!("DATABASE_URL must be set to run the API tests");
+    }
+    let _ignite = IGNITE.lock().await;
+    Client::tracked(rocket_api::rocket()).await.unwrap()
+}
+
+/// A connection to the database of `DATABASE_URL`, for the tests calling the services directly.
+pub async fn database() -> DatabaseConnection {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set to run the database tests");
+    sea_orm::Database::connect(url).await.unwrap()
+}
+
+/// Suffix keeping the natural keys of the objects created by concurrent runs apart.
+pub fn unique() -> String {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos().to_string()
+}
@@ -0,0 +1,123 @@
+//! The `#[default]` and `#[default = expr]` fields, `#[default = expr]` being checked on a model declared here
+//! as the API models only leave their defaults to the columns.
+
+#[macro_use] extern crate rocket;
+
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{self, json, Value};
+use rocket::tokio::sync::OnceCell;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+
+use generic_crud_trait::{ApiSchema, CRUDServiceTrait};
+
+use common::{client, database, unique};
+
+mod entity {
+    pub mod ticket {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "default_ticket")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub title: String,
+            pub priority: i32,
+            pub status: String,
+            pub queue: String,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+mod ticket {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "ticket"]
+        pub struct Ticket {
+            #[idField] pub id: i32,
+            pub title: String,
+            #[default = 3] pub priority: i32,
+            #[default = "open"] pub status: String,
+            #[default] pub queue: String,
+        }
+    }
+
+    pub mod service {
+        use generic_crud_proc_macro::CRUDServiceImpl;
+
+        #[derive(CRUDServiceImpl)]
+        #[module = "ticket"]
+        pub struct TicketService;
+    }
+}
+
+use ticket::models::PostTicket;
+use ticket::service::TicketService;
+
+async fn setup() -> DatabaseConnection {
+    static TABLE_CREATED: OnceCell<()> = OnceCell::const_new();
+
+    let db = database().await;
+    TABLE_CREATED.get_or_init(|| async {
+        let table = "CREATE TABLE IF NOT EXISTS default_ticket (
+            id serial PRIMARY KEY, title text NOT NULL, priority integer NOT NULL, status text NOT NULL, queue text NOT NULL DEFAULT 'support'
+        )";
+        db.execute(Statement::from_string(DbBackend::Postgres, table.to_owned())).await.unwrap();
+    }).await;
+    db
+}
+
+#[test]
+fn defaults_are_optional_in_the_schema() {
+    let post: PostTicket = serde_json::from_value(json!({"title": "Printer"})).unwrap();
+    assert_eq!((post.priority, post.status, post.queue), (None, None, None));
+
+    let schema = PostTicket::schema();
+    assert_eq!(schema["required"], json!(["title"]));
+    assert_eq!(schema["properties"]["priority"]["default"], json!(3));
+    assert_eq!(schema["properties"]["status"]["default"], json!("open"));
+    // The column defaults are not known to the model
+    assert!(schema["properties"]["queue"].get("default").is_none());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn omitted_fields_are_filled_in() {
+    let db = setup().await;
+
+    let post = PostTicket { title: unique(), priority: None, status: None, queue: None };
+    let ticket = TicketService::create(post, &db).await.unwrap();
+    assert_eq!((ticket.priority, ticket.status.as_str(), ticket.queue.as_str()), (3, "open", "support"));
+
+    let post = PostTicket { title: unique(), priority: Some(1), status: Some("closed".to_owned()), queue: Some("billing".to_owned()) };
+    let ticket = TicketService::create(post, &db).await.unwrap();
+    assert_eq!((ticket.priority, ticket.status.as_str(), ticket.queue.as_str()), (1, "closed", "billing"));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn omitted_fuel_type_is_the_column_default() {
+    let client = client().await;
+
+    let response = client.post("/api/cars").json(&json!({"brand": "Defaults", "model": unique(), "year": 2020})).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let car: Value = response.into_json().await.unwrap();
+    assert_eq!(car["fuel_type"], json!("petrol"));
+
+    let response = client.post("/api/cars")
+        .json(&json!({"brand": "Defaults", "model": unique(), "year": 2020, "fuel_type": "diesel"}))
+        .dispatch().await;
+    let car: Value = response.into_json().await.unwrap();
+    assert_eq!(car["fuel_type"], json!("diesel"));
+}
@@ -0,0 +1,453 @@
+//! The `onDelete` policies, on models declared here as the API models only use `restrict`.
+//!
+//! The macros resolve the entities and the models from the crate root, like in the API crate.
+
+#[macro_use] extern crate rocket;
+
+mod common;
+
+use rocket::tokio::sync::OnceCell;
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, Statement};
+
+use generic_crud_trait::{CRUDServiceTrait, DeleteError, WriteError};
+
+use common::{client, database, unique};
+
+mod entity {
+    pub mod fleet {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "policy_fleet")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub name: String,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(has_many = "super::vessel::Entity")]
+            Vessel,
+            #[sea_orm(has_many = "super::berth::Entity")]
+            Berth,
+        }
+
+        impl Related<super::vessel::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Vessel.def()
+            }
+        }
+
+        impl Related<super::berth::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Berth.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    pub mod vessel {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "policy_vessel")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub fleet_id: i32,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(belongs_to = "super::fleet::Entity", from = "Column::FleetId", to = "super::fleet::Column::Id")]
+            Fleet,
+            #[sea_orm(has_many = "super::crew::Entity")]
+            Crew,
+        }
+
+        impl Related<super::fleet::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Fleet.def()
+            }
+        }
+
+        impl Related<super::crew::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Crew.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    pub mod crew {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "policy_crew")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub vessel_id: i32,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(belongs_to = "super::vessel::Entity", from = "Column::VesselId", to = "super::vessel::Column::Id")]
+            Vessel,
+        }
+
+        impl Related<super::vessel::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Vessel.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    pub mod berth {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "policy_berth")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub fleet_id: Option<i32>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(belongs_to = "super::fleet::Entity", from = "Column::FleetId", to = "super::fleet::Column::Id")]
+            Fleet,
+        }
+
+        impl Related<super::fleet::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Fleet.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    pub mod shelf {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "policy_shelf")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(has_many = "super::book::Entity")]
+            Book,
+        }
+
+        impl Related<super::book::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Book.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    pub mod book {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "policy_book")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub shelf_id: i32,
+            pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(belongs_to = "super::shelf::Entity", from = "Column::ShelfId", to = "super::shelf::Column::Id")]
+            Shelf,
+        }
+
+        impl Related<super::shelf::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Shelf.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+mod fleet {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "fleet"]
+        #[hasMany(vessel, onDelete = cascade)]
+        #[hasMany(berth, onDelete = setNull)]
+        pub struct Fleet {
+            #[idField] pub id: i32,
+            pub name: String,
+        }
+    }
+
+    pub mod service {
+        use generic_crud_proc_macro::CRUDServiceImpl;
+
+        #[derive(CRUDServiceImpl)]
+        #[module = "fleet"]
+        pub struct FleetService;
+    }
+}
+
+mod vessel {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "vessel"]
+        #[hasMany(crew, onDelete = restrict)]
+        pub struct Vessel {
+            #[idField] pub id: i32,
+            #[belongsTo(fleet)] pub fleet_id: i32,
+        }
+    }
+}
+
+mod crew {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "crew"]
+        pub struct Crew {
+            #[idField] pub id: i32,
+            #[belongsTo(vessel)] pub vessel_id: i32,
+        }
+    }
+}
+
+mod berth {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "berth"]
+        pub struct Berth {
+            #[idField] pub id: i32,
+            #[belongsTo(fleet)] pub fleet_id: Option<i32>,
+        }
+    }
+}
+
+mod shelf {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use sea_orm::prelude::DateTimeWithTimeZone;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "shelf"]
+        #[hasMany(book, onDelete = softCascade)]
+        pub struct Shelf {
+            #[idField] pub id: i32,
+            #[deletedAt] pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+    }
+
+    pub mod service {
+        use generic_crud_proc_macro::CRUDServiceImpl;
+
+        #[derive(CRUDServiceImpl)]
+        #[module = "shelf"]
+        pub struct ShelfService;
+    }
+}
+
+mod book {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use sea_orm::prelude::DateTimeWithTimeZone;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "book"]
+        pub struct Book {
+            // The id field is not named `id`
+            #[idField] #[column = "id"] pub book_id: i32,
+            #[belongsTo(shelf)] pub shelf_id: i32,
+            #[deletedAt] pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+    }
+}
+
+use fleet::models::{Fleet, PostFleet, PartialFleet};
+use fleet::service::FleetService;
+use shelf::models::{PartialShelf, PostShelf, Shelf};
+use shelf::service::ShelfService;
+
+/// The tables of the models, and a table referencing the fleets without a policy.
+const TABLES: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS policy_fleet (id serial PRIMARY KEY, name text NOT NULL)",
+    "CREATE TABLE IF NOT EXISTS policy_vessel (id serial PRIMARY KEY, fleet_id integer NOT NULL REFERENCES policy_fleet (id))",
+    "CREATE TABLE IF NOT EXISTS policy_crew (id serial PRIMARY KEY, vessel_id integer NOT NULL REFERENCES policy_vessel (id))",
+    "CREATE TABLE IF NOT EXISTS policy_berth (id serial PRIMARY KEY, fleet_id integer REFERENCES policy_fleet (id))",
+    "CREATE TABLE IF NOT EXISTS policy_mooring (id serial PRIMARY KEY, fleet_id integer NOT NULL REFERENCES policy_fleet (id))",
+    "CREATE TABLE IF NOT EXISTS policy_shelf (id serial PRIMARY KEY, deleted_at timestamptz)",
+    "CREATE TABLE IF NOT EXISTS policy_book (id serial PRIMARY KEY, shelf_id integer NOT NULL REFERENCES policy_shelf (id), deleted_at timestamptz)",
+];
+
+async fn setup() -> DatabaseConnection {
+    static TABLES_CREATED: OnceCell<()> = OnceCell::const_new();
+
+    let db = database().await;
+    TABLES_CREATED.get_or_init(|| async {
+        for table in TABLES {
+            db.execute(Statement::from_string(DbBackend::Postgres, table.to_string())).await.unwrap();
+        }
+    }).await;
+    db
+}
+
+async fn fleet(db: &DatabaseConnection) -> i32 {
+    let fleet = <FleetService as CRUDServiceTrait<Fleet, PostFleet, PartialFleet>>::create(PostFleet { name: unique() }, db).await.unwrap();
+    fleet.id
+}
+
+async fn vessel(fleet_id: i32, db: &DatabaseConnection) -> i32 {
+    let vessel = entity::vessel::ActiveModel { fleet_id: ActiveValue::set(fleet_id), ..Default::default() };
+    vessel.insert(db).await.unwrap().id
+}
+
+async fn delete_fleet(fleet_id: i32, db: &DatabaseConnection) -> Result<Option<()>, DeleteError> {
+    <FleetService as CRUDServiceTrait<Fleet, PostFleet, PartialFleet>>::delete(fleet_id, db).await
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn cascade_deletes_the_children() {
+    let db = setup().await;
+
+    let fleet_id = fleet(&db).await;
+    let vessels = [vessel(fleet_id, &db).await, vessel(fleet_id, &db).await];
+
+    assert_eq!(delete_fleet(fleet_id, &db).await, Ok(Some(())));
+    for vessel_id in vessels {
+        assert_eq!(entity::vessel::Entity::find_by_id(vessel_id).one(&db).await.unwrap(), None);
+    }
+    assert_eq!(entity::fleet::Entity::find_by_id(fleet_id).one(&db).await.unwrap(), None);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn cascade_is_blocked_by_a_restricted_grandchild() {
+    let db = setup().await;
+
+    let fleet_id = fleet(&db).await;
+    let vessel_id = vessel(fleet_id, &db).await;
+    let crew = entity::crew::ActiveModel { vessel_id: ActiveValue::set(vessel_id), ..Default::default() };
+    let crew_id = crew.insert(&db).await.unwrap().id;
+
+    match delete_fleet(fleet_id, &db).await {
+        Err(DeleteError::Blocked(blocking)) => assert_eq!(blocking.0["crew"], vec![crew_id]),
+        other => panic!("Expected the crew to block the delete, got {:?}", other),
+    }
+    // Nothing was deleted
+    assert!(entity::vessel::Entity::find_by_id(vessel_id).one(&db).await.unwrap().is_some());
+    assert!(entity::fleet::Entity::find_by_id(fleet_id).one(&db).await.unwrap().is_some());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn set_null_detaches_the_children() {
+    let db = setup().await;
+
+    let fleet_id = fleet(&db).await;
+    let berth = entity::berth::ActiveModel { fleet_id: ActiveValue::set(Some(fleet_id)), ..Default::default() };
+    let berth_id = berth.insert(&db).await.unwrap().id;
+
+    assert_eq!(delete_fleet(fleet_id, &db).await, Ok(Some(())));
+    let berth = entity::berth::Entity::find_by_id(berth_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(berth.fleet_id, None);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn soft_cascade_soft_deletes_the_children() {
+    let db = setup().await;
+
+    let shelf = <ShelfService as CRUDServiceTrait<Shelf, PostShelf, PartialShelf>>::create(PostShelf {}, &db).await.unwrap();
+    let book = entity::book::ActiveModel { shelf_id: ActiveValue::set(shelf.id), ..Default::default() };
+    let book_id = book.insert(&db).await.unwrap().id;
+
+    let deleted = <ShelfService as CRUDServiceTrait<Shelf, PostShelf, PartialShelf>>::delete(shelf.id, &db).await;
+    assert_eq!(deleted, Ok(Some(())));
+    let book = entity::book::Entity::find_by_id(book_id).one(&db).await.unwrap().unwrap();
+    assert!(book.deleted_at.is_some());
+    let shelf = entity::shelf::Entity::find_by_id(shelf.id).one(&db).await.unwrap().unwrap();
+    assert!(shelf.deleted_at.is_some());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn foreign_key_without_policy_is_an_error() {
+    let db = setup().await;
+
+    let fleet_id = fleet(&db).await;
+    let insert = format!("INSERT INTO policy_mooring (fleet_id) VALUES ({})", fleet_id);
+    db.execute(Statement::from_string(DbBackend::Postgres, insert)).await.unwrap();
+
+    match delete_fleet(fleet_id, &db).await {
+        Err(DeleteError::Write(WriteError::Invalid(invalid))) => {
+            assert_eq!(invalid.constraint.as_deref(), Some("policy_mooring_fleet_id_fkey"));
+        }
+        other => panic!("Expected a foreign key violation, got {:?}", other),
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn restrict_lists_the_blocking_children() {
+    use rocket::http::Status;
+    use rocket::serde::json::serde_json::{json, Value};
+
+    let client = client().await;
+
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+    let response = client.post(format!("/api/owners/{}/cars", owner["id"]))
+        .json(&json!({"brand": "Restricted", "model": unique(), "year": 2022}))
+        .dispatch().await;
+    let car: Value = response.into_json().await.unwrap();
+
+    let uri = format!("/api/owners/{}", owner["id"]);
+    let response = client.delete(uri.as_str()).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body, json!({"car": [car["id"]]}));
+
+    client.delete(format!("/api/cars/{}", car["id"])).dispatch().await;
+    let response = client.delete(uri.as_str()).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn duplicate_natural_key_is_a_conflict() {
+    let client = client().await;
+
+    let car = json!({"brand": "Duplicate", "model": unique(), "year": 2015});
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["constraint"], "idx_car_natural_key");
+
+    // The same key under an owner
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+    let uri = format!("/api/owners/{}/cars", owner["id"]);
+    let response = client.post(uri).json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["constraint"], "idx_car_natural_key");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn duplicate_natural_key_fails_the_batch() {
+    let client = client().await;
+
+    let name = unique();
+    let car = json!({"brand": "Duplicate", "model": unique(), "year": 2016});
+    let operations = json!([
+        {"op": "create", "resource": "owners", "body": {"name": name}},
+        {"op": "create", "resource": "cars", "body": car},
+        {"op": "create", "resource": "cars", "body": car},
+    ]);
+    let response = client.post("/api/_batch").json(&operations).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let results: Value = response.into_json().await.unwrap();
+    assert_eq!(results[2]["body"]["constraint"], "idx_car_natural_key");
+
+    // The whole batch was rolled back
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn update_to_a_duplicate_natural_key_is_a_conflict() {
+    let client = client().await;
+
+    let model = unique();
+    let car = json!({"brand": "Duplicate", "model": model, "year": 2017});
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let response = client.post("/api/cars").json(&json!({"brand": "Duplicate", "model": model, "year": 2018})).dispatch().await;
+    let other: Value = response.into_json().await.unwrap();
+
+    let response = client.patch(format!("/api/cars/{}", other["id"])).json(&json!({"year": 2017})).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["constraint"], "idx_car_natural_key");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn missing_foreign_key_is_unprocessable() {
+    let client = client().await;
+
+    // The upsert checks the parent before the insert, naming the field
+    let uri = format!("/api/cars/by-key?brand=Orphan&model={}&year=2019", unique());
+    let response = client.put(uri).json(&json!({"owner_id": i32::MAX})).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.into_string().await.unwrap(), "The referenced object does not exist (owner_id)");
+}
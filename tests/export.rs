@@ -0,0 +1,30 @@
+mod common;
+
+use std::fs;
+
+use arrow_ipc::reader::FileReader;
+
+use rocket_api::export::cli;
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn export_replaces_the_file_once_complete() {
+    let dir = std::env::temp_dir().join(format!("export-{}", common::unique()));
+    fs::create_dir(&dir).unwrap();
+    let path = dir.join("cars.arrow").to_str().unwrap().to_owned();
+
+    // A failed export leaves the previous file untouched
+    fs::write(&path, b"previous").unwrap();
+    let err = cli::run(&["drivers".to_owned(), path.clone()]).await.unwrap_err();
+    assert!(err.starts_with("Usage"), "{}", err);
+    assert_eq!(fs::read(&path).unwrap(), b"previous");
+
+    cli::run(&["cars".to_owned(), path.clone()]).await.unwrap();
+    let reader = FileReader::try_new(fs::File::open(&path).unwrap(), None).unwrap();
+    assert!(reader.schema().column_with_name("brand").is_some());
+
+    // No temporary file is left behind
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(files, vec!["cars.arrow"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
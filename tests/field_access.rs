@@ -0,0 +1,131 @@
+//! The `#[readOnly]`, `#[writeOnly]` and `#[immutable]` fields, on a model declared here as the API models have none.
+//!
+//! The exhaustive patterns on the generated structs fail to compile when they have other fields.
+
+#[macro_use] extern crate rocket;
+
+mod common;
+
+use std::io::Cursor;
+
+use arrow_ipc::reader::FileReader;
+use rocket::futures::stream::{self, StreamExt};
+use rocket::serde::json::serde_json::{self, json};
+use rocket::tokio::sync::OnceCell;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, Statement};
+
+use generic_crud_trait::{arrow_file, csv_header, csv_record, CRUDServiceTrait};
+
+use common::{database, unique};
+
+mod entity {
+    pub mod account {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "access_account")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub login: String,
+            pub name: String,
+            pub secret: String,
+            pub score: i32,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+mod account {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "account"]
+        pub struct Account {
+            #[idField] pub id: i32,
+            #[immutable] pub login: String,
+            pub name: String,
+            #[writeOnly] #[serde(skip_serializing)] pub secret: String,
+            #[readOnly] pub score: i32,
+        }
+    }
+
+    pub mod service {
+        use generic_crud_proc_macro::CRUDServiceImpl;
+
+        #[derive(CRUDServiceImpl)]
+        #[module = "account"]
+        pub struct AccountService;
+    }
+}
+
+use account::models::{Account, PartialAccount, PostAccount};
+use account::service::AccountService;
+
+async fn setup() -> DatabaseConnection {
+    static TABLE_CREATED: OnceCell<()> = OnceCell::const_new();
+
+    let db = database().await;
+    TABLE_CREATED.get_or_init(|| async {
+        let table = "CREATE TABLE IF NOT EXISTS access_account (
+            id serial PRIMARY KEY, login text NOT NULL, name text NOT NULL, secret text NOT NULL, score integer NOT NULL DEFAULT 0
+        )";
+        db.execute(Statement::from_string(DbBackend::Postgres, table.to_owned())).await.unwrap();
+    }).await;
+    db
+}
+
+#[test]
+fn generated_structs_leave_the_fields_out() {
+    // The read only fields are ignored in the create body
+    let post: PostAccount = serde_json::from_value(json!({"login": "ada", "name": "Ada", "secret": "s3cr3t", "score": 99})).unwrap();
+    let PostAccount { login, name, secret } = post;
+    assert_eq!((login.as_str(), name.as_str(), secret.as_str()), ("ada", "Ada", "s3cr3t"));
+
+    // The immutable fields can't be updated
+    let partial: PartialAccount = serde_json::from_value(json!({"login": "grace", "secret": "n3w"})).unwrap();
+    let PartialAccount { name, secret } = partial;
+    assert_eq!((name, secret), (None, Some("n3w".to_owned())));
+
+    // The write only fields are never returned
+    let account = Account { id: 1, login: "ada".to_owned(), name: "Ada".to_owned(), secret: "s3cr3t".to_owned(), score: 3 };
+    assert_eq!(serde_json::to_value(account).unwrap(), json!({"id": 1, "login": "ada", "name": "Ada", "score": 3}));
+}
+
+#[rocket::async_test]
+async fn write_only_fields_are_not_exported() {
+    let account = Account { id: 1, login: "ada".to_owned(), name: "Ada".to_owned(), secret: "s3cr3t".to_owned(), score: 3 };
+    let file: Vec<u8> = arrow_file(Box::pin(stream::iter([Ok(account.clone())]))).map(Result::unwrap).concat().await;
+    let reader = FileReader::try_new(Cursor::new(file), None).unwrap();
+    let names: Vec<String> = reader.schema().fields().iter().map(|field| field.name().clone()).collect();
+    assert_eq!(names, ["id", "login", "name", "score"]);
+
+    let csv = String::from_utf8([csv_header::<Account>(), csv_record(&account)].concat()).unwrap();
+    assert_eq!(csv, "id,login,name,score\r\n1,ada,Ada,3\r\n");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn write_only_fields_are_stored() {
+    let db = setup().await;
+    let login = unique();
+
+    let post = PostAccount { login: login.clone(), name: "Ada".to_owned(), secret: "s3cr3t".to_owned() };
+    let account = AccountService::create(post, &db).await.unwrap();
+    assert_eq!((account.login.as_str(), account.score), (login.as_str(), 0));
+
+    let partial = PartialAccount { name: Some("Grace".to_owned()), secret: Some("n3w".to_owned()) };
+    let updated = AccountService::update(account.id, partial, &db).await.unwrap().unwrap();
+    assert_eq!((updated.login.as_str(), updated.name.as_str()), (login.as_str(), "Grace"));
+
+    let row = entity::account::Entity::find_by_id(account.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(row.secret, "n3w");
+    assert!(serde_json::to_value(updated).unwrap().get("secret").is_none());
+}
@@ -0,0 +1,155 @@
+//! The `#[column]`, `#[from_entity]`, `#[to_entity]` and `#[computed]` fields, on a model declared here
+//! as the API models mirror their entities.
+
+#[macro_use] extern crate rocket;
+
+mod common;
+
+use std::io::Cursor;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int32Type;
+use arrow_ipc::reader::FileReader;
+use arrow_schema::DataType;
+use rocket::futures::stream::{self, StreamExt};
+use rocket::serde::json::serde_json::{self, json};
+use rocket::tokio::sync::OnceCell;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, Statement};
+
+use generic_crud_trait::{arrow_file, CRUDServiceTrait, FromEntity};
+
+use common::{database, unique};
+
+mod entity {
+    pub mod timer {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "mapping_timer")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub label: String,
+            pub manufacture_year: i32,
+            pub duration_secs: i32,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+mod timer {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        use crate::entity;
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "timer"]
+        pub struct Timer {
+            #[idField] pub id: i32,
+            pub label: String,
+            #[column = "manufacture_year"] pub year: i32,
+            #[column = "duration_secs"]
+            #[from_entity(with = to_minutes)]
+            #[to_entity(with = to_secs)]
+            pub duration_minutes: i32,
+            #[computed(title)] pub title: String,
+        }
+
+        fn to_minutes(secs: i32) -> i32 {
+            secs / 60
+        }
+
+        fn to_secs(minutes: i32) -> i32 {
+            minutes * 60
+        }
+
+        fn title(obj: &entity::timer::Model) -> String {
+            format!("{} ({})", obj.label, obj.manufacture_year)
+        }
+    }
+
+    pub mod service {
+        use generic_crud_proc_macro::CRUDServiceImpl;
+
+        #[derive(CRUDServiceImpl)]
+        #[module = "timer"]
+        pub struct TimerService;
+    }
+}
+
+use timer::models::{PartialTimer, PostTimer, Timer};
+use timer::service::TimerService;
+
+async fn setup() -> DatabaseConnection {
+    static TABLE_CREATED: OnceCell<()> = OnceCell::const_new();
+
+    let db = database().await;
+    TABLE_CREATED.get_or_init(|| async {
+        let table = "CREATE TABLE IF NOT EXISTS mapping_timer (
+            id serial PRIMARY KEY, label text NOT NULL, manufacture_year integer NOT NULL, duration_secs integer NOT NULL
+        )";
+        db.execute(Statement::from_string(DbBackend::Postgres, table.to_owned())).await.unwrap();
+    }).await;
+    db
+}
+
+#[test]
+fn model_is_mapped_from_the_entity() {
+    let entity = entity::timer::Model { id: 1, label: "Egg".to_owned(), manufacture_year: 2019, duration_secs: 180 };
+    let timer = Timer::from_entity(entity);
+    assert_eq!(
+        serde_json::to_value(timer).unwrap(),
+        json!({"id": 1, "label": "Egg", "year": 2019, "duration_minutes": 3, "title": "Egg (2019)"}),
+    );
+
+    // The computed fields are read only
+    let PartialTimer { label, year, duration_minutes } = serde_json::from_value(json!({"title": "Ignored"})).unwrap();
+    assert_eq!((label, year, duration_minutes), (None, None, None));
+}
+
+#[rocket::async_test]
+async fn arrow_export_has_the_model_fields() {
+    let timer = Timer::from_entity(entity::timer::Model { id: 1, label: "Egg".to_owned(), manufacture_year: 2019, duration_secs: 180 });
+    let file: Vec<u8> = arrow_file(Box::pin(stream::iter([Ok(timer)]))).map(Result::unwrap).concat().await;
+    let reader = FileReader::try_new(Cursor::new(file), None).unwrap();
+    let fields: Vec<(String, DataType)> = reader.schema().fields().iter()
+        .map(|field| (field.name().clone(), field.data_type().clone()))
+        .collect();
+    assert_eq!(fields, [
+        ("id".to_owned(), DataType::Int32),
+        ("label".to_owned(), DataType::Utf8),
+        ("year".to_owned(), DataType::Int32),
+        ("duration_minutes".to_owned(), DataType::Int32),
+        ("title".to_owned(), DataType::Utf8),
+    ]);
+
+    let batch = reader.into_iter().next().unwrap().unwrap();
+    assert_eq!(batch.column_by_name("duration_minutes").unwrap().as_primitive::<Int32Type>().value(0), 3);
+    assert_eq!(batch.column_by_name("title").unwrap().as_string::<i32>().value(0), "Egg (2019)");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn model_is_mapped_to_the_columns() {
+    let db = setup().await;
+    let label = unique();
+
+    let post = PostTimer { label: label.clone(), year: 2019, duration_minutes: 3 };
+    let timer = TimerService::create(post, &db).await.unwrap();
+    assert_eq!((timer.year, timer.duration_minutes), (2019, 3));
+    assert_eq!(timer.title, format!("{} (2019)", label));
+
+    let partial = PartialTimer { label: None, year: Some(2021), duration_minutes: Some(5) };
+    let updated = TimerService::update(timer.id, partial, &db).await.unwrap().unwrap();
+    assert_eq!(updated.title, format!("{} (2021)", label));
+
+    let row = entity::timer::Entity::find_by_id(timer.id).one(&db).await.unwrap().unwrap();
+    assert_eq!((row.manufacture_year, row.duration_secs), (2021, 300));
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+async fn create_car(client: &Client, body: Value) -> Value {
+    let response = client.post("/api/cars").json(&body).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    response.into_json().await.unwrap()
+}
+
+async fn car_ids(client: &Client, query: &str) -> Vec<i64> {
+    let response = client.get(format!("/api/cars?{}", query)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok, "{}", query);
+    let cars: Vec<Value> = response.into_json().await.unwrap();
+    cars.iter().map(|car| car["id"].as_i64().unwrap()).collect()
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn lists_are_filtered_by_field() {
+    let client = client().await;
+    let model = unique();
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+
+    let old = create_car(&client, json!({"brand": "Filter", "model": model, "year": 2010, "owner_id": owner["id"]})).await;
+    let new = create_car(&client, json!({"brand": "Filter", "model": model, "year": 2020, "fuel_type": "electric"})).await;
+    let (old, new) = (old["id"].as_i64().unwrap(), new["id"].as_i64().unwrap());
+
+    // The values are parsed as the field types, the encoded and the plain brackets being the same
+    let by_model = format!("filter%5Bmodel%5D={}", model);
+    assert_eq!(car_ids(&client, &by_model).await, vec![old, new]);
+    assert_eq!(car_ids(&client, &format!("filter[model]={}&filter[year]=2020", model)).await, vec![new]);
+    assert_eq!(car_ids(&client, &format!("{}&filter[fuel_type]=electric", by_model)).await, vec![new]);
+    assert_eq!(car_ids(&client, &format!("{}&filter[owner_id]={}", by_model, owner["id"])).await, vec![old]);
+    assert_eq!(car_ids(&client, &format!("{}&filter[owner_id]=null", by_model)).await, vec![new]);
+    assert_eq!(car_ids(&client, &format!("{}&limit=1&offset=1", by_model)).await, vec![new]);
+    assert_eq!(car_ids(&client, &format!("{}&filter[year]=1990", by_model)).await, Vec::<i64>::new());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn invalid_filters_are_rejected() {
+    let client = client().await;
+
+    for query in ["filter[color]=red", "filter[year]=recent", "filter[fuel_type]=steam", "filter[id]=1", "filter[brand]=Filter&ids=1"] {
+        let response = client.get(format!("/api/cars?{}", query)).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", query);
+    }
+}
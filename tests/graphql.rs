@@ -0,0 +1,188 @@
+//! The `POST /api/graphql` endpoint of the `graphql` feature, run with `cargo test --features graphql`.
+
+#![cfg(feature = "graphql")]
+
+mod common;
+
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::serde_json::{json, Value};
+
+use generic_crud_trait::GraphQLRegistry;
+use rocket_api::{car, owner};
+
+use common::{client, unique};
+
+async fn graphql(client: &Client, query: &str, variables: Value) -> Value {
+    let response = client.post("/api/graphql").json(&json!({"query": query, "variables": variables})).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    response.into_json().await.unwrap()
+}
+
+async fn create_car(client: &Client, input: Value) -> Value {
+    let query = "mutation($input: CarCreateInput!) { createCar(input: $input) { id brand model year fuel_type owner_id } }";
+    let response = graphql(client, query, json!({"input": input})).await;
+    assert!(response.get("errors").is_none(), "{}", response);
+    response["data"]["createCar"].clone()
+}
+
+#[test]
+fn schema_is_built_from_the_models() {
+    let schema = GraphQLRegistry::new()
+        .resource::<car::service::CarService, _, _, _, _>("cars")
+        .resource::<owner::service::OwnerService, _, _, _, _>("owners")
+        .finish()
+        .unwrap()
+        .sdl();
+    // The descriptions of the arguments are laid out on several lines
+    let schema: String = schema.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    for definition in [
+        "ids: [Int!], \"\"\" Keep the objects equal to these values, null keeping the objects without a value \"\"\" filter: CarFilter, limit: Int, offset: Int ): [Car!]!",
+        "input CarFilter {",
+        "car(id: Int!): Car",
+        "createCar(input: CarCreateInput!): Car!",
+        "updateCar(id: Int!, input: CarUpdateInput!): Car",
+        "deleteCar(id: Int!): Boolean!",
+        "owner: Owner",
+        "enum CarFuelType",
+    ] {
+        assert!(schema.contains(definition), "{} missing from\n{}", definition, schema);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn queries_return_the_selected_fields() {
+    let client = client().await;
+    let first = create_car(&client, json!({"brand": "GraphQL", "model": unique(), "year": 2018})).await;
+    let second = create_car(&client, json!({"brand": "GraphQL", "model": unique(), "year": 2019, "fuel_type": "hybrid"})).await;
+
+    let query = "query($ids: [Int!]) { cars(ids: $ids) { id year fuel_type } }";
+    let response = graphql(&client, query, json!({"ids": [second["id"], first["id"]]})).await;
+    assert_eq!(response["data"]["cars"], json!([
+        {"id": second["id"], "year": 2019, "fuel_type": "hybrid"},
+        {"id": first["id"], "year": 2018, "fuel_type": "petrol"},
+    ]));
+
+    let response = graphql(&client, "query($id: Int!) { car(id: $id) { brand model } }", json!({"id": first["id"]})).await;
+    assert_eq!(response["data"]["car"], json!({"brand": "GraphQL", "model": first["model"]}));
+    let response = graphql(&client, "{ car(id: 2147483647) { id } }", json!({})).await;
+    assert_eq!(response["data"]["car"], Value::Null);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn list_is_filtered_and_paginated() {
+    let client = client().await;
+    let brand = unique();
+    let first = create_car(&client, json!({"brand": brand, "model": unique(), "year": 2018})).await;
+    let second = create_car(&client, json!({"brand": brand, "model": unique(), "year": 2019, "fuel_type": "hybrid"})).await;
+    create_car(&client, json!({"brand": unique(), "model": unique(), "year": 2019, "fuel_type": "hybrid"})).await;
+
+    let query = "query($filter: CarFilter, $offset: Int) { cars(filter: $filter, offset: $offset) { id } }";
+    let response = graphql(&client, query, json!({"filter": {"brand": brand}})).await;
+    assert_eq!(response["data"]["cars"], json!([{"id": first["id"]}, {"id": second["id"]}]));
+    let response = graphql(&client, query, json!({"filter": {"brand": brand, "fuel_type": "hybrid"}})).await;
+    assert_eq!(response["data"]["cars"], json!([{"id": second["id"]}]));
+    let response = graphql(&client, query, json!({"filter": {"brand": brand}, "offset": 1})).await;
+    assert_eq!(response["data"]["cars"], json!([{"id": second["id"]}]));
+    let response = graphql(&client, query, json!({"filter": {"brand": brand, "owner_id": null}})).await;
+    assert_eq!(response["data"]["cars"], json!([{"id": first["id"]}, {"id": second["id"]}]));
+
+    // The ids are returned in their order, which can't be paginated or filtered
+    for arguments in ["limit: 1", "offset: 1", "filter: {year: 2019}"] {
+        let query = format!("query($ids: [Int!]) {{ cars(ids: $ids, {}) {{ id }} }}", arguments);
+        let response = graphql(&client, &query, json!({"ids": [second["id"], first["id"]]})).await;
+        assert!(response["errors"][0]["message"].as_str().unwrap().contains("can't be combined with `ids`"), "{}", response);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn mutations_create_update_and_delete() {
+    let client = client().await;
+    let car = create_car(&client, json!({"brand": "GraphQL", "model": unique(), "year": 2018})).await;
+    assert_eq!((&car["year"], &car["fuel_type"]), (&json!(2018), &json!("petrol")));
+
+    let query = "mutation($id: Int!, $input: CarUpdateInput!) { updateCar(id: $id, input: $input) { year fuel_type model } }";
+    let response = graphql(&client, query, json!({"id": car["id"], "input": {"year": 2020, "fuel_type": "electric"}})).await;
+    assert_eq!(response["data"]["updateCar"], json!({"year": 2020, "fuel_type": "electric", "model": car["model"]}));
+
+    let query = "mutation($id: Int!) { deleteCar(id: $id) }";
+    assert_eq!(graphql(&client, query, json!({"id": car["id"]})).await["data"]["deleteCar"], true);
+    assert_eq!(graphql(&client, query, json!({"id": car["id"]})).await["data"]["deleteCar"], false);
+    let response = graphql(&client, "query($id: Int!) { car(id: $id) { id } }", json!({"id": car["id"]})).await;
+    assert_eq!(response["data"]["car"], Value::Null);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn selected_relations_are_included() {
+    let client = client().await;
+    let query = "mutation($input: OwnerCreateInput!) { createOwner(input: $input) { id name } }";
+    let owner = graphql(&client, query, json!({"input": {"name": unique()}})).await["data"]["createOwner"].clone();
+    let car = create_car(&client, json!({"brand": "GraphQL", "model": unique(), "year": 2018, "owner_id": owner["id"]})).await;
+    let orphan = create_car(&client, json!({"brand": "GraphQL", "model": unique(), "year": 2018})).await;
+
+    let query = "query($ids: [Int!]) { cars(ids: $ids) { id owner { id name } } }";
+    let response = graphql(&client, query, json!({"ids": [car["id"], orphan["id"]]})).await;
+    assert_eq!(response["data"]["cars"], json!([
+        {"id": car["id"], "owner": owner},
+        {"id": orphan["id"], "owner": null},
+    ]));
+
+    // The owner with cars can't be deleted
+    let response = graphql(&client, "mutation($id: Int!) { deleteOwner(id: $id) }", json!({"id": owner["id"]})).await;
+    assert_eq!(response["errors"][0]["message"], "The object has children preventing its deletion");
+    assert_eq!(response["errors"][0]["extensions"]["children"]["car"], json!([car["id"]]));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn invalid_requests_are_errors() {
+    let client = client().await;
+
+    // Rejected by the schema before running
+    for query in [
+        "{ cars { id color } }",
+        "mutation { createCar(input: {brand: \"GraphQL\", year: 2018}) { id } }",
+        "mutation { createCar(input: {brand: \"GraphQL\", model: \"Validation\", year: \"recent\"}) { id } }",
+        "mutation { createCar(input: {brand: \"GraphQL\", model: \"Validation\", year: 2018, fuel_type: steam}) { id } }",
+    ] {
+        let response = graphql(&client, query, json!({})).await;
+        assert!(response["errors"][0]["message"].is_string(), "{}", query);
+        assert_eq!(response["data"], Value::Null, "{}", query);
+    }
+
+    // Rejected by the database, with the status the REST route would answer
+    let input = json!({"brand": "GraphQL", "model": unique(), "year": 2018});
+    create_car(&client, input.clone()).await;
+    let query = "mutation($input: CarCreateInput!) { createCar(input: $input) { id } }";
+    let response = graphql(&client, query, json!({"input": input})).await;
+    assert_eq!(response["errors"][0]["extensions"]["status"], 409);
+    assert!(response["errors"][0]["extensions"]["constraint"].is_string());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn errors_roll_the_request_back() {
+    let client = client().await;
+    let (model, duplicate) = (unique(), unique());
+    create_car(&client, json!({"brand": "GraphQL", "model": duplicate, "year": 2018})).await;
+
+    let query = "mutation($created: CarCreateInput!, $duplicate: CarCreateInput!) {
+        created: createCar(input: $created) { id }
+        duplicate: createCar(input: $duplicate) { id }
+    }";
+    let variables = json!({
+        "created": {"brand": "GraphQL", "model": model, "year": 2018},
+        "duplicate": {"brand": "GraphQL", "model": duplicate, "year": 2018},
+    });
+    let response = graphql(&client, query, variables).await;
+    assert_eq!(response["errors"].as_array().unwrap().len(), 1);
+
+    let response = client.get(format!("/api/cars?filter[model]={}", model)).dispatch().await;
+    let cars: Vec<Value> = response.into_json().await.unwrap();
+    assert!(cars.is_empty());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::time::Duration;
+
+use rocket::futures::join;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::serde_json::{json, Value};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+use sea_orm_rocket::Database;
+
+use db::Db;
+use generic_crud_trait::IdempotencyKey;
+
+use common::{client, unique};
+
+async fn post_animal(client: &Client, key: &str, name: &str) -> (Status, bool, Option<Value>) {
+    let response = client.post("/api/animals")
+        .header(Header::new("Idempotency-Key", key.to_owned()))
+        .json(&json!({"race": "Dog", "name": name, "age": 3}))
+        .dispatch()
+        .await;
+    let replayed = response.headers().get_one("Idempotent-Replayed") == Some("true");
+    (response.status(), replayed, response.into_json().await)
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn concurrent_retries_create_one_object() {
+    let client = client().await;
+    let key = unique();
+    let name = unique();
+
+    // The second request waits for the first one to commit, and replays its response
+    let (first, second) = join!(post_animal(&client, &key, &name), post_animal(&client, &key, &name));
+    assert_eq!((first.0, second.0), (Status::Created, Status::Created));
+    assert!(first.1 != second.1, "Exactly one response is replayed");
+    assert_eq!(first.2, second.2);
+
+    let (status, replayed, _body) = post_animal(&client, &key, &unique()).await;
+    assert_eq!((status, replayed), (Status::UnprocessableEntity, false));
+
+    // Purged keys can be used again
+    let db = &Db::fetch(client.rocket()).unwrap().conn;
+    assert!(IdempotencyKey::purge(Duration::ZERO, db).await.unwrap() >= 1);
+    let (status, replayed, body) = post_animal(&client, &key, &name).await;
+    assert_eq!((status, replayed), (Status::Created, false));
+    assert_ne!(body.unwrap()["id"], first.2.unwrap()["id"]);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn expired_keys_are_not_replayed() {
+    let client = client().await;
+    let key = unique();
+    let name = unique();
+
+    let (status, _replayed, first) = post_animal(&client, &key, &name).await;
+    assert_eq!(status, Status::Created);
+
+    // The key expires before the purge deletes it
+    let db = &Db::fetch(client.rocket()).unwrap().conn;
+    let expire = format!("UPDATE idempotency_key SET created_at = now() - interval '25 hours' WHERE key = '{}'", key);
+    db.execute(Statement::from_string(DbBackend::Postgres, expire)).await.unwrap();
+
+    let (status, replayed, body) = post_animal(&client, &key, &name).await;
+    assert_eq!((status, replayed), (Status::Created, false));
+    assert_ne!(body.as_ref().unwrap()["id"], first.unwrap()["id"]);
+
+    // The key is claimed again, and replayed from then on
+    let (status, replayed, replay) = post_animal(&client, &key, &name).await;
+    assert_eq!((status, replayed), (Status::Created, true));
+    assert_eq!(replay, body);
+}
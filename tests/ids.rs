@@ -0,0 +1,43 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn ids_are_returned_in_the_requested_order() {
+    let client = client().await;
+
+    let mut ids = vec![];
+    for year in [2001, 2002] {
+        let response = client.post("/api/cars").json(&json!({"brand": "Ids", "model": unique(), "year": year})).dispatch().await;
+        let car: Value = response.into_json().await.unwrap();
+        ids.push(car["id"].as_i64().unwrap());
+    }
+    let missing = i32::MAX as i64;
+
+    let uri = format!("/api/cars?ids={},{},{},{}", ids[1], missing, ids[0], ids[1]);
+    let body: Value = client.get(uri).dispatch().await.into_json().await.unwrap();
+    let data: Vec<i64> = body["data"].as_array().unwrap().iter().map(|car| car["id"].as_i64().unwrap()).collect();
+    assert_eq!(data, vec![ids[1], ids[0]]);
+    assert_eq!(body["missing"], json!([missing]));
+
+    let response = client.post("/api/cars/_get").json(&json!({"ids": [ids[0], missing]})).dispatch().await;
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["data"][0]["id"], ids[0]);
+    assert_eq!(body["missing"], json!([missing]));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn ids_cannot_be_paginated() {
+    let client = client().await;
+
+    for uri in ["/api/cars?ids=1,2&limit=1", "/api/cars?ids=1,2&offset=1", "/api/cars/export.ndjson?ids=1,2&limit=1"] {
+        let response = client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", uri);
+        assert_eq!(response.into_string().await.unwrap(), "`limit` and `offset` can't be combined with `ids`");
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn import_rows_setting_different_columns() {
+    let client = client().await;
+
+    // The blank fuel types are left to the database default, the other rows setting the column
+    let model = unique();
+    let csv = format!(
+        "brand,model,year,fuel_type,purchased_at,owner_id,price,tracker_id,specs\r\n\
+         Import,{model},2001,,,,,,\r\n\
+         Import,{model},2002,diesel,,,,,\r\n\
+         Import,{model},2003,,,,12.5,,\r\n",
+    );
+    let response = client.post("/api/cars/import")
+        .header(ContentType::CSV)
+        .body(csv)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let report: Value = response.into_json().await.unwrap();
+    assert_eq!(report["inserted"], 3);
+    assert_eq!(report["errors"], Value::Array(vec![]));
+
+    let cars: Vec<Value> = client.get("/api/cars").dispatch().await.into_json().await.unwrap();
+    let fuel_types: Vec<&Value> = cars.iter()
+        .filter(|car| car["model"] == model.as_str())
+        .map(|car| &car["fuel_type"])
+        .collect();
+    assert_eq!(fuel_types, ["petrol", "diesel", "petrol"]);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn exported_csv_imports_back() {
+    let client = client().await;
+
+    // Fields with a separator, quotes and line breaks, and a JSON column
+    let car = json!({
+        "brand": "Round, \"trip\"",
+        "model": format!("{}\r\nsecond line", unique()),
+        "year": 2010,
+        "fuel_type": "electric",
+        "price": "9999.99",
+        "tracker_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "specs": {"doors": 4, "extras": ["gps", "a, \"b\""]},
+    });
+    let response = client.post("/api/cars").json(&car).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let created: Value = response.into_json().await.unwrap();
+
+    let uri = format!("/api/cars/export.csv?ids={}", created["id"]);
+    let csv = client.get(uri).dispatch().await.into_string().await.unwrap();
+    let response = client.delete(format!("/api/cars/{}", created["id"])).dispatch().await;
+    assert!(response.status().class().is_success());
+
+    let response = client.post("/api/cars/import")
+        .header(ContentType::CSV)
+        .body(csv)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let report: Value = response.into_json().await.unwrap();
+    assert_eq!(report["errors"], Value::Array(vec![]));
+    assert_eq!(report["inserted"], 1);
+
+    let cars: Vec<Value> = client.get("/api/cars").dispatch().await.into_json().await.unwrap();
+    let imported = cars.iter().find(|imported| imported["model"] == car["model"]).unwrap();
+    for field in ["brand", "year", "fuel_type", "price", "tracker_id", "specs"] {
+        assert_eq!(imported[field], created[field], "{}", field);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn csv_rows_with_missing_fields_are_reported() {
+    let client = client().await;
+
+    let csv = format!("brand,model,year\r\n\r\nImport,{},2004\r\nImport,\"a\r\nb\",2005,extra\r\nImport\r\n", unique());
+    let response = client.post("/api/cars/import")
+        .header(ContentType::CSV)
+        .body(csv)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let report: Value = response.into_json().await.unwrap();
+    assert_eq!(report["errors"], json!([
+        {"line": 4, "error": "Expected 3 fields, found 4"},
+        {"line": 6, "error": "Expected 3 fields, found 1"},
+    ]));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn missing_owner_is_reported_with_its_field() {
+    let client = client().await;
+
+    let model = unique();
+    let csv = format!(
+        "brand,model,year,owner_id\r\n\
+         Import,{model},2001,\r\n\
+         Import,{model},2002,2147483647\r\n",
+    );
+    let response = client.post("/api/cars/import")
+        .header(ContentType::CSV)
+        .body(csv)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let report: Value = response.into_json().await.unwrap();
+    assert_eq!(report["inserted"], 0);
+    assert_eq!(report["errors"], json!([
+        {"line": 3, "error": "The referenced object does not exist", "field": "owner_id"},
+    ]));
+}
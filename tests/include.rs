@@ -0,0 +1,203 @@
+//! The `?include=` relations, the soft deleted parents being checked on models declared here
+//! as the API models are not soft deletable.
+
+#[macro_use] extern crate rocket;
+
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+use rocket::tokio::sync::OnceCell;
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+
+use generic_crud_trait::{CRUDServiceTrait, FromEntity, LoadRelations};
+
+use common::{client, database, unique};
+
+mod entity {
+    pub mod publisher {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "include_publisher")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub name: String,
+            pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(has_many = "super::novel::Entity")]
+            Novel,
+        }
+
+        impl Related<super::novel::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Novel.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    pub mod novel {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "include_novel")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub title: String,
+            pub publisher_id: Option<i32>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(belongs_to = "super::publisher::Entity", from = "Column::PublisherId", to = "super::publisher::Column::Id")]
+            Publisher,
+        }
+
+        impl Related<super::publisher::Entity> for Entity {
+            fn to() -> RelationDef {
+                Relation::Publisher.def()
+            }
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+mod publisher {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use sea_orm::prelude::DateTimeWithTimeZone;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "publisher"]
+        pub struct Publisher {
+            #[idField] pub id: i32,
+            pub name: String,
+            #[deletedAt] pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+    }
+
+    pub mod service {
+        use generic_crud_proc_macro::CRUDServiceImpl;
+
+        #[derive(CRUDServiceImpl)]
+        #[module = "publisher"]
+        pub struct PublisherService;
+    }
+}
+
+mod novel {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "novel"]
+        pub struct Novel {
+            #[idField] pub id: i32,
+            pub title: String,
+            #[belongsTo(publisher)] pub publisher_id: Option<i32>,
+        }
+    }
+}
+
+use novel::models::Novel;
+use publisher::models::{PartialPublisher, PostPublisher, Publisher};
+use publisher::service::PublisherService;
+
+const TABLES: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS include_publisher (id serial PRIMARY KEY, name text NOT NULL, deleted_at timestamptz)",
+    "CREATE TABLE IF NOT EXISTS include_novel (id serial PRIMARY KEY, title text NOT NULL, publisher_id integer REFERENCES include_publisher (id))",
+];
+
+async fn setup() -> DatabaseConnection {
+    static TABLES_CREATED: OnceCell<()> = OnceCell::const_new();
+
+    let db = database().await;
+    TABLES_CREATED.get_or_init(|| async {
+        for table in TABLES {
+            db.execute(Statement::from_string(DbBackend::Postgres, table.to_string())).await.unwrap();
+        }
+    }).await;
+    db
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn belongs_to_relation_is_embedded() {
+    let client = client().await;
+
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+    let response = client.post("/api/cars")
+        .json(&json!({"brand": "Included", "model": unique(), "year": 2022, "owner_id": owner["id"]}))
+        .dispatch().await;
+    let owned: Value = response.into_json().await.unwrap();
+    let response = client.post("/api/cars").json(&json!({"brand": "Included", "model": unique(), "year": 2022})).dispatch().await;
+    let unowned: Value = response.into_json().await.unwrap();
+
+    let response = client.get(format!("/api/cars/{}?include=owner", owned["id"])).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let car: Value = response.into_json().await.unwrap();
+    assert_eq!(car["owner"]["name"], owner["name"]);
+
+    let response = client.get(format!("/api/cars?ids={},{}&include=owner", owned["id"], unowned["id"])).dispatch().await;
+    let cars: Value = response.into_json().await.unwrap();
+    assert_eq!(cars["data"][0]["owner"]["id"], owner["id"]);
+    assert_eq!(cars["data"][1]["owner"], Value::Null);
+
+    // Without ?include= the relation is left out
+    let response = client.get(format!("/api/cars/{}", owned["id"])).dispatch().await;
+    let car: Value = response.into_json().await.unwrap();
+    assert!(car.get("owner").is_none());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn only_belongs_to_relations_can_be_included() {
+    let client = client().await;
+
+    // hasMany, manyToMany and unknown relations
+    for uri in ["/api/owners?include=car", "/api/owners?include=cars", "/api/animals?include=caretakers", "/api/cars?include=driver"] {
+        let response = client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", uri);
+    }
+
+    let response = client.get("/api/cars?include=owner.car").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.into_string().await.unwrap(), "Unknown relation `car`");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn soft_deleted_parent_is_not_included() {
+    let db = setup().await;
+
+    let publisher = <PublisherService as CRUDServiceTrait<Publisher, PostPublisher, PartialPublisher>>::create(PostPublisher { name: unique() }, &db)
+        .await
+        .unwrap();
+    let novel = entity::novel::ActiveModel {
+        title: ActiveValue::set(unique()),
+        publisher_id: ActiveValue::set(Some(publisher.id)),
+        ..Default::default()
+    };
+    let novel = Novel::from_entity(novel.insert(&db).await.unwrap());
+
+    let included = Novel::load_relations(vec![novel.clone()], &["publisher".to_owned()], &db).await.unwrap();
+    assert_eq!(included[0].relations["publisher"]["id"], publisher.id);
+
+    let deleted = <PublisherService as CRUDServiceTrait<Publisher, PostPublisher, PartialPublisher>>::delete(publisher.id, &db).await;
+    assert_eq!(deleted, Ok(Some(())));
+    let included = Novel::load_relations(vec![novel], &["publisher".to_owned()], &db).await.unwrap();
+    assert_eq!(included[0].relations["publisher"], Value::Null);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+async fn create(client: &Client, uri: &str, body: Value) -> i64 {
+    let response = client.post(uri).json(&body).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    response.into_json::<Value>().await.unwrap()["id"].as_i64().unwrap()
+}
+
+async fn linked_ids(client: &Client, uri: String) -> Vec<i64> {
+    let response = client.get(uri).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let objs: Vec<Value> = response.into_json().await.unwrap();
+    objs.iter().map(|obj| obj["id"].as_i64().unwrap()).collect()
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn links_are_added_replaced_and_removed() {
+    let client = client().await;
+    let animal = create(&client, "/api/animals", json!({"race": "Cat", "name": unique(), "age": 2})).await;
+    let first = create(&client, "/api/caretakers", json!({"name": unique()})).await;
+    let second = create(&client, "/api/caretakers", json!({"name": unique()})).await;
+    let caretakers = format!("/api/animals/{}/caretakers", animal);
+
+    // Linking twice is a no-op, the link being seen from both sides
+    for _ in 0..2 {
+        let response = client.post(format!("{}/{}", caretakers, first)).dispatch().await;
+        assert_eq!(response.status(), Status::NoContent);
+    }
+    assert_eq!(linked_ids(&client, caretakers.clone()).await, vec![first]);
+    assert_eq!(linked_ids(&client, format!("/api/caretakers/{}/animals", first)).await, vec![animal]);
+
+    let response = client.put(&caretakers).json(&json!([second, second])).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let replaced: Vec<Value> = response.into_json().await.unwrap();
+    assert_eq!(replaced.len(), 1);
+    assert_eq!(replaced[0]["id"], second);
+
+    let response = client.delete(format!("{}/{}", caretakers, second)).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(linked_ids(&client, caretakers).await, Vec::<i64>::new());
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn replacing_with_a_missing_object_keeps_the_links() {
+    let client = client().await;
+    let animal = create(&client, "/api/animals", json!({"race": "Dog", "name": unique(), "age": 4})).await;
+    let caretaker = create(&client, "/api/caretakers", json!({"name": unique()})).await;
+    let caretakers = format!("/api/animals/{}/caretakers", animal);
+    let missing = i32::MAX as i64;
+
+    client.post(format!("{}/{}", caretakers, caretaker)).dispatch().await;
+    let response = client.put(&caretakers).json(&json!([missing, caretaker, missing - 1])).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["error"], format!("Unknown related ids: {}, {}", missing - 1, missing));
+    assert_eq!(linked_ids(&client, caretakers.clone()).await, vec![caretaker]);
+
+    let response = client.put(format!("/api/animals/{}/caretakers", missing)).json(&json!([caretaker])).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    for uri in [format!("/api/animals/{}/caretakers/{}", missing, caretaker), format!("{}/{}", caretakers, missing)] {
+        let response = client.post(&uri).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound, "{}", uri);
+    }
+    let response = client.get(format!("/api/animals/{}/caretakers", missing)).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn link_routes_are_generated_from_the_relations() {
+    let rocket = rocket_api::rocket();
+    let routes: Vec<(String, String)> = rocket.routes()
+        .map(|route| (route.method.to_string(), route.uri.path().to_string()))
+        .collect();
+
+    // Mounted at the base of each side, with the segment of the other one
+    for (method, path) in [
+        ("GET", "/api/animals/<obj_id>/caretakers"),
+        ("PUT", "/api/animals/<obj_id>/caretakers"),
+        ("POST", "/api/animals/<obj_id>/caretakers/<caretaker_id>"),
+        ("DELETE", "/api/animals/<obj_id>/caretakers/<caretaker_id>"),
+        ("GET", "/api/caretakers/<obj_id>/animals"),
+        ("DELETE", "/api/caretakers/<obj_id>/animals/<animal_id>"),
+    ] {
+        assert!(routes.contains(&(method.to_owned(), path.to_owned())), "{} {} missing from {:?}", method, path, routes);
+    }
+}
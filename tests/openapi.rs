@@ -0,0 +1,40 @@
+mod common;
+
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::client;
+
+/// The parameter of `operation` named `name`, if any.
+fn parameter<'a>(operation: &'a Value, name: &str) -> Option<&'a Value> {
+    operation["parameters"].as_array()?.iter().find(|parameter| parameter["name"] == name)
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn include_lists_the_belongs_to_relations() {
+    let client = client().await;
+    let document: Value = client.get("/api/openapi.json").dispatch().await.into_json().await.unwrap();
+    let paths = &document["paths"];
+
+    for (path, method) in [("/api/cars", "get"), ("/api/cars/_get", "post"), ("/api/cars/{obj_id}", "get")] {
+        let include = parameter(&paths[path][method], "include").unwrap_or_else(|| panic!("{} {}", method, path));
+        assert_eq!(include["schema"]["example"], json!("owner"));
+    }
+
+    // The owners have no parent to embed
+    for (path, method) in [("/api/owners", "get"), ("/api/owners/_get", "post"), ("/api/owners/{obj_id}", "get")] {
+        assert!(paths[path][method].is_object(), "{} {}", method, path);
+        assert!(parameter(&paths[path][method], "include").is_none(), "{} {}", method, path);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn list_filter_is_a_deep_object() {
+    let client = client().await;
+    let document: Value = client.get("/api/openapi.json").dispatch().await.into_json().await.unwrap();
+
+    let filter = parameter(&document["paths"]["/api/cars"]["get"], "filter").unwrap();
+    assert_eq!((&filter["style"], &filter["explode"]), (&json!("deepObject"), &json!(true)));
+    assert_eq!(filter["schema"], json!({"$ref": "#/components/schemas/PartialCar"}));
+}
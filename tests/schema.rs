@@ -0,0 +1,104 @@
+//! The `_schema` routes, the write only fields and the string bounds being checked on a model declared here.
+
+#[macro_use] extern crate rocket;
+
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+
+use generic_crud_trait::ResourceSchemas;
+
+use common::client;
+
+mod entity {
+    pub mod member {
+        use sea_orm::entity::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+        #[sea_orm(table_name = "schema_member")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            #[sea_orm(column_type = "String(Some(16))")]
+            pub nickname: String,
+            #[sea_orm(column_type = "String(Some(64))")]
+            pub password: String,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+mod member {
+    pub mod models {
+        use generic_crud_proc_macro::CRUDModel;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, CRUDModel)]
+        #[module = "member"]
+        pub struct Member {
+            #[idField] pub id: i32,
+            pub nickname: String,
+            #[writeOnly] #[serde(skip_serializing)] pub password: String,
+        }
+    }
+}
+
+use member::models::{Member, PartialMember, PostMember};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn every_resource_has_its_schemas() {
+    let client = client().await;
+
+    for (resource, name) in [("cars", "Car"), ("animals", "Animal"), ("owners", "Owner"), ("caretakers", "Caretaker")] {
+        let response = client.get(format!("/api/{}/_schema", resource)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok, "{}", resource);
+        let schemas: Value = response.into_json().await.unwrap();
+        for (key, title) in [("model", name.to_owned()), ("post", format!("Post{}", name)), ("partial", format!("Partial{}", name))] {
+            assert_eq!(schemas[key]["title"], json!(title));
+            assert_eq!(schemas[key]["$schema"], "https://json-schema.org/draft/2020-12/schema");
+        }
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn car_schemas_describe_the_request_bodies() {
+    let client = client().await;
+    let schemas: Value = client.get("/api/cars/_schema").dispatch().await.into_json().await.unwrap();
+    let (model, post, partial) = (&schemas["model"], &schemas["post"], &schemas["partial"]);
+
+    // The server-side fields are read only, and left out of the request bodies
+    for field in ["id", "created_at", "updated_at"] {
+        assert_eq!(model["properties"][field]["readOnly"], true, "{}", field);
+        assert!(post["properties"].get(field).is_none(), "{}", field);
+        assert!(partial["properties"].get(field).is_none(), "{}", field);
+    }
+
+    // The #[default] fields can be omitted, and nothing is required to update
+    assert_eq!(post["required"], json!(["brand", "model", "year"]));
+    assert!(partial.get("required").is_none());
+
+    assert_eq!(post["properties"]["fuel_type"]["enum"], json!(["diesel", "electric", "hybrid", "petrol"]));
+    assert_eq!(post["properties"]["year"]["maximum"], i32::MAX);
+    assert_eq!(post["properties"]["owner_id"]["type"], json!(["integer", "null"]));
+}
+
+#[test]
+fn write_only_fields_and_string_bounds() {
+    let schemas = ResourceSchemas::of::<Member, PostMember, PartialMember>();
+
+    // The write only fields are never returned
+    assert!(schemas.model["properties"].get("password").is_none());
+    assert_eq!(schemas.post["properties"]["password"]["writeOnly"], true);
+    assert_eq!(schemas.partial["properties"]["password"]["writeOnly"], true);
+
+    assert_eq!(schemas.post["properties"]["nickname"]["maxLength"], 16);
+    assert_eq!(schemas.post["properties"]["password"]["maxLength"], 64);
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use std::io::Cursor;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Int32Type, TimestampMicrosecondType};
+use arrow_ipc::reader::FileReader;
+use rocket::futures::stream::{self, BoxStream, StreamExt};
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{self, json, Value};
+use sea_orm::prelude::{DateTimeWithTimeZone, Decimal, Uuid};
+use sea_orm::DbErr;
+
+use generic_crud_trait::{arrow_file, csv_interrupted, ndjson_interrupted, stream_body, FromEntity, ARROW_BATCH_SIZE};
+use rocket_api::car::models::Car;
+use rocket_api::entity::car;
+use rocket_api::entity::sea_orm_active_enums::FuelType;
+use rocket_api::owner::models::Owner;
+
+use common::client;
+
+fn chunks(chunks: Vec<Result<&'static str, DbErr>>) -> BoxStream<'static, Result<Vec<u8>, DbErr>> {
+    Box::pin(stream::iter(chunks.into_iter().map(|chunk| chunk.map(|chunk| chunk.as_bytes().to_vec()))))
+}
+
+async fn body(body: BoxStream<'_, Vec<u8>>) -> Vec<u8> {
+    body.concat().await
+}
+
+#[rocket::async_test]
+async fn complete_stream_ends_with_end() {
+    let json = stream_body(chunks(vec![Ok("["), Ok("1"), Ok(",2")]), b"]".to_vec(), Vec::new());
+    assert_eq!(body(json).await, b"[1,2]");
+}
+
+#[rocket::async_test]
+async fn interrupted_stream_ends_with_the_marker() {
+    let error = || Err(DbErr::Custom("connection reset".to_owned()));
+
+    let json = stream_body(chunks(vec![Ok("["), Ok("1"), error(), Ok(",2")]), b"]".to_vec(), Vec::new());
+    assert_eq!(body(json).await, b"[1");
+
+    let ndjson = stream_body(chunks(vec![Ok("1\n"), error()]), Vec::new(), ndjson_interrupted());
+    let ndjson = String::from_utf8(body(ndjson).await).unwrap();
+    assert_eq!(ndjson, "1\n{\"error\":\"The stream was interrupted by a database error\"}\n");
+
+    let csv = stream_body(chunks(vec![Ok("id,name\r\n"), error()]), Vec::new(), csv_interrupted());
+    let csv = String::from_utf8(body(csv).await).unwrap();
+    assert_eq!(csv, "id,name\r\nerror: The stream was interrupted by a database error\r\n");
+}
+
+#[rocket::async_test]
+async fn interrupted_arrow_file_has_no_footer() {
+    let objs: Vec<Result<Owner, DbErr>> = vec![Err(DbErr::Custom("connection reset".to_owned()))];
+    let chunks: Vec<Result<Vec<u8>, DbErr>> = arrow_file(Box::pin(stream::iter(objs))).collect().await;
+
+    // The header, then the error instead of the record batches and the footer
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].as_ref().unwrap().starts_with(b"ARROW1"));
+    assert!(chunks[1].is_err());
+}
+
+#[rocket::async_test]
+async fn arrow_file_reads_back() {
+    let time: DateTimeWithTimeZone = "2023-03-15T09:30:00.000001Z".parse().unwrap();
+    let car = |id: i32| car::Model {
+        id,
+        brand: "Arrow".to_owned(),
+        model: format!("Model {}", id),
+        year: 2000 + id,
+        fuel_type: FuelType::Hybrid,
+        purchased_at: None,
+        owner_id: None,
+        price: None,
+        tracker_id: None,
+        specs: None,
+        created_at: time,
+        updated_at: time,
+    };
+    let tracker_id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    let mut rows = vec![car::Model {
+        purchased_at: Some(time),
+        owner_id: Some(7),
+        price: Some(Decimal::new(1250, 2)),
+        tracker_id: Some(tracker_id),
+        specs: Some(json!({"doors": 5})),
+        ..car(0)
+    }];
+    // One more row than a record batch
+    rows.extend((1..=ARROW_BATCH_SIZE as i32).map(car));
+
+    let objs: Vec<Result<Car, DbErr>> = rows.into_iter().map(|row| Ok(Car::from_entity(row))).collect();
+    let chunks: Vec<Result<Vec<u8>, DbErr>> = arrow_file(Box::pin(stream::iter(objs))).collect().await;
+    let file: Vec<u8> = chunks.into_iter().map(Result::unwrap).collect::<Vec<_>>().concat();
+
+    let reader = FileReader::try_new(Cursor::new(file), None).unwrap();
+    assert_eq!(reader.num_batches(), 2);
+    let names: Vec<String> = reader.schema().fields().iter().map(|field| field.name().clone()).collect();
+    assert_eq!(names, ["id", "brand", "model", "year", "fuel_type", "purchased_at", "owner_id", "price", "tracker_id", "specs", "created_at", "updated_at"]);
+
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(batches[0].num_rows(), ARROW_BATCH_SIZE);
+    assert_eq!(batches[1].num_rows(), 1);
+
+    let first = &batches[0];
+    let column = |name: &str| first.column_by_name(name).unwrap();
+    assert_eq!(column("id").as_primitive::<Int32Type>().value(1), 1);
+    assert_eq!(column("year").as_primitive::<Int32Type>().value(1), 2001);
+    assert_eq!(column("model").as_string::<i32>().value(1), "Model 1");
+    assert_eq!(column("fuel_type").as_string::<i32>().value(0), "hybrid");
+    assert_eq!(column("price").as_string::<i32>().value(0), "12.50");
+    assert_eq!(column("tracker_id").as_string::<i32>().value(0), tracker_id.to_string());
+    assert_eq!(column("specs").as_string::<i32>().value(0), r#"{"doors":5}"#);
+    assert_eq!(column("owner_id").as_primitive::<Int32Type>().value(0), 7);
+
+    let purchased_at = column("purchased_at").as_primitive::<TimestampMicrosecondType>();
+    assert_eq!(purchased_at.value(0), time.timestamp_micros());
+    assert_eq!(purchased_at.timezone(), Some("UTC"));
+
+    // The columns of the rows without a value are null
+    for name in ["purchased_at", "owner_id", "price", "tracker_id", "specs"] {
+        assert!(column(name).is_valid(0), "{}", name);
+        assert_eq!(column(name).null_count(), ARROW_BATCH_SIZE - 1, "{}", name);
+    }
+    assert_eq!(batches[1].column_by_name("id").unwrap().as_primitive::<Int32Type>().value(0), ARROW_BATCH_SIZE as i32);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn streamed_list_is_complete() {
+    let client = client().await;
+
+    for resource in ["animals", "cars", "caretakers", "owners"] {
+        let response = client.get(format!("/api/{}/_stream", resource)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok, "{}", resource);
+        let objs: Value = response.into_json().await.unwrap();
+        assert!(objs.is_array(), "{}", resource);
+    }
+
+    let response = client.get("/api/cars/export.ndjson").dispatch().await;
+    let ndjson = response.into_string().await.unwrap();
+    assert!(ndjson.lines().all(|line| !line.starts_with("{\"error\"")));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn exports_are_paginated_like_the_list() {
+    let client = client().await;
+
+    for year in 2001..2005 {
+        let car = json!({"brand": "Paginated", "model": common::unique(), "year": year});
+        client.post("/api/cars").json(&car).dispatch().await;
+    }
+    let ids = |cars: Vec<Value>| cars.iter().map(|car| car["id"].as_i64().unwrap()).collect::<Vec<_>>();
+
+    let cars: Vec<Value> = client.get("/api/cars?limit=3&offset=1").dispatch().await.into_json().await.unwrap();
+    let listed = ids(cars);
+    assert_eq!(listed.len(), 3);
+
+    let ndjson = client.get("/api/cars/export.ndjson?limit=3&offset=1").dispatch().await.into_string().await.unwrap();
+    let cars = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(ids(cars), listed);
+
+    let csv = client.get("/api/cars/export.csv?limit=3&offset=1").dispatch().await.into_string().await.unwrap();
+    // Quoted fields, such as the JSON of the specs, may span several lines
+    let exported: Vec<i64> = csv::Reader::from_reader(csv.as_bytes()).records()
+        .map(|record| record.unwrap()[0].parse().unwrap())
+        .collect();
+    assert_eq!(exported, listed);
+
+    let animals: Vec<Value> = client.get("/api/animals/_stream?limit=1").dispatch().await.into_json().await.unwrap();
+    assert!(animals.len() <= 1);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn invalid_pagination_is_rejected() {
+    let client = client().await;
+
+    for uri in [
+        "/api/cars?limit=-1",
+        "/api/cars?limit=abc",
+        "/api/cars?offset=1.5",
+        "/api/cars/_stream?limit=-1",
+        "/api/cars/export.csv?offset=abc",
+        "/api/cars/export.ndjson?limit=",
+        "/api/cars/export.arrow?limit=-1",
+    ] {
+        let response = client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", uri);
+        assert!(response.into_string().await.unwrap().starts_with("Invalid "), "{}", uri);
+    }
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn exports_are_filtered_like_the_list() {
+    let client = client().await;
+    let model = common::unique();
+    let mut matching = vec![];
+    // Unique by brand, model and year
+    for (brand, year) in [("Filtered", 2011), ("Filtered", 2012), ("Other", 2011)] {
+        let car = json!({"brand": brand, "model": model, "year": year, "fuel_type": if brand == "Filtered" { "diesel" } else { "petrol" }});
+        let car: Value = client.post("/api/cars").json(&car).dispatch().await.into_json().await.unwrap();
+        if year == 2011 {
+            matching.push(car["id"].as_i64().unwrap());
+        }
+    }
+    let filter = format!("filter[model]={}&filter[year]=2011", model);
+
+    let ndjson = client.get(format!("/api/cars/export.ndjson?{}", filter)).dispatch().await.into_string().await.unwrap();
+    let exported: Vec<i64> = ndjson.lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(exported, matching);
+
+    let csv = client.get(format!("/api/cars/export.csv?{}", filter)).dispatch().await.into_string().await.unwrap();
+    let exported: Vec<i64> = csv::Reader::from_reader(csv.as_bytes()).records()
+        .map(|record| record.unwrap()[0].parse().unwrap())
+        .collect();
+    assert_eq!(exported, matching);
+
+    let cars: Vec<Value> = client.get(format!("/api/cars/_stream?{}&filter[fuel_type]=diesel&limit=1", filter)).dispatch().await
+        .into_json().await.unwrap();
+    assert_eq!(cars.len(), 1);
+    assert_eq!(cars[0]["id"], matching[0]);
+
+    for uri in ["/api/cars/export.csv?filter[color]=red", "/api/cars/export.ndjson?ids=1&filter[year]=2011", "/api/cars/export.arrow?filter[year]=recent"] {
+        let response = client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", uri);
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+use migration::helpers::{add_timestamps, drop_timestamps};
+use migration::{Alias, SchemaManager};
+
+use common::{client, database, unique};
+
+fn timestamp(obj: &Value, field: &str) -> DateTime<Utc> {
+    obj[field].as_str().unwrap().parse().unwrap()
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn timestamps_are_set_on_insert() {
+    let client = client().await;
+    let before = Utc::now() - Duration::seconds(1);
+
+    // The timestamps of the body are ignored
+    let response = client.post("/api/cars")
+        .json(&json!({
+            "brand": "Timestamps", "model": unique(), "year": 2020,
+            "created_at": "2000-01-01T00:00:00Z", "updated_at": "2000-01-01T00:00:00Z",
+        }))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let car: Value = response.into_json().await.unwrap();
+
+    let created_at = timestamp(&car, "created_at");
+    assert!(created_at >= before, "{}", created_at);
+    assert!(timestamp(&car, "updated_at") >= created_at);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn update_only_changes_updated_at() {
+    let client = client().await;
+
+    let response = client.post("/api/owners").json(&json!({"name": unique()})).dispatch().await;
+    let owner: Value = response.into_json().await.unwrap();
+
+    let response = client.patch(format!("/api/owners/{}", owner["id"]))
+        .json(&json!({"name": unique(), "created_at": "2000-01-01T00:00:00Z"}))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let updated: Value = response.into_json().await.unwrap();
+
+    assert_eq!(timestamp(&updated, "created_at"), timestamp(&owner, "created_at"));
+    assert!(timestamp(&updated, "updated_at") > timestamp(&owner, "updated_at"));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn helper_adds_timestamps_to_existing_rows() {
+    let db = database().await;
+    let table = format!("timestamps_{}", unique());
+    let sql = |sql: String| Statement::from_string(DbBackend::Postgres, sql);
+
+    db.execute(sql(format!("CREATE TABLE {} (id serial PRIMARY KEY)", table))).await.unwrap();
+    db.execute(sql(format!("INSERT INTO {} DEFAULT VALUES", table))).await.unwrap();
+
+    let manager = SchemaManager::new(&db);
+    add_timestamps(&manager, Alias::new(&table)).await.unwrap();
+    let row = db.query_one(sql(format!("SELECT created_at, updated_at FROM {}", table))).await.unwrap().unwrap();
+    let created_at: DateTime<Utc> = row.try_get("", "created_at").unwrap();
+    let updated_at: DateTime<Utc> = row.try_get("", "updated_at").unwrap();
+    assert_eq!(created_at, updated_at);
+
+    drop_timestamps(&manager, Alias::new(&table)).await.unwrap();
+    assert!(db.query_one(sql(format!("SELECT created_at FROM {}", table))).await.is_err());
+    db.execute(sql(format!("DROP TABLE {}", table))).await.unwrap();
+}
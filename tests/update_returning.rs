@@ -0,0 +1,53 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+use common::{client, database, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn update_only_sets_the_fields_of_the_body() {
+    let client = client().await;
+    let db = database().await;
+
+    let response = client.post("/api/cars").json(&json!({"brand": "Returning", "model": unique(), "year": 2010})).dispatch().await;
+    let car: Value = response.into_json().await.unwrap();
+
+    // A concurrent change of another field is kept
+    let sql = format!("UPDATE car SET year = 2011 WHERE id = {}", car["id"]);
+    db.execute(Statement::from_string(DbBackend::Postgres, sql)).await.unwrap();
+
+    let response = client.patch(format!("/api/cars/{}", car["id"])).json(&json!({"fuel_type": "electric"})).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let updated: Value = response.into_json().await.unwrap();
+    assert_eq!(updated["year"], 2011);
+    assert_eq!(updated["fuel_type"], "electric");
+    assert_eq!(updated["model"], car["model"]);
+
+    // An empty body returns the object as it is
+    let response = client.patch(format!("/api/cars/{}", car["id"])).json(&json!({})).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let unchanged: Value = response.into_json().await.unwrap();
+    assert_eq!(unchanged["year"], 2011);
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn missing_objects_are_not_found() {
+    let client = client().await;
+    let missing = i32::MAX;
+
+    let response = client.patch(format!("/api/cars/{}", missing)).json(&json!({"year": 2000})).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = client.patch(format!("/api/cars/{}", missing)).json(&json!({})).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = client.post("/api/cars").json(&json!({"brand": "Returning", "model": unique(), "year": 2010})).dispatch().await;
+    let car: Value = response.into_json().await.unwrap();
+    let uri = format!("/api/cars/{}", car["id"]);
+    assert_eq!(client.delete(&uri).dispatch().await.status(), Status::NoContent);
+    assert_eq!(client.delete(&uri).dispatch().await.status(), Status::NotFound);
+    assert_eq!(client.get(&uri).dispatch().await.status(), Status::NotFound);
+}
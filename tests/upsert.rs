@@ -0,0 +1,106 @@
+//! The `PUT /by-key` and `PUT /_upsert` routes of the `#[naturalKey(brand, model, year)]` cars.
+
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::serde_json::{json, Value};
+
+use common::{client, unique};
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn upsert_by_key_inserts_then_updates() {
+    let client = client().await;
+    let uri = format!("/api/cars/by-key?brand=Upsert&model={}&year=2020", unique());
+
+    let response = client.put(&uri).json(&json!({"fuel_type": "diesel"})).dispatch().await;
+    assert_eq!(response.status(), Status::Created);
+    let created: Value = response.into_json().await.unwrap();
+
+    let response = client.put(&uri).json(&json!({"fuel_type": "electric"})).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let updated: Value = response.into_json().await.unwrap();
+    assert_eq!((&updated["id"], &updated["fuel_type"]), (&created["id"], &json!("electric")));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn upsert_by_key_requires_the_whole_key() {
+    let client = client().await;
+    let uri = format!("/api/cars/by-key?brand=Upsert&model={}", unique());
+
+    let response = client.put(&uri).json(&json!({"year": 2020})).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[test]
+fn upsert_routes_are_generated_from_the_natural_key() {
+    let rocket = rocket_api::rocket();
+    let routes: Vec<(String, String)> = rocket.routes()
+        .map(|route| (route.method.to_string(), route.uri.path().to_string()))
+        .collect();
+
+    for path in ["/api/cars/by-key", "/api/cars/_upsert"] {
+        assert!(routes.contains(&("PUT".to_owned(), path.to_owned())), "PUT {} missing from {:?}", path, routes);
+    }
+    // The owners have no natural key
+    assert!(!routes.iter().any(|(_method, path)| path.starts_with("/api/owners/by-key")));
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn upsert_many_keeps_the_order_of_the_body() {
+    let client = client().await;
+    let model = unique();
+    let existing: Value = client.post("/api/cars").json(&json!({"brand": "Bulk", "model": model, "year": 2002}))
+        .dispatch().await
+        .into_json().await.unwrap();
+
+    // The rows setting other columns are upserted by another statement
+    let cars = json!([
+        {"brand": "Bulk", "model": model, "year": 2003},
+        {"brand": "Bulk", "model": model, "year": 2002, "fuel_type": "hybrid"},
+        {"brand": "Bulk", "model": model, "year": 2001},
+    ]);
+    let response = client.put("/api/cars/_upsert").json(&cars).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let upserted: Vec<Value> = response.into_json().await.unwrap();
+
+    let years: Vec<&Value> = upserted.iter().map(|car| &car["data"]["year"]).collect();
+    assert_eq!(years, [2003, 2002, 2001]);
+    let inserted: Vec<&Value> = upserted.iter().map(|car| &car["inserted"]).collect();
+    assert_eq!(inserted, [true, false, true]);
+    assert_eq!(upserted[1]["data"]["id"], existing["id"]);
+    assert_eq!(upserted[1]["data"]["fuel_type"], "hybrid");
+}
+
+#[rocket::async_test]
+#[ignore = "requires a database, see the README"]
+async fn upsert_many_rejects_invalid_objects() {
+    let client = client().await;
+    let model = unique();
+
+    let cars = json!([
+        {"brand": "Bulk", "model": model, "year": 2001},
+        {"brand": "Bulk", "model": model, "year": 2002},
+        {"brand": "Bulk", "model": model, "year": 2001, "fuel_type": "diesel"},
+    ]);
+    let response = client.put("/api/cars/_upsert").json(&cars).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["error"], "The objects 0 and 2 have the same natural key");
+
+    let cars = json!([
+        {"brand": "Bulk", "model": model, "year": 2001},
+        {"brand": "Bulk", "model": model, "year": 2002, "owner_id": i32::MAX},
+    ]);
+    let response = client.put("/api/cars/_upsert").json(&cars).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["field"], "owner_id");
+
+    // Nothing was written
+    let response = client.get(format!("/api/cars?filter[model]={}", model)).dispatch().await;
+    let cars: Vec<Value> = response.into_json().await.unwrap();
+    assert!(cars.is_empty());
+}